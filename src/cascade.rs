@@ -0,0 +1,171 @@
+//! Loading the cascade of files for a mode, like Vite, Next.js, Ruby's dotenv
+//! and dotenv-flow do:
+//!
+//! 1. `.env`
+//! 2. `.env.local`
+//! 3. `.env.<mode>`
+//! 4. `.env.<mode>.local`
+//!
+//! Later files take precedence over earlier ones, but none of them replaces an
+//! existing environment variable unless [`Options::override_env`] is set.
+//! Missing files are skipped. In [`TEST_MODE`] `.env.local` isn't loaded, so
+//! tests get the same environment on every machine.
+//!
+//! The cascade is used by [`crate::config_with_options()`] when
+//! [`Options::mode`] is set (`DOTENV_CONFIG_MODE`). The file names are derived
+//! from [`Options::path`] by appending the suffixes.
+
+use std::{borrow::Cow, collections::HashMap, ffi::{OsStr, OsString}, fs::File, io::BufReader, path::{Path, PathBuf}};
+
+use crate::{env::GetEnv, Diagnostic, DiagnosticCode, Env, Error, ErrorKind, Options, Origin, Result};
+
+/// The mode in which `.env.local` is not loaded.
+pub const TEST_MODE: &str = "test";
+
+#[inline]
+fn with_suffix(path: &Path, suffix: &OsStr) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(".");
+    path.push(suffix);
+    path.into()
+}
+
+/// The files of the cascade for `mode` based on `path` (usually `.env`), from
+/// lowest to highest precedence.
+pub fn paths(path: &Path, mode: &OsStr) -> Vec<PathBuf> {
+    let local = OsStr::new("local");
+    let mode_path = with_suffix(path, mode);
+    let mode_local_path = with_suffix(&mode_path, local);
+    let mut paths = vec![path.to_owned()];
+
+    if mode != TEST_MODE {
+        paths.push(with_suffix(path, local));
+    }
+
+    paths.push(mode_path);
+    paths.push(mode_local_path);
+
+    paths
+}
+
+/// The variables of the cascade files read so far. Variables of the
+/// environment shadow them unless `override_env` is set, so substitutions
+/// already see the values that will end up in the environment.
+struct CascadeEnv<'a> {
+    env: &'a dyn GetEnv,
+    override_env: bool,
+    file_index: usize,
+    vars: HashMap<OsString, (OsString, Option<Origin>, usize)>,
+    keys: Vec<OsString>,
+}
+
+impl GetEnv for CascadeEnv<'_> {
+    fn get<'a>(&'a self, key: &OsStr) -> Option<Cow<'a, OsStr>> {
+        if !self.override_env {
+            if let Some(value) = self.env.get(key) {
+                return Some(value);
+            }
+        }
+
+        if let Some((value, _, _)) = self.vars.get(key) {
+            return Some(Cow::from(value.as_os_str()));
+        }
+
+        self.env.get(key)
+    }
+}
+
+impl Env for CascadeEnv<'_> {
+    fn set(&mut self, key: &OsStr, value: &OsStr) {
+        if self.vars.insert(key.to_owned(), (value.to_owned(), None, self.file_index)).is_none() {
+            self.keys.push(key.to_owned());
+        }
+    }
+
+    fn set_from(&mut self, key: &OsStr, value: &OsStr, origin: &Origin) {
+        if self.vars.insert(key.to_owned(), (value.to_owned(), Some(*origin), self.file_index)).is_none() {
+            self.keys.push(key.to_owned());
+        }
+    }
+
+    fn remove(&mut self, key: &OsStr) {
+        if self.vars.remove(key).is_some() {
+            self.keys.retain(|other| other != key);
+        }
+    }
+
+    #[inline]
+    fn as_get_env(&self) -> &dyn GetEnv {
+        self
+    }
+}
+
+pub(crate) fn config_cascade<P>(env: &mut impl Env, parent: &impl GetEnv, options: &Options<P>, mode: &OsStr) -> Result<()>
+where P: AsRef<Path> {
+    let paths = paths(options.path.as_ref(), mode);
+    let file_options: Vec<_> = paths.iter().map(|path| Options {
+        override_env:      options.override_env,
+        strict:            options.strict,
+        debug:             options.debug,
+        encoding:          options.encoding,
+        fallback_encoding: options.fallback_encoding,
+        dialect:           options.dialect,
+        path:              path.as_path(),
+        mode:              None,
+        search:            options.search,
+        include_root:      options.include_root.clone(),
+        diagnostics:       options.diagnostics.clone(),
+        command_policy:    options.command_policy.clone(),
+        key_mapping:       options.key_mapping.clone(),
+    }).collect();
+
+    let mut cascade = CascadeEnv {
+        env: env.as_get_env(),
+        override_env: options.override_env,
+        file_index: 0,
+        vars: HashMap::new(),
+        keys: Vec::new(),
+    };
+
+    for (file_index, options) in file_options.iter().enumerate() {
+        let file = match File::open(options.path) {
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                if options.diagnostics_enabled() {
+                    options.report(Diagnostic::new(DiagnosticCode::FileLookup,
+                        format!("file of mode {mode:?} not found, skipped")));
+                }
+                continue;
+            },
+            Err(err) => {
+                if options.diagnostics_enabled() {
+                    options.report(Diagnostic::new(DiagnosticCode::IOError, err.to_string()));
+                }
+                if options.strict {
+                    return Err(Error::with_cause(ErrorKind::IOError, err));
+                }
+                continue;
+            },
+            Ok(file) => file,
+        };
+
+        // later files replace the values of earlier ones
+        let options = Options { override_env: true, ..options.clone() };
+        cascade.file_index = file_index;
+        crate::config_with_reader(&mut BufReader::new(file), &mut cascade, parent, &options)?;
+    }
+
+    let CascadeEnv { mut vars, keys, .. } = cascade;
+    for key in keys {
+        match vars.remove(&key) {
+            Some((value, Some(origin), file_index)) => {
+                file_options[file_index].set_var(env, &key, &value, origin);
+            },
+            Some((value, None, _)) if options.override_env || env.get(&key).is_none() => {
+                env.set(&key, &value);
+            },
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,281 @@
+use std::io::{BufRead, Read, Write};
+
+use crate::{encoding::Decoded, Encoding};
+
+/// Where [`DecodingReader`] found input that isn't valid in its encoding.
+///
+/// It is the payload of the returned [`std::io::Error`], which is of kind
+/// [`std::io::ErrorKind::InvalidData`]. Use [`DecodeError::from_io()`] to get
+/// it back.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct DecodeError {
+    encoding: Encoding,
+    offset: u64,
+    lineno: usize,
+    column: usize,
+}
+
+impl DecodeError {
+    #[inline]
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Byte offset of the invalid input, counted from the start of the input
+    /// (including any BOM).
+    #[inline]
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// 1-based line number of the invalid input.
+    #[inline]
+    pub fn lineno(&self) -> usize {
+        self.lineno
+    }
+
+    /// 1-based column of the invalid input, counted in bytes like in
+    /// [`crate::Error`].
+    #[inline]
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    #[inline]
+    pub fn from_io(error: &std::io::Error) -> Option<&DecodeError> {
+        error.get_ref()?.downcast_ref()
+    }
+}
+
+impl std::fmt::Display for DecodeError {
+    #[inline]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid {:?} at byte offset {} on line {} at column {}", self.encoding, self.offset, self.lineno, self.column)
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Decodes `reader` from `encoding` and provides the text as UTF-8.
+///
+/// Input is decoded one line at a time and only the bytes of decoded lines
+/// are consumed from `reader`. If a line contains invalid input the whole line
+/// is skipped and an error with a [`DecodeError`] is returned, reading resumes
+/// with the next line. In [lossy](DecodingReader::lossy) mode invalid input is
+/// replaced with U+FFFD instead and the errors are collected.
+///
+/// [`Encoding::Auto`] detects the encoding by the BOM (which is skipped) or
+/// the null byte pattern in the first buffered bytes and otherwise is UTF-8.
+#[derive(Debug)]
+pub struct DecodingReader<R: BufRead> {
+    reader: R,
+    encoding: Encoding,
+    line: String,
+    pos: usize,
+    offset: u64,
+    lineno: usize,
+    lossy: bool,
+    errors: Vec<DecodeError>,
+}
+
+impl<R: BufRead> DecodingReader<R> {
+    #[inline]
+    pub fn new(reader: R, encoding: Encoding) -> Self {
+        Self {
+            reader,
+            encoding,
+            line: String::new(),
+            pos: 0,
+            offset: 0,
+            lineno: 1,
+            lossy: false,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Replace invalid input with U+FFFD instead of returning an error. The
+    /// first error of each line is kept for [`DecodingReader::take_errors()`].
+    #[inline]
+    pub fn lossy(mut self, lossy: bool) -> Self {
+        self.lossy = lossy;
+        self
+    }
+
+    /// The errors collected in lossy mode since the last call.
+    #[inline]
+    pub fn take_errors(&mut self) -> Vec<DecodeError> {
+        std::mem::take(&mut self.errors)
+    }
+
+    /// The encoding, which is [`Encoding::Auto`] only until the first read.
+    #[inline]
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Number of bytes consumed from the underlying reader.
+    #[inline]
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// 1-based line number of the next line to be decoded.
+    #[inline]
+    pub fn lineno(&self) -> usize {
+        self.lineno
+    }
+
+    /// The underlying reader. Decoded text that wasn't read yet is lost.
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn decode_line(&mut self) -> std::io::Result<()> {
+        self.line.clear();
+        self.pos = 0;
+
+        if self.encoding == Encoding::Auto {
+            let (encoding, bom_len) = Encoding::detect(self.reader.fill_buf()?).
+                unwrap_or((Encoding::UTF8, 0));
+            self.reader.consume(bom_len);
+            self.offset += bom_len as u64;
+            self.encoding = encoding;
+        }
+
+        let line_offset = self.offset;
+        let mut error = None;
+        loop {
+            let offset = self.offset;
+            match self.encoding.read_char(&mut self.reader)? {
+                None => break,
+                Some(Decoded::Char(ch, len)) => {
+                    self.offset += len as u64;
+                    self.line.push(ch);
+                    if ch == '\n' {
+                        self.lineno += 1;
+                        break;
+                    }
+                }
+                Some(Decoded::Invalid(len)) => {
+                    self.offset += len as u64;
+                    self.line.push('\u{FFFD}');
+                    if error.is_none() {
+                        error = Some(DecodeError {
+                            encoding: self.encoding,
+                            offset,
+                            lineno: self.lineno,
+                            column: (offset - line_offset) as usize + 1,
+                        });
+                    }
+                }
+            }
+        }
+
+        if let Some(error) = error {
+            if self.lossy {
+                self.errors.push(error);
+            } else {
+                self.line.clear();
+                return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, error));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Read for DecodingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let src = self.fill_buf()?;
+        let len = src.len().min(buf.len());
+        buf[..len].copy_from_slice(&src[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl<R: BufRead> BufRead for DecodingReader<R> {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        if self.pos >= self.line.len() {
+            self.decode_line()?;
+        }
+        Ok(&self.line.as_bytes()[self.pos..])
+    }
+
+    #[inline]
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.line.len());
+    }
+}
+
+/// Encodes UTF-8 written to it in `encoding` and writes it to `writer`.
+///
+/// Writing invalid UTF-8 fails with [`std::io::ErrorKind::InvalidInput`] and
+/// characters that can't be represented in `encoding` fail with
+/// [`std::io::ErrorKind::InvalidData`]. Nothing of such a write is passed on.
+/// No BOM is written and [`Encoding::Auto`] writes UTF-8.
+#[derive(Debug)]
+pub struct EncodingWriter<W: Write> {
+    writer: W,
+    encoding: Encoding,
+    /// An incomplete UTF-8 sequence at the end of the last write.
+    pending: Vec<u8>,
+}
+
+impl<W: Write> EncodingWriter<W> {
+    #[inline]
+    pub fn new(writer: W, encoding: Encoding) -> Self {
+        Self { writer, encoding, pending: Vec::new() }
+    }
+
+    #[inline]
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// The underlying writer. An incomplete UTF-8 sequence that is still
+    /// pending is lost.
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> Write for EncodingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        // the pending bytes are only replaced once the write succeeded
+        let mut input = Vec::with_capacity(self.pending.len() + buf.len());
+        input.extend_from_slice(&self.pending);
+        input.extend_from_slice(buf);
+
+        let valid_len = match std::str::from_utf8(&input) {
+            Ok(_) => input.len(),
+            Err(err) if err.error_len().is_none() => err.valid_up_to(),
+            Err(_) => return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "stream did not contain valid UTF-8")),
+        };
+        let src = std::str::from_utf8(&input[..valid_len]).unwrap_or_default();
+
+        let mut out = Vec::with_capacity(src.len());
+        for ch in src.chars() {
+            if !self.encoding.encode_char(ch, &mut out) {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("character {ch:?} can't be encoded in {:?}", self.encoding)));
+            }
+        }
+
+        self.writer.write_all(&out)?;
+        self.pending.clear();
+        self.pending.extend_from_slice(&input[valid_len..]);
+
+        Ok(buf.len())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+}
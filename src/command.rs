@@ -0,0 +1,65 @@
+use std::sync::Arc;
+
+/// Runs the command of a `$(command)` substitution on behalf of a dialect.
+///
+/// Implemented for closures. Punktum itself never runs commands, so this is
+/// the only way to get command substitution.
+pub trait CommandHook: Send + Sync {
+    /// Returns what the command wrote to its standard output.
+    fn run(&self, command: &str) -> std::io::Result<String>;
+}
+
+impl<F> CommandHook for F
+where F: Fn(&str) -> std::io::Result<String> + Send + Sync {
+    #[inline]
+    fn run(&self, command: &str) -> std::io::Result<String> {
+        self(command)
+    }
+}
+
+/// What dialects that support command substitution (like
+/// [`crate::Dialect::Dotenvx`]) do with `$(command)`.
+///
+/// Two instances are equal if they are the same variant and, for hooks, refer
+/// to the same hook.
+#[derive(Clone, Default)]
+pub enum CommandPolicy {
+    /// Treat it as an error. In non-strict mode this is reported as a warning
+    /// and `$(command)` is kept as is.
+    #[default]
+    Error,
+
+    /// Keep `$(command)` as is.
+    Keep,
+
+    /// Pass the command to a caller-supplied hook.
+    Hook(Arc<dyn CommandHook>),
+}
+
+impl CommandPolicy {
+    #[inline]
+    pub fn hook(hook: impl CommandHook + 'static) -> Self {
+        CommandPolicy::Hook(Arc::new(hook))
+    }
+}
+
+impl PartialEq for CommandPolicy {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (CommandPolicy::Error, CommandPolicy::Error) => true,
+            (CommandPolicy::Keep,  CommandPolicy::Keep)  => true,
+            (CommandPolicy::Hook(lhs), CommandPolicy::Hook(rhs)) => Arc::ptr_eq(lhs, rhs),
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Debug for CommandPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommandPolicy::Error   => f.write_str("Error"),
+            CommandPolicy::Keep    => f.write_str("Keep"),
+            CommandPolicy::Hook(_) => f.write_str("Hook(..)"),
+        }
+    }
+}
@@ -0,0 +1,339 @@
+use std::{path::{Path, PathBuf}, sync::{Arc, Mutex}};
+
+use crate::error::{Include, SourceLocation};
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Severity::Info    => "info",
+            Severity::Warning => "warning",
+            Severity::Error   => "error",
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum DiagnosticCode {
+    /// The file couldn't be opened or read.
+    IOError,
+    /// The file isn't valid in the configured encoding.
+    EncodingError,
+    SyntaxError,
+    UnterminatedString,
+    IllegalEscape,
+    IllegalNullByte,
+    SubstitutionError,
+    /// A `$(command)` substitution wasn't run, see [`crate::CommandPolicy`].
+    CommandSubstitution,
+    /// A variable was already defined and override wasn't enabled.
+    NotOverwritten,
+    /// Which dialect [`crate::Dialect::Auto`] chose, or why it didn't.
+    DialectDetection,
+    /// A modeline was applied or is invalid, see [`crate::modeline`].
+    Modeline,
+    /// Which encoding [`crate::Encoding::Auto`] chose.
+    EncodingDetection,
+    /// Which file [`crate::Search::Upward`] found, or a file of the
+    /// [mode cascade](crate::cascade) that doesn't exist and was skipped.
+    FileLookup,
+    /// An `include` statement failed, e.g. because of a cycle.
+    IncludeError,
+}
+
+impl DiagnosticCode {
+    /// Short stable identifier, e.g. `"syntax-error"`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DiagnosticCode::IOError             => "io-error",
+            DiagnosticCode::EncodingError       => "encoding-error",
+            DiagnosticCode::SyntaxError         => "syntax-error",
+            DiagnosticCode::UnterminatedString  => "unterminated-string",
+            DiagnosticCode::IllegalEscape       => "illegal-escape",
+            DiagnosticCode::IllegalNullByte     => "illegal-null-byte",
+            DiagnosticCode::SubstitutionError   => "substitution-error",
+            DiagnosticCode::CommandSubstitution => "command-substitution",
+            DiagnosticCode::NotOverwritten      => "not-overwritten",
+            DiagnosticCode::DialectDetection    => "dialect-detection",
+            DiagnosticCode::Modeline            => "modeline",
+            DiagnosticCode::EncodingDetection   => "encoding-detection",
+            DiagnosticCode::FileLookup          => "file-lookup",
+            DiagnosticCode::IncludeError        => "include-error",
+        }
+    }
+
+    #[inline]
+    pub fn default_severity(&self) -> Severity {
+        match self {
+            DiagnosticCode::NotOverwritten |
+            DiagnosticCode::DialectDetection |
+            DiagnosticCode::Modeline |
+            DiagnosticCode::EncodingDetection |
+            DiagnosticCode::FileLookup => Severity::Info,
+            _ => Severity::Error,
+        }
+    }
+}
+
+impl From<&std::io::Error> for DiagnosticCode {
+    #[inline]
+    fn from(error: &std::io::Error) -> Self {
+        if error.kind() == std::io::ErrorKind::InvalidData {
+            DiagnosticCode::EncodingError
+        } else {
+            DiagnosticCode::IOError
+        }
+    }
+}
+
+impl std::fmt::Display for DiagnosticCode {
+    #[inline]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A problem (or note) found while reading a file.
+///
+/// Line and column numbers are 1-based, columns count bytes like in
+/// [`crate::Error`].
+#[derive(Debug, PartialEq, Clone)]
+pub struct Diagnostic {
+    severity: Severity,
+    code: DiagnosticCode,
+    message: String,
+    path: Option<PathBuf>,
+    lineno: Option<usize>,
+    column: Option<usize>,
+    end: Option<SourceLocation>,
+    include_chain: Vec<Include>,
+}
+
+impl Diagnostic {
+    #[inline]
+    pub fn new(code: DiagnosticCode, message: impl Into<String>) -> Self {
+        Self {
+            severity: code.default_severity(),
+            code,
+            message: message.into(),
+            path: None,
+            lineno: None,
+            column: None,
+            end: None,
+            include_chain: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+
+    #[inline]
+    pub fn with_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    #[inline]
+    pub fn at(mut self, lineno: usize, column: usize) -> Self {
+        self.lineno = Some(lineno);
+        self.column = Some(column);
+        self
+    }
+
+    #[inline]
+    pub fn at_line(mut self, lineno: usize) -> Self {
+        self.lineno = Some(lineno);
+        self
+    }
+
+    /// End of the offending source range (exclusive).
+    #[inline]
+    pub fn until(mut self, lineno: usize, column: usize) -> Self {
+        self.end = Some(SourceLocation::new(lineno, column));
+        self
+    }
+
+    #[inline]
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    #[inline]
+    pub fn code(&self) -> DiagnosticCode {
+        self.code
+    }
+
+    #[inline]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    #[inline]
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    #[inline]
+    pub fn lineno(&self) -> Option<usize> {
+        self.lineno
+    }
+
+    #[inline]
+    pub fn column(&self) -> Option<usize> {
+        self.column
+    }
+
+    /// Start of the offending source range, if both line and column are known.
+    #[inline]
+    pub fn location(&self) -> Option<SourceLocation> {
+        Some(SourceLocation::new(self.lineno?, self.column?))
+    }
+
+    #[inline]
+    pub fn end(&self) -> Option<SourceLocation> {
+        self.end
+    }
+
+    /// Like [`crate::Error::include_chain()`], if the diagnostic is about an
+    /// included file.
+    #[inline]
+    pub fn include_chain(&self) -> &[Include] {
+        &self.include_chain
+    }
+
+    #[inline]
+    pub(crate) fn included(mut self, include: Include) -> Self {
+        self.include_chain.push(include);
+        self
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    /// `path:line:column: message`, leaving out what isn't known.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(path) = &self.path {
+            write!(f, "{}:", path.to_string_lossy())?;
+            if let Some(lineno) = self.lineno {
+                write!(f, "{lineno}:")?;
+                if let Some(column) = self.column {
+                    write!(f, "{column}:")?;
+                }
+            }
+            f.write_str(" ")?;
+        } else if let Some(lineno) = self.lineno {
+            write!(f, "line {lineno}")?;
+            if let Some(column) = self.column {
+                write!(f, " column {column}")?;
+            }
+            f.write_str(": ")?;
+        }
+        f.write_str(&self.message)?;
+
+        for include in &self.include_chain {
+            write!(f, " (in {}, included on line {} at column {})",
+                include.path().display(), include.location().lineno(), include.location().column())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Receives the diagnostics of a `config_*` call.
+///
+/// Implemented for closures, for `Mutex<Vec<Diagnostic>>` to simply collect
+/// everything, and for `Arc`s of sinks so you can keep a handle to it.
+pub trait DiagnosticSink: Send + Sync {
+    fn report(&self, diagnostic: Diagnostic);
+}
+
+impl<F> DiagnosticSink for F
+where F: Fn(Diagnostic) + Send + Sync {
+    #[inline]
+    fn report(&self, diagnostic: Diagnostic) {
+        self(diagnostic)
+    }
+}
+
+impl DiagnosticSink for Mutex<Vec<Diagnostic>> {
+    #[inline]
+    fn report(&self, diagnostic: Diagnostic) {
+        // a poisoned list is still a list
+        let mut diagnostics = match self.lock() {
+            Ok(diagnostics) => diagnostics,
+            Err(err) => err.into_inner(),
+        };
+        diagnostics.push(diagnostic);
+    }
+}
+
+impl<T> DiagnosticSink for Arc<T>
+where T: DiagnosticSink + ?Sized {
+    #[inline]
+    fn report(&self, diagnostic: Diagnostic) {
+        T::report(self, diagnostic)
+    }
+}
+
+/// Optional shared [`DiagnosticSink`] as stored in [`crate::Options`].
+///
+/// Two instances are equal if they refer to the same sink.
+#[derive(Clone, Default)]
+pub struct Diagnostics(Option<Arc<dyn DiagnosticSink>>);
+
+impl Diagnostics {
+    #[inline]
+    pub fn new(sink: impl DiagnosticSink + 'static) -> Self {
+        Self(Some(Arc::new(sink)))
+    }
+
+    #[inline]
+    pub fn none() -> Self {
+        Self(None)
+    }
+
+    #[inline]
+    pub fn is_some(&self) -> bool {
+        self.0.is_some()
+    }
+
+    #[inline]
+    pub fn is_none(&self) -> bool {
+        self.0.is_none()
+    }
+
+    #[inline]
+    pub fn report(&self, diagnostic: Diagnostic) {
+        if let Some(sink) = &self.0 {
+            sink.report(diagnostic);
+        }
+    }
+}
+
+impl std::fmt::Debug for Diagnostics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.0.is_some() {
+            f.write_str("Diagnostics(Some(..))")
+        } else {
+            f.write_str("Diagnostics(None)")
+        }
+    }
+}
+
+impl PartialEq for Diagnostics {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (Some(lhs), Some(rhs)) => Arc::ptr_eq(lhs, rhs),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
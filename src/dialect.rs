@@ -1,17 +1,35 @@
-use std::ffi::OsStr;
+use std::{ffi::OsStr, io::BufRead, path::Path, sync::{RwLock, RwLockReadGuard}};
+
+use crate::{dialects, env::GetEnv, Env, Error, ErrorKind, Options};
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum Dialect {
     Punktum,
     NodeJS,
+    NodeJS20,
+    NodeJS24,
     JavaScriptDotenv,
+    DotenvExpand,
+    Dotenvx,
     PythonDotenv,
     PythonDotenvCLI,
     ComposeGo,
+    ComposeRaw,
+    DockerCLI,
     GoDotenv,
     RubyDotenv,
+    PhpDotenv,
     JavaDotenv,
+    JavaProperties,
+    Dotenvy,
+    Systemd,
+    PosixShell,
     Binary,
+    /// Not a dialect of its own: guesses the dialect from the file's content,
+    /// see [`crate::dialects::auto::detect()`].
+    Auto,
+    /// A dialect registered with [`register_dialect()`].
+    Custom(&'static dyn DialectParser),
 }
 
 impl Default for Dialect {
@@ -21,10 +39,53 @@ impl Default for Dialect {
     }
 }
 
+impl Dialect {
+    /// The parser implementing this dialect.
+    pub fn parser(&self) -> &'static dyn DialectParser {
+        match self {
+            Dialect::Punktum          => &dialects::PUNKTUM,
+            Dialect::NodeJS           => &dialects::NODEJS,
+            Dialect::NodeJS20         => &dialects::NODEJS20,
+            Dialect::NodeJS24         => &dialects::NODEJS24,
+            Dialect::JavaScriptDotenv => &dialects::JAVASCRIPT_DOTENV,
+            Dialect::DotenvExpand     => &dialects::DOTENV_EXPAND,
+            Dialect::Dotenvx          => &dialects::DOTENVX,
+            Dialect::PythonDotenv     => &dialects::PYTHON_DOTENV,
+            Dialect::PythonDotenvCLI  => &dialects::PYTHON_DOTENV_CLI,
+            Dialect::ComposeGo        => &dialects::COMPOSEGO,
+            Dialect::ComposeRaw       => &dialects::COMPOSE_RAW,
+            Dialect::DockerCLI        => &dialects::DOCKER_CLI,
+            Dialect::GoDotenv         => &dialects::GO_DOTENV,
+            Dialect::RubyDotenv       => &dialects::RUBY_DOTENV,
+            Dialect::PhpDotenv        => &dialects::PHP_DOTENV,
+            Dialect::JavaDotenv       => &dialects::JAVA_DOTENV,
+            Dialect::JavaProperties   => &dialects::JAVA_PROPERTIES,
+            Dialect::Dotenvy          => &dialects::DOTENVY,
+            Dialect::Systemd          => &dialects::SYSTEMD,
+            Dialect::PosixShell       => &dialects::POSIX_SHELL,
+            Dialect::Binary           => &dialects::BINARY,
+            Dialect::Auto             => &dialects::AUTO,
+            Dialect::Custom(parser)   => *parser,
+        }
+    }
+
+    /// The built-in dialects followed by all registered dialects.
+    /// [`Dialect::Auto`] isn't included.
+    pub fn all() -> Vec<Dialect> {
+        let mut all = BUILTIN_DIALECTS.to_vec();
+        all.extend(registry().iter().map(|parser| Dialect::Custom(*parser)));
+        all
+    }
+}
+
 impl std::fmt::Display for Dialect {
     #[inline]
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        std::fmt::Debug::fmt(&self, f)
+        if let Dialect::Custom(parser) = self {
+            f.write_str(parser.name())
+        } else {
+            std::fmt::Debug::fmt(&self, f)
+        }
     }
 }
 
@@ -32,44 +93,129 @@ impl TryFrom<&OsStr> for Dialect {
     type Error = IllegalDialect;
 
     fn try_from(value: &OsStr) -> Result<Self, Self::Error> {
-        if value.is_empty() ||
-           value.eq_ignore_ascii_case("punktum") {
-            Ok(Dialect::Punktum)
-        } else if value.eq_ignore_ascii_case("nodejs") {
-            Ok(Dialect::NodeJS)
-        } else if value.eq_ignore_ascii_case("javascriptdotenv") ||
-                  value.eq_ignore_ascii_case("jsdotenv") ||
-                  value.eq_ignore_ascii_case("javascript-dotenv") ||
-                  value.eq_ignore_ascii_case("js-dotenv") {
-            Ok(Dialect::JavaScriptDotenv)
-        } else if value.eq_ignore_ascii_case("pythondotenvcli") ||
-                  value.eq_ignore_ascii_case("pydotenvcli") ||
-                  value.eq_ignore_ascii_case("python-dotenv-cli") ||
-                  value.eq_ignore_ascii_case("py-dotenv-cli") {
-            Ok(Dialect::PythonDotenvCLI)
-        } else if value.eq_ignore_ascii_case("pythondotenv") ||
-                  value.eq_ignore_ascii_case("pydotenv") ||
-                  value.eq_ignore_ascii_case("python-dotenv") ||
-                  value.eq_ignore_ascii_case("py-dotenv") {
-            Ok(Dialect::PythonDotenv)
-        } else if value.eq_ignore_ascii_case("composego") ||
-                  value.eq_ignore_ascii_case("compose-go") {
-            Ok(Dialect::ComposeGo)
-        } else if value.eq_ignore_ascii_case("godotenv") ||
-                  value.eq_ignore_ascii_case("go-dotenv") {
-            Ok(Dialect::GoDotenv)
-        } else if value.eq_ignore_ascii_case("rubydotenv") ||
-                  value.eq_ignore_ascii_case("ruby-dotenv") {
-            Ok(Dialect::RubyDotenv)
-        } else if value.eq_ignore_ascii_case("javadotenv") ||
-                  value.eq_ignore_ascii_case("java-dotenv") {
-            Ok(Dialect::JavaDotenv)
-        } else if value.eq_ignore_ascii_case("binary") {
-            Ok(Dialect::Binary)
-        } else {
-            Err(IllegalDialect())
+        if value.is_empty() {
+            return Ok(Dialect::Punktum);
+        }
+
+        for dialect in BUILTIN_DIALECTS.iter().chain([&Dialect::Auto]) {
+            if dialect.parser().matches(value) {
+                return Ok(*dialect);
+            }
         }
+
+        for parser in registry().iter() {
+            if parser.matches(value) {
+                return Ok(Dialect::Custom(*parser));
+            }
+        }
+
+        Err(IllegalDialect())
+    }
+}
+
+const BUILTIN_DIALECTS: &[Dialect] = &[
+    Dialect::Punktum,
+    Dialect::NodeJS,
+    Dialect::NodeJS20,
+    Dialect::NodeJS24,
+    Dialect::JavaScriptDotenv,
+    Dialect::DotenvExpand,
+    Dialect::Dotenvx,
+    Dialect::PythonDotenv,
+    Dialect::PythonDotenvCLI,
+    Dialect::ComposeGo,
+    Dialect::ComposeRaw,
+    Dialect::DockerCLI,
+    Dialect::GoDotenv,
+    Dialect::RubyDotenv,
+    Dialect::PhpDotenv,
+    Dialect::JavaDotenv,
+    Dialect::JavaProperties,
+    Dialect::Dotenvy,
+    Dialect::Systemd,
+    Dialect::PosixShell,
+    Dialect::Binary,
+];
+
+/// A parser for a dotenv dialect.
+///
+/// Implement this to support your own file format and make it available by
+/// name with [`register_dialect()`]. The parser should respect the settings
+/// in `options` like the built-in ones do: set variables with the
+/// `override_env` semantics, only return syntax errors if `strict` is set,
+/// decode input with `encoding` (e.g. with [`Options::read_to_string()`]) and
+/// report problems to the diagnostics.
+pub trait DialectParser: Send + Sync {
+    /// Name as used in `DOTENV_CONFIG_DIALECT` and `--dialect`.
+    fn name(&self) -> &str;
+
+    /// Alternative names. Names are matched ignoring ASCII case.
+    #[inline]
+    fn aliases(&self) -> &[&str] {
+        &[]
     }
+
+    /// Parse `reader` and set the found variables in `env`. `parent` is the
+    /// environment to import variables from, if the dialect supports that.
+    fn parse(&self, reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dyn GetEnv, options: &Options<&Path>) -> crate::Result<()>;
+}
+
+impl dyn DialectParser {
+    fn matches(&self, name: &OsStr) -> bool {
+        name.eq_ignore_ascii_case(self.name()) ||
+        self.aliases().iter().any(|alias| name.eq_ignore_ascii_case(alias))
+    }
+
+    fn names(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.name()).chain(self.aliases().iter().copied())
+    }
+}
+
+impl std::fmt::Debug for dyn DialectParser {
+    #[inline]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("DialectParser").field(&self.name()).finish()
+    }
+}
+
+impl PartialEq for dyn DialectParser {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.name() == other.name()
+    }
+}
+
+static REGISTRY: RwLock<Vec<&'static dyn DialectParser>> = RwLock::new(Vec::new());
+
+#[inline]
+fn registry() -> RwLockReadGuard<'static, Vec<&'static dyn DialectParser>> {
+    // the registry is only ever pushed to, so a poisoned lock is still fine
+    REGISTRY.read().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Make `parser` available as [`Dialect::Custom`] through
+/// [`Dialect::try_from()`], `DOTENV_CONFIG_DIALECT` and `--dialect`.
+///
+/// Fails if its name or one of its aliases is already taken. Use
+/// [`Box::leak()`] if your parser isn't a `static`.
+pub fn register_dialect(parser: &'static dyn DialectParser) -> crate::Result<Dialect> {
+    let mut registry = REGISTRY.write().unwrap_or_else(|err| err.into_inner());
+
+    for name in parser.names() {
+        let taken = name.is_empty() ||
+            BUILTIN_DIALECTS.iter().chain([&Dialect::Auto]).any(|dialect| dialect.parser().matches(name.as_ref())) ||
+            registry.iter().any(|other| other.matches(name.as_ref()));
+
+        if taken {
+            return Err(Error::with_cause(
+                ErrorKind::IllegalArgument,
+                format!("dialect name is already taken: {name:?}")));
+        }
+    }
+
+    registry.push(parser);
+
+    Ok(Dialect::Custom(parser))
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -0,0 +1,214 @@
+use std::{ffi::OsString, io::BufRead, path::Path};
+
+use crate::{diagnostic::{Diagnostics, Severity}, env::{EmptyEnv, GetEnv}, CommandPolicy, Diagnostic, DiagnosticCode, Dialect, Env, Error, ErrorKind, Options, Result};
+
+/// Dialects [`Dialect::Auto`] chooses from, in order of preference.
+const CANDIDATES: &[Dialect] = &[
+    Dialect::Punktum,
+    Dialect::JavaScriptDotenv,
+    Dialect::NodeJS,
+    Dialect::JavaProperties,
+    Dialect::Binary,
+];
+
+/// The outcome of [`detect()`].
+#[derive(Debug, PartialEq, Clone)]
+pub enum Detection {
+    /// `dialect` was chosen. `confidence` is the percentage of lines with
+    /// dialect specific constructs that are understood by it.
+    Detected { dialect: Dialect, confidence: u32 },
+
+    /// Nothing dialect specific was found, so the default dialect is used.
+    Default,
+
+    /// The candidates (at least two) are equally likely, but read the file
+    /// differently.
+    Ambiguous { candidates: Vec<Dialect> },
+}
+
+pub fn config_auto(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dyn GetEnv, options: &Options<&Path>) -> Result<()> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+
+    let dialect = match detect(&bytes, options) {
+        Detection::Detected { dialect, confidence } => {
+            if options.diagnostics_enabled() {
+                options.report(Diagnostic::new(DiagnosticCode::DialectDetection,
+                    format!("detected dialect {dialect} (confidence: {confidence}%)")));
+            }
+            dialect
+        }
+        Detection::Default => {
+            if options.diagnostics_enabled() {
+                options.report(Diagnostic::new(DiagnosticCode::DialectDetection,
+                    format!("no dialect specific constructs found, using {}", Dialect::default())));
+            }
+            Dialect::default()
+        }
+        Detection::Ambiguous { candidates } => {
+            let names = candidates.iter().map(Dialect::to_string).collect::<Vec<_>>().join(", ");
+            // the candidates are in order of preference
+            let dialect = candidates[0];
+            let message = if options.strict {
+                format!("refusing to guess the dialect, these would read the file differently: {names}")
+            } else {
+                format!("guessing the dialect {dialect}, but these would read the file differently: {names}")
+            };
+            if options.diagnostics_enabled() {
+                options.report(Diagnostic::new(DiagnosticCode::DialectDetection, message.clone()).
+                    with_severity(Severity::Error));
+            }
+            if options.strict {
+                return Err(Error::with_cause(ErrorKind::AmbiguousDialect, message));
+            }
+            dialect
+        }
+    };
+
+    let options = Options { dialect, ..options.clone() };
+    dialect.parser().parse(&mut bytes.as_slice(), env, parent, &options)
+}
+
+/// Guess the dialect of `bytes`. Only the encoding of `options` is used.
+pub fn detect<P>(bytes: &[u8], options: &Options<P>) -> Detection
+where P: AsRef<Path> {
+    let mut src = String::new();
+    if options.encoding.read_to_string(&mut &bytes[..], &mut src).is_err() {
+        // the parser will report that
+        src = String::from_utf8_lossy(bytes).into_owned();
+    }
+
+    if src.contains('\0') {
+        return Detection::Detected { dialect: Dialect::Binary, confidence: 100 };
+    }
+
+    let mut scores = [0u32; CANDIDATES.len()];
+    let mut evidence = 0;
+
+    for line in src.lines() {
+        let supported = line_features(line);
+        if supported.is_empty() {
+            continue;
+        }
+        evidence += 1;
+        for (dialect, score) in CANDIDATES.iter().zip(scores.iter_mut()) {
+            if supported.contains(dialect) {
+                *score += 1;
+            }
+        }
+    }
+
+    let best = scores.iter().copied().max().unwrap_or(0);
+    if best == 0 {
+        return Detection::Default;
+    }
+    let confidence = best * 100 / evidence;
+
+    let top: Vec<Dialect> = CANDIDATES.iter().zip(scores).
+        filter(|(_, score)| *score == best).
+        map(|(dialect, _)| *dialect).
+        collect();
+
+    if let [dialect] = top[..] {
+        return Detection::Detected { dialect, confidence };
+    }
+
+    // Equally likely candidates are fine if they agree on what the file means.
+    // Ones that can't parse the file at all are out.
+    let mut results: Vec<(Dialect, Vec<(OsString, OsString)>)> = Vec::new();
+    for dialect in &top {
+        if let Some(vars) = trial_parse(bytes, *dialect, options) {
+            results.push((*dialect, vars));
+        }
+    }
+
+    match &results[..] {
+        [] => Detection::Detected { dialect: top[0], confidence },
+        [(dialect, vars), rest @ ..] => {
+            if rest.iter().all(|(_, other)| other == vars) {
+                Detection::Detected { dialect: *dialect, confidence }
+            } else {
+                Detection::Ambiguous { candidates: results.into_iter().map(|(dialect, _)| dialect).collect() }
+            }
+        }
+    }
+}
+
+/// The candidates that understand the construct in `line`, or nothing if the
+/// line isn't telling.
+fn line_features(line: &str) -> Vec<Dialect> {
+    let line = line.trim_start();
+
+    if line.is_empty() || line.starts_with('#') {
+        return Vec::new();
+    }
+
+    if line.starts_with('!') {
+        return vec![Dialect::JavaProperties];
+    }
+
+    let line = line.strip_prefix("export ").map(str::trim_start).unwrap_or(line);
+    let key_end = line.find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_' || ch == '.' || ch == '-')).unwrap_or(line.len());
+    let key = &line[..key_end];
+    let tail = &line[key_end..];
+
+    let mut supported = Vec::new();
+
+    if tail.starts_with(':') {
+        // KEY: value
+        supported.push(Dialect::JavaScriptDotenv);
+        supported.push(Dialect::JavaProperties);
+    } else if !tail.trim_start().starts_with('=') {
+        // key value
+        supported.push(Dialect::JavaProperties);
+        return supported;
+    }
+
+    if key.contains(['.', '-']) {
+        for dialect in [Dialect::JavaScriptDotenv, Dialect::NodeJS, Dialect::JavaProperties] {
+            if dialect == Dialect::NodeJS && tail.starts_with(':') {
+                continue;
+            }
+            if !supported.contains(&dialect) {
+                supported.push(dialect);
+            }
+        }
+    }
+
+    let value = tail.trim_start()[1..].trim_start();
+    if value.starts_with('`') {
+        supported.retain(|dialect| *dialect != Dialect::JavaProperties);
+        for dialect in [Dialect::JavaScriptDotenv, Dialect::NodeJS] {
+            if !supported.contains(&dialect) {
+                supported.push(dialect);
+            }
+        }
+    }
+
+    supported
+}
+
+/// Variables in file order, or `None` if `dialect` can't parse the file.
+fn trial_parse<P>(bytes: &[u8], dialect: Dialect, options: &Options<P>) -> Option<Vec<(OsString, OsString)>>
+where P: AsRef<Path> {
+    let options = Options {
+        override_env:      true,
+        strict:            true,
+        debug:             false,
+        encoding:          options.encoding,
+        fallback_encoding: options.fallback_encoding,
+        dialect,
+        path:              options.path.as_ref(),
+        mode:              options.mode.clone(),
+        search:            options.search,
+        include_root:      options.include_root.clone(),
+        diagnostics:       Diagnostics::none(),
+        command_policy:    CommandPolicy::Keep,
+        key_mapping:       options.key_mapping.clone(),
+    };
+
+    crate::parse_with_parent(bytes, &EmptyEnv(), &options).
+        map(|entry| entry.map(|entry| entry.into_key_value())).
+        collect::<Result<Vec<_>>>().
+        ok()
+}
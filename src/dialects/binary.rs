@@ -1,94 +1,100 @@
 use std::{io::BufRead, path::Path};
 
-use crate::{Env, Error, Options, Result, DEBUG_PREFIX};
+use crate::{env::os_str_from_bytes, Diagnostic, DiagnosticCode, Env, Error, Options, Origin, Result};
 
 pub fn config_binary(reader: &mut dyn BufRead, env: &mut dyn Env, options: &Options<&Path>) -> Result<()> {
-    let path_str = options.path.to_string_lossy();
+    let mut records = Records::default();
+    while records.parse_next(reader, env, options)? {}
+    Ok(())
+}
+
+/// The state of reading a file one record at a time, see
+/// [`super::punktum::Statements`].
+#[derive(Debug, Default)]
+pub(crate) struct Records {
+    lineno: usize,
+    buf: Vec<u8>,
+}
 
-    let mut buf = Vec::new();
-    let mut lineno = 0;
-    loop {
-        lineno += 1;
-        buf.clear();
-        let byte_count = reader.read_until(b'\0', &mut buf)?;
+impl Records {
+    /// Read the next record. Returns `false` at the end of the input.
+    pub(crate) fn parse_next(&mut self, reader: &mut dyn BufRead, env: &mut dyn Env, options: &Options<&Path>) -> Result<bool> {
+        self.lineno += 1;
+        self.buf.clear();
+        let byte_count = reader.read_until(b'\0', &mut self.buf)?;
 
         if byte_count == 0 {
-            break;
+            return Ok(false);
         }
 
-        let line = if buf.ends_with(b"\0") {
-            &buf[..buf.len() - 1]
+        let line = if self.buf.ends_with(b"\0") {
+            &self.buf[..self.buf.len() - 1]
         } else {
             let column = byte_count + 1;
-            if options.debug {
-                eprintln!("{DEBUG_PREFIX}{path_str}:{lineno}:{column}: line isn't terminated with a null byte");
+            if options.diagnostics_enabled() {
+                options.report(Diagnostic::new(DiagnosticCode::SyntaxError, "line isn't terminated with a null byte").at(self.lineno, column));
             }
 
             if options.strict {
-                return Err(Error::syntax_error(lineno, column));
+                return Err(Error::syntax_error(self.lineno, column));
             }
-            &buf
+            &self.buf
         };
 
         let Some(equals) = line.iter().cloned().position(|byte| byte == b'=') else {
             let column = byte_count + 1;
-            if options.debug {
-                eprintln!("{DEBUG_PREFIX}{path_str}:{lineno}:{column}: expected '='");
+            if options.diagnostics_enabled() {
+                options.report(Diagnostic::new(DiagnosticCode::SyntaxError, "expected '='").at(self.lineno, column));
             }
 
             if options.strict {
-                return Err(Error::syntax_error(lineno, column));
+                return Err(Error::syntax_error(self.lineno, column));
             }
-            continue;
+            return Ok(true);
         };
 
         let key = &line[..equals];
         let value = &line[equals + 1..];
 
-        let key = match String::from_utf8(key.to_vec()) {
-            Ok(key) => key,
-            Err(err) => {
-                let column = 1;
-                if options.debug {
-                    eprintln!("{DEBUG_PREFIX}{path_str}:{lineno}:{column}: error decoding key: {err}");
-                }
-
-                if options.strict {
-                    return Err(Error::syntax_error(lineno, column));
-                }
-                continue;
+        // on Unix any bytes are taken as is
+        let Some(key) = os_str_from_bytes(key) else {
+            let column = 1;
+            if options.diagnostics_enabled() {
+                options.report(Diagnostic::new(DiagnosticCode::EncodingError, "error decoding key: invalid UTF-8").at(self.lineno, column));
+            }
+
+            if options.strict {
+                return Err(Error::syntax_error(self.lineno, column));
             }
+            return Ok(true);
         };
 
-        let value = match String::from_utf8(value.to_vec()) {
-            Ok(value) => value,
-            Err(err) => {
-                let column = equals + 2;
-                if options.debug {
-                    eprintln!("{DEBUG_PREFIX}{path_str}:{lineno}:{column}: error decoding value: {err}");
-                }
-
-                if options.strict {
-                    return Err(Error::syntax_error(lineno, column));
-                }
-                continue;
+        let Some(value) = os_str_from_bytes(value) else {
+            let column = equals + 2;
+            if options.diagnostics_enabled() {
+                options.report(Diagnostic::new(DiagnosticCode::EncodingError, "error decoding value: invalid UTF-8").at(self.lineno, column));
+            }
+
+            if options.strict {
+                return Err(Error::syntax_error(self.lineno, column));
             }
+            return Ok(true);
         };
 
         if key.is_empty() {
             let column = 1;
-            if options.debug {
-                eprintln!("{DEBUG_PREFIX}{path_str}:{lineno}:{column}: empty keys are not allowed!");
+            if options.diagnostics_enabled() {
+                options.report(Diagnostic::new(DiagnosticCode::SyntaxError, "empty keys are not allowed!").at(self.lineno, column));
             }
 
             if options.strict {
-                return Err(Error::syntax_error(lineno, column));
+                return Err(Error::syntax_error(self.lineno, column));
             }
-            continue;
+            return Ok(true);
         }
 
-        options.set_var(env, key.as_ref(), value.as_ref());
-    }
+        options.set_var(env, key, value, Origin::new(self.lineno, 1));
 
-    Ok(())
+        Ok(true)
+    }
 }
@@ -1,13 +1,11 @@
-use std::{borrow::Cow, io::BufRead, num::NonZeroU8, path::Path};
+use std::{io::BufRead, num::NonZeroU8, path::Path};
 
-use crate::{env::GetEnv, Env, Error, Options, Result, DEBUG_PREFIX};
+use crate::{env::GetEnv, error::SourceLocation, parse::LineTracker, Diagnostic, DiagnosticCode, Env, Error, Options, Result};
 
 // trying to be compatible to: https://github.com/compose-spec/compose-go/blob/main/dotenv/parser.go
 pub fn config_composego(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dyn GetEnv, options: &Options<&Path>) -> Result<()> {
-    let path_str = options.path.to_string_lossy();
-
     let mut src = String::new();
-    options.encoding.read_to_string(reader, &mut src)?;
+    options.read_to_string(reader, &mut src)?;
 
     // strip byte order mark
     let src = src.strip_prefix('\u{FEFF}').unwrap_or(&src);
@@ -17,22 +15,21 @@ pub fn config_composego(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dy
     // let src = src.replace("\r\n", "\n");
 
     let mut cutset = src;
+    let mut tracker = LineTracker::new();
     let mut parser = Parser {
         lineno: 1,
-        path: path_str,
-        debug: options.debug,
+        options,
         strict: options.strict,
+        substituted: false,
     };
 
-    loop {
-        let Some(next_cutset) = parser.get_statement_start(cutset) else {
-            break;
-        };
+    while let Some(next_cutset) = parser.get_statement_start(cutset) {
         cutset = next_cutset;
         if cutset.is_empty() {
             break;
         }
 
+        let origin = tracker.origin_of(src, parser.trim_export(cutset));
         let (key, left, inherited) = match parser.locate_key_name(cutset) {
             Err(err) => {
                 if options.strict {
@@ -45,8 +42,8 @@ pub fn config_composego(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dy
         };
 
         if key.contains(' ') {
-            if options.debug {
-                eprintln!("{DEBUG_PREFIX}{}:{}: key cannot contain a space: {key:?}", &parser.path, parser.lineno);
+            if options.diagnostics_enabled() {
+                options.report(Diagnostic::new(DiagnosticCode::SyntaxError, format!("key cannot contain a space: {key:?}")).at_line(parser.lineno));
             }
             if options.strict {
                 return Err(Error::syntax_error(parser.lineno, 1));
@@ -58,8 +55,8 @@ pub fn config_composego(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dy
         let raw_key = key;
         let key = key.split('\0').next().unwrap();
         if key.len() != raw_key.len() {
-            if options.debug {
-                eprintln!("{DEBUG_PREFIX}{}:{}: key contains null byte: {key:?}", &parser.path, parser.lineno);
+            if options.diagnostics_enabled() {
+                options.report(Diagnostic::new(DiagnosticCode::IllegalNullByte, format!("key contains null byte: {key:?}")).at_line(parser.lineno));
             }
             if options.strict {
                 return Err(Error::syntax_error(parser.lineno, 1));
@@ -68,11 +65,13 @@ pub fn config_composego(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dy
 
         if inherited {
             if let Some(value) = parent.get(key.as_ref()) {
-                options.set_var(env, key.as_ref(), &value);
+                options.set_var(env, key.as_ref(), &value, origin.substituted(true));
             }
             cutset = left;
             continue;
         }
+        let quoted = has_quote_prefix(left).is_some();
+        parser.substituted = false;
         let (value, left) = match parser.extract_var_value(left, env.as_get_env()) {
             Err(err) => {
                 if options.strict {
@@ -86,15 +85,15 @@ pub fn config_composego(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dy
         let raw_value = &value;
         let value = value.split('\0').next().unwrap();
         if value.len() != raw_value.len() {
-            if options.debug {
-                eprintln!("{DEBUG_PREFIX}{}:{}: value of key {key:?} contains null byte: {value:?}", &parser.path, parser.lineno);
+            if options.diagnostics_enabled() {
+                options.report(Diagnostic::new(DiagnosticCode::IllegalNullByte, format!("value of key {key:?} contains null byte: {value:?}")).at_line(parser.lineno));
             }
             if options.strict {
                 return Err(Error::syntax_error(parser.lineno, 1));
             }
         }
 
-        options.set_var(env, key.as_ref(), value.as_ref());
+        options.set_var(env, key.as_ref(), value.as_ref(), origin.quoted(quoted).substituted(parser.substituted));
 
         cutset = left;
     }
@@ -104,9 +103,9 @@ pub fn config_composego(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dy
 
 struct Parser<'a> {
     lineno: usize,
-    path: Cow<'a, str>,
-    debug: bool,
+    options: &'a Options<&'a Path>,
     strict: bool,
+    substituted: bool,
 }
 
 impl<'a> Parser<'a> {
@@ -176,11 +175,9 @@ impl<'a> Parser<'a> {
                         continue;
                     }
 
-                    if self.debug {
+                    if self.options.diagnostics_enabled() {
                         let newline = src.find('\n').unwrap_or(src.len());
-                        eprintln!("{DEBUG_PREFIX}{}:{}: unexpected character {:?} in variable name {:?}",
-                            &self.path, self.lineno, rune, &src[..newline]
-                        );
+                        self.options.report(Diagnostic::new(DiagnosticCode::SyntaxError, format!("unexpected character {:?} in variable name {:?}", rune, &src[..newline])).at_line(self.lineno));
                     }
     
                     return Err(Error::syntax_error(self.lineno, 1));
@@ -189,10 +186,8 @@ impl<'a> Parser<'a> {
         }
 
         if src.is_empty() {
-            if self.debug {
-                eprintln!("{DEBUG_PREFIX}{}:{}: zero length string",
-                    &self.path, self.lineno
-                );
+            if self.options.diagnostics_enabled() {
+                self.options.report(Diagnostic::new(DiagnosticCode::SyntaxError, "zero length string").at_line(self.lineno));
             }
 
             return Err(Error::syntax_error(self.lineno, 1));
@@ -264,17 +259,15 @@ impl<'a> Parser<'a> {
             return Ok((value, &src[index + ch.len_utf8()..]));
         }
 
-        if self.debug {
-            eprintln!("{DEBUG_PREFIX}{}:{}: unterminated quoted value: {}",
-                &self.path, self.lineno, &quoted_start
-            );
+        if self.options.diagnostics_enabled() {
+            self.options.report(Diagnostic::new(DiagnosticCode::UnterminatedString, format!("unterminated quoted value: {}", &quoted_start)).at_line(self.lineno));
         }
 
         Err(Error::syntax_error(self.lineno, 1))
     }
 
     // see: https://github.com/compose-spec/compose-go/blob/e1496cd905b20b799fa3acecefed8056338961a2/template/template.go
-    fn expand_variables(&self, mut src: &str, env: &dyn GetEnv) -> Result<String> {
+    fn expand_variables(&mut self, mut src: &str, env: &dyn GetEnv) -> Result<String> {
         let mut buf = String::new();
 
         while !src.is_empty() {
@@ -288,10 +281,8 @@ impl<'a> Parser<'a> {
             src = &subst_start[1..];
 
             let Some(ch) = src.chars().next() else {
-                if self.debug {
-                    eprintln!("{DEBUG_PREFIX}{}:{}: single $ encounterd",
-                        &self.path, self.lineno
-                    );
+                if self.options.diagnostics_enabled() {
+                    self.options.report(Diagnostic::new(DiagnosticCode::SyntaxError, "single $ encounterd").at_line(self.lineno));
                 }
                 buf.push('$');
                 break;
@@ -306,10 +297,8 @@ impl<'a> Parser<'a> {
                     src = &src[1..];
                     let index = find_var_subst_end(src);
                     if index == 0 || !src.starts_with(is_var_subst_start) {
-                        if self.debug {
-                            eprintln!("{DEBUG_PREFIX}{}:{}: substitution syntax truncated: {}",
-                                &self.path, self.lineno, &subst_start[..2]
-                            );
+                        if self.options.diagnostics_enabled() {
+                            self.options.report(Diagnostic::new(DiagnosticCode::SubstitutionError, format!("substitution syntax truncated: {}", &subst_start[..2])).at_line(self.lineno));
                         }
                         if self.strict {
                             return Err(Error::syntax_error(self.lineno, 1));
@@ -321,13 +310,12 @@ impl<'a> Parser<'a> {
                     let name = &src[..index].split('\0').next().unwrap();
                     src = &src[index..];
                     let value = env.get(name.as_ref());
-                    let var_end_index = if src.starts_with(|ch| ch == ':' || ch == '?' ||ch == '+' || ch == '-') {
+                    self.substituted = true;
+                    let var_end_index = if src.starts_with([':', '?', '+', '-']) {
                         let var_end_index = find_braced_subst_end(src);
                         if var_end_index >= src.len() {
-                            if self.debug {
-                                eprintln!("{DEBUG_PREFIX}{}:{}: expected: \"}}\", actual: {src:?}",
-                                    &self.path, self.lineno
-                                );
+                            if self.options.diagnostics_enabled() {
+                                self.options.report(Diagnostic::new(DiagnosticCode::SyntaxError, format!("expected: \"}}\", actual: {src:?}")).at_line(self.lineno));
                             }
                             if self.strict {
                                 return Err(Error::syntax_error(self.lineno, 1));
@@ -340,10 +328,8 @@ impl<'a> Parser<'a> {
                     } else if src.starts_with('}') {
                         0
                     } else {
-                        if self.debug {
-                            eprintln!("{DEBUG_PREFIX}{}:{}: expected: \"}}\", actual: {src:?}",
-                                &self.path, self.lineno
-                            );
+                        if self.options.diagnostics_enabled() {
+                            self.options.report(Diagnostic::new(DiagnosticCode::SyntaxError, format!("expected: \"}}\", actual: {src:?}")).at_line(self.lineno));
                         }
                         if self.strict {
                             return Err(Error::syntax_error(self.lineno, 1));
@@ -359,32 +345,24 @@ impl<'a> Parser<'a> {
                         src = &src[var_end_index..];
                         if let Some(value) = value {
                             if value.is_empty() {
-                                if self.debug {
+                                if self.options.diagnostics_enabled() {
                                     let message = self.expand_variables(message, env)?;
                                     if message.is_empty() {
-                                        eprintln!("{DEBUG_PREFIX}{}:{}: variable ${} may not be empty",
-                                            &self.path, self.lineno, name
-                                        );
+                                        self.options.report(Diagnostic::new(DiagnosticCode::SubstitutionError, format!("variable ${} may not be empty", name)).at_line(self.lineno));
                                     } else {
-                                        eprintln!("{DEBUG_PREFIX}{}:{}: {}",
-                                            &self.path, self.lineno, message
-                                        );
+                                        self.options.report(Diagnostic::new(DiagnosticCode::SyntaxError, message.to_string()).at_line(self.lineno));
                                     }
                                 }
                                 return Err(Error::syntax_error(self.lineno, 1));
                             }
-                            buf.push_str(value.to_string_lossy().as_ref());
+                            buf.push_str(&self.options.var_value_str(name, &value, SourceLocation::new(self.lineno, 1))?);
                         } else {
-                            if self.debug {
+                            if self.options.diagnostics_enabled() {
                                 let message = self.expand_variables(message, env)?;
                                 if message.is_empty() {
-                                    eprintln!("{DEBUG_PREFIX}{}:{}: variable ${} may not be unset",
-                                        &self.path, self.lineno, name
-                                    );
+                                    self.options.report(Diagnostic::new(DiagnosticCode::SubstitutionError, format!("variable ${} may not be unset", name)).at_line(self.lineno));
                                 } else {
-                                    eprintln!("{DEBUG_PREFIX}{}:{}: {}",
-                                        &self.path, self.lineno, message
-                                    );
+                                    self.options.report(Diagnostic::new(DiagnosticCode::SyntaxError, message.to_string()).at_line(self.lineno));
                                 }
                             }
                             return Err(Error::syntax_error(self.lineno, 1));
@@ -394,18 +372,14 @@ impl<'a> Parser<'a> {
                         let message = &src[1..var_end_index];
                         src = &src[var_end_index..];
                         if let Some(value) = value {
-                            buf.push_str(value.to_string_lossy().as_ref());
+                            buf.push_str(&self.options.var_value_str(name, &value, SourceLocation::new(self.lineno, 1))?);
                         } else {
-                            if self.debug {
+                            if self.options.diagnostics_enabled() {
                                 let message = self.expand_variables(message, env)?;
                                 if message.is_empty() {
-                                    eprintln!("{DEBUG_PREFIX}{}:{}: variable ${} may not be unset",
-                                        &self.path, self.lineno, name
-                                    );
+                                    self.options.report(Diagnostic::new(DiagnosticCode::SubstitutionError, format!("variable ${} may not be unset", name)).at_line(self.lineno));
                                 } else {
-                                    eprintln!("{DEBUG_PREFIX}{}:{}: {}",
-                                        &self.path, self.lineno, message
-                                    );
+                                    self.options.report(Diagnostic::new(DiagnosticCode::SyntaxError, message.to_string()).at_line(self.lineno));
                                 }
                             }
                             return Err(Error::syntax_error(self.lineno, 1));
@@ -419,7 +393,7 @@ impl<'a> Parser<'a> {
                                 let default = self.expand_variables(default, env)?;
                                 buf.push_str(&default);
                             } else {
-                                buf.push_str(value.to_string_lossy().as_ref());
+                                buf.push_str(&self.options.var_value_str(name, &value, SourceLocation::new(self.lineno, 1))?);
                             }
                         } else {
                             let default = self.expand_variables(default, env)?;
@@ -430,7 +404,7 @@ impl<'a> Parser<'a> {
                         let default = &src[1..var_end_index];
                         src = &src[var_end_index..];
                         if let Some(value) = value {
-                            buf.push_str(value.to_string_lossy().as_ref());
+                            buf.push_str(&self.options.var_value_str(name, &value, SourceLocation::new(self.lineno, 1))?);
                         } else {
                             let default = self.expand_variables(default, env)?;
                             buf.push_str(&default);
@@ -454,14 +428,12 @@ impl<'a> Parser<'a> {
                             buf.push_str(&default);
                         }
                     } else if let Some(value) = value {
-                        buf.push_str(value.to_string_lossy().as_ref());
+                        buf.push_str(&self.options.var_value_str(name, &value, SourceLocation::new(self.lineno, 1))?);
                     }
 
                     if !src.starts_with('}') {
-                        if self.debug {
-                            eprintln!("{DEBUG_PREFIX}{}:{}: expected: \"}}\", actual: {src:?}",
-                                &self.path, self.lineno
-                            );
+                        if self.options.diagnostics_enabled() {
+                            self.options.report(Diagnostic::new(DiagnosticCode::SyntaxError, format!("expected: \"}}\", actual: {src:?}")).at_line(self.lineno));
                         }
                         if self.strict {
                             return Err(Error::syntax_error(self.lineno, 1));
@@ -472,20 +444,16 @@ impl<'a> Parser<'a> {
                 }
                 _ => {
                     if !src.starts_with(is_var_subst_start) {
-                        if self.debug {
-                            eprintln!("{DEBUG_PREFIX}{}:{}: ignored substitution syntax error: {:?}",
-                                &self.path, self.lineno, &subst_start[..1 + ch.len_utf8()]
-                            );
+                        if self.options.diagnostics_enabled() {
+                            self.options.report(Diagnostic::new(DiagnosticCode::SubstitutionError, format!("ignored substitution syntax error: {:?}", &subst_start[..1 + ch.len_utf8()])).at_line(self.lineno));
                         }
                         // seems to be ignored by the orginal
                         buf.push('$');
                     } else {
                         let index = find_var_subst_end(src);
                         if index == 0 {
-                            if self.debug {
-                                eprintln!("{DEBUG_PREFIX}{}:{}: substitution syntax error: {:?}",
-                                    &self.path, self.lineno, &subst_start[..1 + ch.len_utf8()]
-                                );
+                            if self.options.diagnostics_enabled() {
+                                self.options.report(Diagnostic::new(DiagnosticCode::SubstitutionError, format!("substitution syntax error: {:?}", &subst_start[..1 + ch.len_utf8()])).at_line(self.lineno));
                             }
                             if self.strict {
                                 return Err(Error::syntax_error(self.lineno, 1));
@@ -495,8 +463,9 @@ impl<'a> Parser<'a> {
                             let name = &src[..index].split('\0').next().unwrap();
                             src = &src[index..];
 
+                            self.substituted = true;
                             if let Some(value) = env.get(name.as_ref()) {
-                                buf.push_str(value.to_string_lossy().as_ref());
+                                buf.push_str(&self.options.var_value_str(name, &value, SourceLocation::new(self.lineno, 1))?);
                             }
                         }
                     }
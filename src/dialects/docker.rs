@@ -0,0 +1,75 @@
+// trying to emulate: https://github.com/docker/cli/blob/v27.3.1/pkg/kvfile/kvfile.go
+// docker compose uses the same for `env_file` entries with `format: raw`.
+use std::{io::BufRead, path::Path};
+
+use crate::{env::GetEnv, parse::LineTracker, Diagnostic, DiagnosticCode, Env, Error, Options, Result};
+
+const WHITESPACES: &[char] = &[' ', '\t'];
+
+pub fn config_docker(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dyn GetEnv, options: &Options<&Path>) -> Result<()> {
+    let mut src = String::new();
+    options.read_to_string(reader, &mut src)?;
+
+    // bufio.ScanLines
+    let lines = src.split_inclusive('\n').map(|line| {
+        let line = line.strip_suffix('\n').unwrap_or(line);
+        line.strip_suffix('\r').unwrap_or(line)
+    });
+    let mut tracker = LineTracker::new();
+
+    for (index, line) in lines.enumerate() {
+        // We trim UTF8 BOM
+        let line = if index == 0 {
+            line.strip_prefix('\u{FEFF}').unwrap_or(line)
+        } else {
+            line
+        };
+
+        // trim the line from all leading whitespace first
+        let line = line.trim_start();
+
+        // line is not empty, and not starting with '#'
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (variable, value) = match line.split_once('=') {
+            Some((variable, value)) => (variable, Some(value)),
+            None => (line, None),
+        };
+
+        // trim the front of a variable, but nothing else
+        let variable = variable.trim_start_matches(WHITESPACES);
+        let origin = tracker.origin_of(&src, variable);
+        let location = origin.location();
+
+        let message = if variable.contains(WHITESPACES) {
+            Some(format!("variable '{variable}' contains whitespaces"))
+        } else if variable.is_empty() {
+            Some(format!("no variable name on line '{line}'"))
+        } else {
+            None
+        };
+
+        if let Some(message) = message {
+            if options.diagnostics_enabled() {
+                options.report(Diagnostic::new(DiagnosticCode::SyntaxError, message).
+                    at(location.lineno(), location.column()));
+            }
+            if options.strict {
+                return Err(Error::syntax_error(location.lineno(), location.column()));
+            }
+            continue;
+        }
+
+        if let Some(value) = value {
+            // pass the value through, no trimming
+            options.set_var_cut_null(env, variable, value, origin);
+        } else if let Some(value) = parent.get(variable.as_ref()) {
+            // if only a pass-through variable is given, clean it up.
+            options.set_var(env, variable.split('\0').next().unwrap().as_ref(), &value, origin.substituted(true));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,347 @@
+// trying to emulate: https://github.com/motdotla/dotenv-expand/blob/v11.0.6/lib/main.js
+// on top of: https://github.com/motdotla/dotenv/blob/8ab33066f90a20445d3c41e4fafba6c929c5e1a5/lib/main.js
+use std::{collections::HashMap, io::BufRead, path::Path};
+
+use crate::{env::GetEnv, error::SourceLocation, Diagnostic, DiagnosticCode, Env, Error, Options, Origin, Result};
+
+use super::javascript_dotenv::parse_javascript_dotenv;
+
+/// Stand-in for JavaScript's call stack size. The original throws a
+/// `RangeError` on reference cycles in the pre-defined environment.
+const MAX_DEPTH: usize = 1024;
+
+pub fn config_dotenv_expand(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dyn GetEnv, options: &Options<&Path>) -> Result<()> {
+    let mut parsed = Parsed::parse(reader, options)?;
+
+    // dotenv.config() populates process.env before dotenv-expand gets to see
+    // the parsed values
+    for (key, value, _) in &parsed.vars {
+        let key = cut_null(key);
+        if options.override_env || env.get(key.as_ref()).is_none() {
+            env.set(key.as_ref(), cut_null(value).as_ref());
+        }
+    }
+
+    // in contrast to process.env punktum separates the environment that is
+    // written to from the one variables are inherited from
+    let from_process_env = parsed.expand(env.as_get_env(), parent, true, options)?;
+
+    // dotenv-expand writes all values back to process.env, which also means
+    // escape sequences are resolved in values that weren't overwritten
+    for ((key, value, origin), from_process_env) in parsed.vars.iter().zip(from_process_env) {
+        if from_process_env && options.diagnostics_enabled() {
+            let location = origin.location();
+            options.report(Diagnostic::new(DiagnosticCode::NotOverwritten,
+                format!("{key:?} is already defined and was NOT overwritten")).
+                at(location.lineno(), location.column()));
+        }
+        env.set_from(cut_null(key).as_ref(), cut_null(value).as_ref(), origin);
+    }
+
+    Ok(())
+}
+
+/// The object returned by `dotenv.parse()`: keys stay where they were first
+/// defined, but the last value wins.
+pub(crate) struct Parsed {
+    pub(crate) vars: Vec<(String, String, Origin)>,
+    indices: HashMap<String, usize>,
+}
+
+impl Parsed {
+    pub(crate) fn parse(reader: &mut dyn BufRead, options: &Options<&Path>) -> Result<Self> {
+        let mut vars: Vec<(String, String, Origin)> = Vec::new();
+        let mut indices: HashMap<String, usize> = HashMap::new();
+
+        parse_javascript_dotenv(reader, options, &mut |key, value, origin| {
+            if let Some(&index) = indices.get(key) {
+                vars[index].1 = value.to_owned();
+                vars[index].2 = origin;
+            } else {
+                indices.insert(key.to_owned(), vars.len());
+                vars.push((key.to_owned(), value.to_owned(), origin));
+            }
+        })?;
+
+        Ok(Self { vars, indices })
+    }
+
+    /// Emulates `expand()` with `env` on top of `parent` as `processEnv`.
+    /// Returns for each variable whether it kept the value from `processEnv`,
+    /// which is only possible if `use_process_env` is set.
+    pub(crate) fn expand(&mut self, env: &dyn GetEnv, parent: &dyn GetEnv, use_process_env: bool, options: &Options<&Path>) -> Result<Vec<bool>> {
+        let mut from_process_env = Vec::with_capacity(self.vars.len());
+        for index in 0..self.vars.len() {
+            let (key, value, origin) = &self.vars[index];
+            let process_value = if use_process_env {
+                get_process_env(env, parent, key, options, origin.location())?
+            } else {
+                None
+            };
+
+            let value = match process_value {
+                Some(process_value) if !process_value.is_empty() && process_value != *value => {
+                    // do not interpolate - assume processEnv had the intended value even if containing a $
+                    from_process_env.push(true);
+                    process_value
+                }
+                _ => {
+                    from_process_env.push(false);
+                    let mut expander = Expander {
+                        env,
+                        parent,
+                        parsed: self,
+                        options,
+                        location: origin.location(),
+                        substituted: false,
+                        error: None,
+                    };
+
+                    let expanded = expander.interpolate(value, 0);
+                    if let Some(error) = expander.error {
+                        return Err(error);
+                    }
+
+                    match expanded {
+                        Some(expanded) => {
+                            let substituted = expander.substituted;
+                            self.vars[index].2 = origin.substituted(substituted);
+                            expanded
+                        }
+                        None => {
+                            let location = origin.location();
+                            if options.diagnostics_enabled() {
+                                options.report(Diagnostic::new(DiagnosticCode::SubstitutionError,
+                                    format!("maximum recursion depth exceeded while expanding {key:?}")).
+                                    at(location.lineno(), location.column()));
+                            }
+                            if options.strict {
+                                return Err(Error::substitution_error(location.lineno(), location.column()));
+                            }
+                            value.clone()
+                        }
+                    }
+                }
+            };
+
+            self.vars[index].1 = resolve_escape_sequences(&value);
+        }
+
+        Ok(from_process_env)
+    }
+
+    #[inline]
+    fn get(&self, key: &str) -> Option<&str> {
+        self.indices.get(key).map(|&index| &self.vars[index].1[..])
+    }
+}
+
+#[inline]
+pub(crate) fn cut_null(value: &str) -> &str {
+    value.split('\0').next().unwrap()
+}
+
+#[inline]
+fn get_process_env(env: &dyn GetEnv, parent: &dyn GetEnv, key: &str, options: &Options<&Path>, location: SourceLocation) -> Result<Option<String>> {
+    let Some(value) = env.get(key.as_ref()).or_else(|| parent.get(key.as_ref())) else {
+        return Ok(None);
+    };
+    Ok(Some(options.var_value_str(key, &value, location)?.into_owned()))
+}
+
+#[inline]
+fn resolve_escape_sequences(value: &str) -> String {
+    value.replace("\\$", "$")
+}
+
+struct Expander<'a> {
+    env: &'a dyn GetEnv,
+    parent: &'a dyn GetEnv,
+    parsed: &'a Parsed,
+    options: &'a Options<&'a Path>,
+    /// Location of the variable that is expanded.
+    location: SourceLocation,
+    substituted: bool,
+    /// Set if substituting a value failed in strict mode.
+    error: Option<Error>,
+}
+
+impl<'a> Expander<'a> {
+
+    /// Returns `None` if the maximum recursion depth is exceeded or
+    /// [`Self::error`] was set.
+    fn interpolate(&mut self, value: &str, depth: usize) -> Option<String> {
+        if depth >= MAX_DEPTH {
+            return None;
+        }
+
+        let mut buf = String::new();
+        let mut index = 0;
+        let mut prev_index = 0;
+
+        while index < value.len() {
+            let Some(subst) = match_substitution(value, index) else {
+                index += value[index..].chars().next().map(char::len_utf8).unwrap_or(1);
+                continue;
+            };
+
+            buf.push_str(&value[prev_index..index]);
+
+            if subst.escaped {
+                buf.push_str(&value[index + 1..subst.end]);
+            } else {
+                self.substituted = true;
+                let parsed_value = self.parsed.get(subst.key);
+
+                let process_value = match get_process_env(self.env, self.parent, subst.key, self.options, self.location) {
+                    Ok(process_value) => process_value,
+                    Err(error) => {
+                        self.error = Some(error);
+                        return None;
+                    }
+                };
+
+                if let Some(process_value) = process_value.filter(|value| !value.is_empty()) {
+                    if Some(&process_value[..]) == parsed_value {
+                        buf.push_str(&process_value);
+                    } else {
+                        // scenario: PASSWORD_EXPAND_NESTED=${PASSWORD_EXPAND}
+                        buf.push_str(&self.interpolate(&process_value, depth + 1)?);
+                    }
+                } else if let Some(parsed_value) = parsed_value.filter(|parsed_value| !parsed_value.is_empty() && *parsed_value != value) {
+                    // avoid recursion from EXPAND_SELF=$EXPAND_SELF
+                    buf.push_str(&self.interpolate(parsed_value, depth + 1)?);
+                } else if let Some(default) = subst.default.filter(|default| !default.is_empty()) {
+                    if default.starts_with('$') {
+                        buf.push_str(&self.interpolate(default, depth + 1)?);
+                    } else {
+                        buf.push_str(default);
+                    }
+                }
+            }
+
+            index = subst.end;
+            prev_index = index;
+        }
+
+        buf.push_str(&value[prev_index..]);
+
+        Some(buf)
+    }
+}
+
+pub(crate) struct Substitution<'a> {
+    escaped: bool,
+    key: &'a str,
+    default: Option<&'a str>,
+    pub(crate) end: usize,
+}
+
+/// Emulates a match of this regular expression at `start`:
+///
+/// ```plain
+/// /(\\)?(\$)(?!\()(\{?)([\w.]+)(?::?-((?:\$\{(?:\$\{(?:\$\{[^}]*\}|[^}])*}|[^}])*}|[^}])+))?(\}?)/gi
+/// ```
+///
+/// Everything after the key is optional, so the first way the regular
+/// expression finds is the match. Only inside of the nested `${...}` groups
+/// backtracking can happen.
+pub(crate) fn match_substitution(src: &str, start: usize) -> Option<Substitution<'_>> {
+    let bytes = src.as_bytes();
+    let mut index = start;
+
+    let escaped = bytes.get(index) == Some(&b'\\');
+    if escaped {
+        index += 1;
+    }
+
+    if bytes.get(index) != Some(&b'$') {
+        return None;
+    }
+    index += 1;
+
+    match bytes.get(index) {
+        Some(b'(') => return None,
+        Some(b'{') => index += 1,
+        _ => {}
+    }
+
+    let key_start = index;
+    while index < bytes.len() && (bytes[index].is_ascii_alphanumeric() || bytes[index] == b'_' || bytes[index] == b'.') {
+        index += 1;
+    }
+
+    if key_start == index {
+        return None;
+    }
+    let key = &src[key_start..index];
+
+    let mut default = None;
+    let mut default_start = index;
+    if bytes.get(default_start) == Some(&b':') {
+        default_start += 1;
+    }
+    if bytes.get(default_start) == Some(&b'-') {
+        default_start += 1;
+        if let Some(default_end) = match_default(bytes, default_start) {
+            default = Some(&src[default_start..default_end]);
+            index = default_end;
+        }
+    }
+
+    if bytes.get(index) == Some(&b'}') {
+        index += 1;
+    }
+
+    Some(Substitution { escaped, key, default, end: index })
+}
+
+/// `(?:\$\{(?:\$\{(?:\$\{[^}]*\}|[^}])*}|[^}])*}|[^}])+`
+fn match_default(bytes: &[u8], start: usize) -> Option<usize> {
+    let mut index = start;
+    loop {
+        let mut group_end = None;
+        match_group(bytes, index, 3, &mut |end| {
+            group_end = Some(end);
+            true
+        });
+
+        if let Some(end) = group_end {
+            index = end;
+        } else if index < bytes.len() && bytes[index] != b'}' {
+            index += 1;
+        } else {
+            break;
+        }
+    }
+
+    if index == start {
+        None
+    } else {
+        Some(index)
+    }
+}
+
+/// Continuation passing backtracking matcher for `\$\{(?:GROUP|[^}])*}`
+/// nested `level` times.
+fn match_group(bytes: &[u8], index: usize, level: u32, cont: &mut dyn FnMut(usize) -> bool) -> bool {
+    if !bytes[index..].starts_with(b"${") {
+        return false;
+    }
+
+    match_group_items(bytes, index + 2, level, &mut |end| {
+        bytes.get(end) == Some(&b'}') && cont(end + 1)
+    })
+}
+
+fn match_group_items(bytes: &[u8], index: usize, level: u32, cont: &mut dyn FnMut(usize) -> bool) -> bool {
+    if level > 1 && match_group(bytes, index, level - 1, &mut |end| match_group_items(bytes, end, level, cont)) {
+        return true;
+    }
+
+    if index < bytes.len() && bytes[index] != b'}' && match_group_items(bytes, index + 1, level, cont) {
+        return true;
+    }
+
+    cont(index)
+}
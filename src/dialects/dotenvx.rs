@@ -0,0 +1,162 @@
+// trying to emulate: https://github.com/dotenvx/dotenvx/blob/v1.0.0/src/lib/helpers/parseExpandAndEval.js
+// and: https://github.com/dotenvx/dotenvx/blob/v1.0.0/src/lib/helpers/dotenvEval.js
+use std::{io::BufRead, path::Path};
+
+use crate::{env::GetEnv, error::SourceLocation, CommandPolicy, Diagnostic, DiagnosticCode, Env, Error, ErrorKind, Options, Result, Severity};
+
+use super::dotenv_expand::Parsed;
+
+pub fn config_dotenvx(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dyn GetEnv, options: &Options<&Path>) -> Result<()> {
+    let mut parsed = Parsed::parse(reader, options)?;
+
+    // commands are evaluated in parsed values only, never in process.env
+    for (_, value, origin) in &mut parsed.vars {
+        if let Some(evaled) = eval_commands(value, origin.location(), options)? {
+            *value = evaled;
+            *origin = origin.substituted(true);
+        }
+    }
+
+    // in contrast to dotenv-expand the file isn't applied to process.env
+    // before expanding, it works on a copy of process.env
+    parsed.expand(env.as_get_env(), parent, !options.override_env, options)?;
+
+    for (key, value, origin) in &parsed.vars {
+        options.set_var_cut_null(env, key, value, *origin);
+    }
+
+    Ok(())
+}
+
+/// Returns `None` if nothing was replaced.
+fn eval_commands(value: &str, location: SourceLocation, options: &Options<&Path>) -> Result<Option<String>> {
+    let matches = find_commands(value);
+    if matches.is_empty() {
+        return Ok(None);
+    }
+
+    let mut new_value = value.to_owned();
+    let mut replaced = false;
+    for subst in matches {
+        let command = &subst[2..subst.len() - 1];
+        let output = match &options.command_policy {
+            CommandPolicy::Hook(hook) => match hook.run(command) {
+                Ok(output) => output,
+                Err(err) => {
+                    if options.diagnostics_enabled() {
+                        options.report(Diagnostic::new(DiagnosticCode::CommandSubstitution,
+                            format!("command {command:?} failed: {err}")).
+                            at(location.lineno(), location.column()));
+                    }
+                    if options.strict {
+                        return Err(Error::new(ErrorKind::ExecError, err, location));
+                    }
+                    continue;
+                }
+            },
+            CommandPolicy::Keep => {
+                if options.diagnostics_enabled() {
+                    options.report(Diagnostic::new(DiagnosticCode::CommandSubstitution,
+                        format!("command substitution not performed: {subst}")).
+                        with_severity(Severity::Info).
+                        at(location.lineno(), location.column()));
+                }
+                continue;
+            }
+            CommandPolicy::Error => {
+                if options.diagnostics_enabled() {
+                    options.report(Diagnostic::new(DiagnosticCode::CommandSubstitution,
+                        format!("command substitution is not allowed: {subst}")).
+                        at(location.lineno(), location.column()));
+                }
+                if options.strict {
+                    return Err(Error::new(ErrorKind::ExecError,
+                        format!("command substitution is not allowed: {subst}"), location));
+                }
+                continue;
+            }
+        };
+
+        // chomp
+        let output = output.trim_end_matches('\n');
+
+        // newValue.replace(match, value) replaces the first occurrence, which
+        // might be in the output of a previous command!
+        new_value = js_replace_first(&new_value, subst, output);
+        replaced = true;
+    }
+
+    Ok(replaced.then_some(new_value))
+}
+
+/// Emulates `value.match(/\$\(([^)]+(?:\)[^)]+)*)\)/g)`.
+///
+/// The command ends at the last `)` that isn't followed by another `)` or the
+/// end of the string, so `$(a) $(b)` is one command `a) $(b`.
+fn find_commands(value: &str) -> Vec<&str> {
+    let bytes = value.as_bytes();
+    let mut matches = Vec::new();
+    let mut index = 0;
+
+    while let Some(pos) = value[index..].find("$(") {
+        let start = index + pos;
+        let mut end = None;
+        let mut segment_start = start + 2;
+
+        loop {
+            let segment_end = bytes[segment_start..].iter().position(|&byte| byte == b')').
+                map(|pos| segment_start + pos).
+                unwrap_or(bytes.len());
+
+            if segment_end == segment_start || segment_end == bytes.len() {
+                break;
+            }
+
+            end = Some(segment_end + 1);
+            segment_start = segment_end + 1;
+        }
+
+        if let Some(end) = end {
+            matches.push(&value[start..end]);
+            index = end;
+        } else {
+            index = start + 1;
+        }
+    }
+
+    matches
+}
+
+/// Emulates `haystack.replace(needle, replacement)` with a string as the
+/// pattern, including the `$` patterns in the replacement string.
+fn js_replace_first(haystack: &str, needle: &str, replacement: &str) -> String {
+    let Some(start) = haystack.find(needle) else {
+        return haystack.to_owned();
+    };
+    let end = start + needle.len();
+
+    let mut buf = String::with_capacity(haystack.len() + replacement.len());
+    buf.push_str(&haystack[..start]);
+
+    let mut rest = replacement;
+    while let Some(pos) = rest.find('$') {
+        buf.push_str(&rest[..pos]);
+        rest = &rest[pos + 1..];
+
+        match rest.as_bytes().first() {
+            Some(b'$')  => buf.push('$'),
+            Some(b'&')  => buf.push_str(needle),
+            Some(b'`')  => buf.push_str(&haystack[..start]),
+            Some(b'\'') => buf.push_str(&haystack[end..]),
+            _ => {
+                buf.push('$');
+                continue;
+            }
+        }
+        rest = &rest[1..];
+    }
+    buf.push_str(rest);
+
+    buf.push_str(&haystack[end..]);
+    buf
+}
@@ -0,0 +1,392 @@
+// trying to emulate: https://github.com/allan2/dotenvy/blob/v0.15.7/dotenv/src/iter.rs
+// and: https://github.com/allan2/dotenvy/blob/v0.15.7/dotenv/src/parse.rs
+use std::{collections::HashMap, io::BufRead, path::Path};
+
+use crate::{env::GetEnv, Diagnostic, DiagnosticCode, Env, Error, Options, Origin, Result};
+
+pub fn config_dotenvy(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dyn GetEnv, options: &Options<&Path>) -> Result<()> {
+    let mut src = String::new();
+    options.read_to_string(reader, &mut src)?;
+
+    let src = src.strip_prefix('\u{feff}').unwrap_or(&src);
+
+    let mut substitution_data = HashMap::new();
+    let mut lines = src.split_inclusive('\n');
+    let mut lineno = 0;
+
+    loop {
+        let record_lineno = lineno + 1;
+        let Some(record) = read_record(&mut lines, &mut lineno) else {
+            if options.diagnostics_enabled() {
+                options.report(Diagnostic::new(DiagnosticCode::UnterminatedString,
+                    "unexpected end of file").
+                    at_line(record_lineno));
+            }
+            if options.strict {
+                return Err(Error::syntax_error(record_lineno, 1));
+            }
+            break;
+        };
+
+        let Some(record) = record else {
+            break;
+        };
+
+        let mut parser = LineParser::new(&record, &mut substitution_data, env.as_get_env(), parent);
+        match parser.parse_line() {
+            Ok(Some((key, value))) => {
+                let origin = Origin::new(record_lineno, parser.key_pos + 1).
+                    quoted(parser.quoted).
+                    substituted(parser.substituted);
+                options.set_var_cut_null(env, &key, &value, origin);
+            }
+            Ok(None) => {}
+            Err(pos) => {
+                let (lineno, column) = location_of(&record, record_lineno, pos);
+                if options.diagnostics_enabled() {
+                    options.report(Diagnostic::new(DiagnosticCode::SyntaxError,
+                        format!("error parsing line: {:?}, error at line index: {}", record, pos)).
+                        at(lineno, column));
+                }
+                if options.strict {
+                    return Err(Error::syntax_error(lineno, column));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn location_of(record: &str, lineno: usize, pos: usize) -> (usize, usize) {
+    let pos = pos.min(record.len());
+    let before = &record[..pos];
+    let newlines = before.matches('\n').count();
+    let line_start = before.rfind('\n').map(|index| index + 1).unwrap_or(0);
+    (lineno + newlines, pos - line_start + 1)
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum ParseState {
+    Complete,
+    Escape,
+    StrongOpen,
+    WeakOpen,
+    WeakOpenEscape,
+    Comment,
+    WhiteSpace,
+}
+
+/// Emulates `QuotedLines`, which joins lines while a quote is open and cuts
+/// off comments. Returns `None` on an unexpected end of file and `Some(None)`
+/// at the end of the file.
+fn read_record<'a>(lines: &mut impl Iterator<Item = &'a str>, lineno: &mut usize) -> Option<Option<String>> {
+    let mut buf = String::new();
+    let mut cur_state = ParseState::Complete;
+
+    loop {
+        let Some(line) = lines.next() else {
+            if cur_state == ParseState::Complete {
+                return Some(None);
+            }
+            return None;
+        };
+        *lineno += 1;
+
+        let buf_pos = buf.len();
+        buf.push_str(line);
+
+        // Skip lines which start with a # before iteration
+        if buf.trim_start().starts_with('#') {
+            return Some(Some(String::new()));
+        }
+
+        let (cur_pos, state) = eval_end_state(cur_state, &buf[buf_pos..]);
+        cur_state = state;
+
+        match cur_state {
+            ParseState::Complete => {
+                if buf.ends_with('\n') {
+                    buf.pop();
+                    if buf.ends_with('\r') {
+                        buf.pop();
+                    }
+                }
+                return Some(Some(buf));
+            }
+            ParseState::Comment => {
+                buf.truncate(buf_pos + cur_pos);
+                return Some(Some(buf));
+            }
+            ParseState::Escape |
+            ParseState::StrongOpen |
+            ParseState::WeakOpen |
+            ParseState::WeakOpenEscape |
+            ParseState::WhiteSpace => {}
+        }
+    }
+}
+
+fn eval_end_state(prev_state: ParseState, buf: &str) -> (usize, ParseState) {
+    let mut cur_state = prev_state;
+    let mut cur_pos = 0;
+
+    for (pos, c) in buf.char_indices() {
+        cur_pos = pos;
+        cur_state = match cur_state {
+            ParseState::WhiteSpace => match c {
+                '#' => return (cur_pos, ParseState::Comment),
+                '\\' => ParseState::Escape,
+                '"' => ParseState::WeakOpen,
+                '\'' => ParseState::StrongOpen,
+                _ => ParseState::Complete,
+            },
+            ParseState::Escape => ParseState::Complete,
+            ParseState::Complete => match c {
+                c if c.is_whitespace() && c != '\n' && c != '\r' => ParseState::WhiteSpace,
+                '\\' => ParseState::Escape,
+                '"' => ParseState::WeakOpen,
+                '\'' => ParseState::StrongOpen,
+                _ => ParseState::Complete,
+            },
+            ParseState::WeakOpen => match c {
+                '\\' => ParseState::WeakOpenEscape,
+                '"' => ParseState::Complete,
+                _ => ParseState::WeakOpen,
+            },
+            ParseState::WeakOpenEscape => ParseState::WeakOpen,
+            ParseState::StrongOpen => match c {
+                '\'' => ParseState::Complete,
+                _ => ParseState::StrongOpen,
+            },
+            ParseState::Comment => unreachable!(),
+        };
+    }
+
+    (cur_pos, cur_state)
+}
+
+struct LineParser<'a> {
+    substitution_data: &'a mut HashMap<String, Option<String>>,
+    env: &'a dyn GetEnv,
+    parent: &'a dyn GetEnv,
+    line: &'a str,
+    pos: usize,
+    key_pos: usize,
+    quoted: bool,
+    substituted: bool,
+}
+
+/// Errors are the byte offset in the record.
+type ParseResult<T> = std::result::Result<T, usize>;
+
+impl<'a> LineParser<'a> {
+    fn new(line: &'a str, substitution_data: &'a mut HashMap<String, Option<String>>, env: &'a dyn GetEnv, parent: &'a dyn GetEnv) -> Self {
+        Self {
+            substitution_data,
+            env,
+            parent,
+            // we don’t want trailing whitespace
+            line: line.trim_end(),
+            pos: 0,
+            key_pos: 0,
+            quoted: false,
+            substituted: false,
+        }
+    }
+
+    fn parse_line(&mut self) -> ParseResult<Option<(String, String)>> {
+        self.skip_whitespace();
+        // if its an empty line or a comment, skip it
+        if self.line.is_empty() || self.line.starts_with('#') {
+            return Ok(None);
+        }
+
+        let mut key = self.parse_key()?;
+        self.skip_whitespace();
+
+        // export can be either an optional prefix or a key itself
+        if key == "export" {
+            // here we check for an optional `=`, below we throw directly when it’s not found.
+            if self.expect_equal().is_err() {
+                key = self.parse_key()?;
+                self.skip_whitespace();
+                self.expect_equal()?;
+            }
+        } else {
+            self.expect_equal()?;
+        }
+        self.skip_whitespace();
+
+        if self.line.is_empty() || self.line.starts_with('#') {
+            self.substitution_data.insert(key.clone(), None);
+            return Ok(Some((key, String::new())));
+        }
+
+        let parsed_value = self.parse_value()?;
+        self.substitution_data.insert(key.clone(), Some(parsed_value.clone()));
+
+        Ok(Some((key, parsed_value)))
+    }
+
+    fn parse_key(&mut self) -> ParseResult<String> {
+        if !self.line.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+            return Err(self.pos);
+        }
+        let index = match self.line.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '.')) {
+            Some(index) => index,
+            None => self.line.len(),
+        };
+        self.key_pos = self.pos;
+        self.pos += index;
+        let key = String::from(&self.line[..index]);
+        self.line = &self.line[index..];
+        Ok(key)
+    }
+
+    fn expect_equal(&mut self) -> ParseResult<()> {
+        if !self.line.starts_with('=') {
+            return Err(self.pos);
+        }
+        self.line = &self.line[1..];
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn skip_whitespace(&mut self) {
+        if let Some(index) = self.line.find(|c: char| !c.is_whitespace()) {
+            self.pos += index;
+            self.line = &self.line[index..];
+        } else {
+            self.pos += self.line.len();
+            self.line = "";
+        }
+    }
+
+    fn parse_value(&mut self) -> ParseResult<String> {
+        let input = self.line;
+        let mut strong_quote = false; // '
+        let mut weak_quote = false; // "
+        let mut escaped = false;
+        let mut expecting_end = false;
+
+        let mut output = String::new();
+
+        let mut substitution_mode = SubstitutionMode::None;
+        let mut substitution_name = String::new();
+
+        for (index, c) in input.char_indices() {
+            //the regex _should_ already trim whitespace off the end
+            //expecting_end is meant to permit: k=v #comment
+            //without affecting: k=v#comment
+            //and throwing on: k=v w
+            if expecting_end {
+                if c == ' ' || c == '\t' {
+                    continue;
+                } else if c == '#' {
+                    break;
+                } else {
+                    return Err(self.pos + index);
+                }
+            } else if escaped {
+                match c {
+                    '\\' | '\'' | '"' | '$' | ' ' => output.push(c),
+                    'n' => output.push('\n'), // handle \n case
+                    _ => {
+                        return Err(self.pos + index);
+                    }
+                }
+
+                escaped = false;
+            } else if strong_quote {
+                if c == '\'' {
+                    strong_quote = false;
+                } else {
+                    output.push(c);
+                }
+            } else if substitution_mode != SubstitutionMode::None {
+                if c.is_alphanumeric() {
+                    substitution_name.push(c);
+                } else {
+                    match substitution_mode {
+                        SubstitutionMode::None => unreachable!(),
+                        SubstitutionMode::Block => {
+                            if c == '{' && substitution_name.is_empty() {
+                                substitution_mode = SubstitutionMode::EscapedBlock;
+                            } else {
+                                self.apply_substitution(&substitution_name, &mut output);
+                                substitution_name.clear();
+                                if c == '$' {
+                                    substitution_mode = SubstitutionMode::Block;
+                                } else {
+                                    // yes, this pushes a closing quote as part of the value
+                                    substitution_mode = SubstitutionMode::None;
+                                    output.push(c);
+                                }
+                            }
+                        }
+                        SubstitutionMode::EscapedBlock => {
+                            if c == '}' {
+                                substitution_mode = SubstitutionMode::None;
+                                self.apply_substitution(&substitution_name, &mut output);
+                                substitution_name.clear();
+                            } else {
+                                substitution_name.push(c);
+                            }
+                        }
+                    }
+                }
+            } else if c == '$' {
+                substitution_mode = SubstitutionMode::Block;
+                self.substituted = true;
+            } else if weak_quote {
+                if c == '"' {
+                    weak_quote = false;
+                } else if c == '\\' {
+                    escaped = true;
+                } else {
+                    output.push(c);
+                }
+            } else if c == '\'' {
+                strong_quote = true;
+                self.quoted = true;
+            } else if c == '"' {
+                weak_quote = true;
+                self.quoted = true;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == ' ' || c == '\t' {
+                expecting_end = true;
+            } else {
+                output.push(c);
+            }
+        }
+
+        if substitution_mode == SubstitutionMode::EscapedBlock || strong_quote || weak_quote {
+            let value_length = input.len();
+            Err(self.pos + if value_length == 0 { 0 } else { value_length - 1 })
+        } else {
+            self.apply_substitution(&substitution_name, &mut output);
+            Ok(output)
+        }
+    }
+
+    fn apply_substitution(&self, substitution_name: &str, output: &mut String) {
+        // std::env::var() fails for values that aren't valid UTF-8
+        let environment_value = self.env.get(substitution_name.as_ref()).
+            or_else(|| self.parent.get(substitution_name.as_ref()));
+
+        if let Some(environment_value) = environment_value.as_ref().and_then(|value| value.to_str()) {
+            output.push_str(environment_value);
+        } else if let Some(Some(stored_value)) = self.substitution_data.get(substitution_name) {
+            output.push_str(stored_value);
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum SubstitutionMode {
+    None,
+    Block,
+    EscapedBlock,
+}
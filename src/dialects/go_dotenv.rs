@@ -1,58 +1,57 @@
-use std::{borrow::Cow, io::BufRead, num::NonZeroU8, path::Path};
+use std::{io::BufRead, num::NonZeroU8, path::Path};
 
-use crate::{env::GetEnv, Env, Error, Options, Result, DEBUG_PREFIX};
+use crate::{env::GetEnv, error::SourceLocation, parse::LineTracker, Diagnostic, DiagnosticCode, Env, Error, Options, Result};
 
 // trying to be compatible to: https://github.com/joho/godotenv/blob/v1.5.1/parser.go
 pub fn config_go_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, options: &Options<&Path>) -> Result<()> {
-    let path_str = options.path.to_string_lossy();
-
     let mut src = String::new();
-    options.encoding.read_to_string(reader, &mut src)?;
+    options.read_to_string(reader, &mut src)?;
 
     let src = src.replace("\r\n", "\n");
     let mut cutset = &src[..];
+    let mut tracker = LineTracker::new();
     let mut parser = Parser {
         lineno: 1,
-        path: path_str,
-        debug: options.debug,
+        options,
         strict: options.strict,
+        substituted: false,
     };
 
-    loop {
-        let Some(next_cutset) = parser.get_statement_start(cutset) else {
-            break;
-        };
+    while let Some(next_cutset) = parser.get_statement_start(cutset) {
         cutset = next_cutset;
         if cutset.is_empty() {
             break;
         }
 
+        let origin = tracker.origin_of(&src, parser.trim_export(cutset));
         let (key, left) = parser.locate_key_name(cutset)?;
 
         let raw_key = key;
         let key = key.split('\0').next().unwrap();
         if key.len() != raw_key.len() {
-            if options.debug {
-                eprintln!("{DEBUG_PREFIX}{}:{}: key contains null byte: {key:?}", &parser.path, parser.lineno);
+            if options.diagnostics_enabled() {
+                options.report(Diagnostic::new(DiagnosticCode::IllegalNullByte, format!("key contains null byte: {key:?}")).at_line(parser.lineno));
             }
             if options.strict {
                 return Err(Error::syntax_error(parser.lineno, 1));
             }
         }
 
+        let quoted = has_quote_prefix(left).is_some();
+        parser.substituted = false;
         let (value, left) = parser.extract_var_value(left, env.as_get_env())?;
         let raw_value = &value;
         let value = value.split('\0').next().unwrap();
         if value.len() != raw_value.len() {
-            if options.debug {
-                eprintln!("{DEBUG_PREFIX}{}:{}: value of key {key:?} contains null byte: {value:?}", &parser.path, parser.lineno);
+            if options.diagnostics_enabled() {
+                options.report(Diagnostic::new(DiagnosticCode::IllegalNullByte, format!("value of key {key:?} contains null byte: {value:?}")).at_line(parser.lineno));
             }
             if options.strict {
                 return Err(Error::syntax_error(parser.lineno, 1));
             }
         }
 
-        options.set_var(env, key.as_ref(), value.as_ref());
+        options.set_var(env, key.as_ref(), value.as_ref(), origin.quoted(quoted).substituted(parser.substituted));
 
         cutset = left;
     }
@@ -62,9 +61,9 @@ pub fn config_go_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, options: &O
 
 struct Parser<'a> {
     lineno: usize,
-    path: Cow<'a, str>,
-    debug: bool,
+    options: &'a Options<&'a Path>,
     strict: bool,
+    substituted: bool,
 }
 
 impl<'a> Parser<'a> {
@@ -132,11 +131,9 @@ impl<'a> Parser<'a> {
                         continue;
                     }
 
-                    if self.debug {
+                    if self.options.diagnostics_enabled() {
                         let newline = src.find('\n').unwrap_or(src.len());
-                        eprintln!("{DEBUG_PREFIX}{}:{}: unexpected character {} in variable name {:?}",
-                            &self.path, self.lineno, rune, &src[..newline]
-                        );
+                        self.options.report(Diagnostic::new(DiagnosticCode::SyntaxError, format!("unexpected character {} in variable name {:?}", rune, &src[..newline])).at_line(self.lineno));
                     }
     
                     return Err(Error::syntax_error(self.lineno, 1));
@@ -145,10 +142,8 @@ impl<'a> Parser<'a> {
         }
 
         if src.is_empty() {
-            if self.debug {
-                eprintln!("{DEBUG_PREFIX}{}:{}: zero length string",
-                    &self.path, self.lineno
-                );
+            if self.options.diagnostics_enabled() {
+                self.options.report(Diagnostic::new(DiagnosticCode::SyntaxError, "zero length string").at_line(self.lineno));
             }
 
             return Err(Error::syntax_error(self.lineno, 1));
@@ -166,7 +161,7 @@ impl<'a> Parser<'a> {
                 return Ok((String::new(), ""));
             }
 
-            let end_of_line = src.find(|ch| ch == '\r' || ch == '\n').unwrap_or(src.len());
+            let end_of_line = src.find(['\r', '\n']).unwrap_or(src.len());
             let line = &src[..end_of_line];
             if line.is_empty() {
                 return Ok((String::new(), &src[end_of_line..]));
@@ -200,7 +195,7 @@ impl<'a> Parser<'a> {
             if ch == '\n' {
                 self.lineno += 1;
             }
-            if ch != quote || (index > 0 && src[index - 1..].starts_with('\\')) {
+            if ch != quote || (index > 0 && src.as_bytes()[index - 1] == b'\\') {
                 continue;
             }
 
@@ -215,16 +210,14 @@ impl<'a> Parser<'a> {
         }
 
         let val_end_index = src.find('\n').unwrap_or(src.len());
-        if self.debug {
-            eprintln!("{DEBUG_PREFIX}{}:{}: unterminated quoted value {}",
-                &self.path, self.lineno, &src[..val_end_index]
-            );
+        if self.options.diagnostics_enabled() {
+            self.options.report(Diagnostic::new(DiagnosticCode::UnterminatedString, format!("unterminated quoted value {}", &src[..val_end_index])).at_line(self.lineno));
         }
 
         Err(Error::syntax_error(self.lineno, 1))
     }
 
-    fn expand_variables(&self, mut src: &str, env: &dyn GetEnv) -> Result<String> {
+    fn expand_variables(&mut self, mut src: &str, env: &dyn GetEnv) -> Result<String> {
         let mut buf = String::new();
 
         while !src.is_empty() {
@@ -244,10 +237,8 @@ impl<'a> Parser<'a> {
             src = &src[index + 1..];
 
             let Some(ch) = src.chars().next() else {
-                if self.debug {
-                    eprintln!("{DEBUG_PREFIX}{}:{}: single $ encounterd",
-                        &self.path, self.lineno
-                    );
+                if self.options.diagnostics_enabled() {
+                    self.options.report(Diagnostic::new(DiagnosticCode::SyntaxError, "single $ encounterd").at_line(self.lineno));
                 }
                 buf.push('$');
                 break;
@@ -258,10 +249,8 @@ impl<'a> Parser<'a> {
                     src = &src[1..];
                     let index = find_var_subst_end(src);
                     if index == 0 {
-                        if self.debug {
-                            eprintln!("{DEBUG_PREFIX}{}:{}: substitution syntax error",
-                                &self.path, self.lineno
-                            );
+                        if self.options.diagnostics_enabled() {
+                            self.options.report(Diagnostic::new(DiagnosticCode::SubstitutionError, "substitution syntax error").at_line(self.lineno));
                         }
                         return Err(Error::syntax_error(self.lineno, 1));
                     }
@@ -270,10 +259,8 @@ impl<'a> Parser<'a> {
                     src = &src[index..];
 
                     if !src.starts_with('}') {
-                        if self.debug {
-                            eprintln!("{DEBUG_PREFIX}{}:{}: expected: \"}}\", actual: {src:?}",
-                                &self.path, self.lineno
-                            );
+                        if self.options.diagnostics_enabled() {
+                            self.options.report(Diagnostic::new(DiagnosticCode::SyntaxError, format!("expected: \"}}\", actual: {src:?}")).at_line(self.lineno));
                         }
                         if self.strict {
                             return Err(Error::syntax_error(self.lineno, 1));
@@ -284,8 +271,9 @@ impl<'a> Parser<'a> {
                     }
 
                     let value = env.get(name.as_ref());
+                    self.substituted = true;
                     if let Some(value) = value {
-                        buf.push_str(value.to_string_lossy().as_ref());
+                        buf.push_str(&self.options.var_value_str(name, &value, SourceLocation::new(self.lineno, 1))?);
                     }
 
                     src = &src[1..];
@@ -293,10 +281,8 @@ impl<'a> Parser<'a> {
                 _ => {
                     let index = find_var_subst_end(src);
                     if index == 0 {
-                        if self.debug {
-                            eprintln!("{DEBUG_PREFIX}{}:{}: substitution syntax error",
-                                &self.path, self.lineno
-                            );
+                        if self.options.diagnostics_enabled() {
+                            self.options.report(Diagnostic::new(DiagnosticCode::SubstitutionError, "substitution syntax error").at_line(self.lineno));
                         }
                         return Err(Error::syntax_error(self.lineno, 1));
                     }
@@ -304,8 +290,9 @@ impl<'a> Parser<'a> {
                     let name = &src[..index].split('\0').next().unwrap();
                     src = &src[index..];
 
+                    self.substituted = true;
                     if let Some(value) = env.get(name.as_ref()) {
-                        buf.push_str(value.to_string_lossy().as_ref());
+                        buf.push_str(&self.options.var_value_str(name, &value, SourceLocation::new(self.lineno, 1))?);
                     }
                 }
             }
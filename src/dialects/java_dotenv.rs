@@ -1,17 +1,17 @@
 // based on: https://github.com/cdimascio/dotenv-java/blob/0c5642eeac01cc3532d46e02d4901c58a9261961/src/main/java/io/github/cdimascio/dotenv/internal/DotenvParser.java
 use std::{io::BufRead, path::Path};
 
-use crate::{line_splitter::split_lines, Env, Error, Options, Result, DEBUG_PREFIX};
+use crate::{line_splitter::split_lines, Diagnostic, DiagnosticCode, Env, Error, Options, Origin, Result};
 
 pub fn config_java_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, options: &Options<&Path>) -> Result<()> {
-    let path_str = options.path.to_string_lossy();
     let mut lines = String::new();
-    options.encoding.read_to_string(reader, &mut lines)?;
+    options.read_to_string(reader, &mut lines)?;
 
     let mut lineno = 0;
     for line in split_lines(&lines) {
         lineno += 1;
 
+        let indent = line.len() - line.trim_start_matches(|ch| ch <= ' ').len();
         let line = trim(line);
         // Don't know why it tests for an empty line twice in different ways?
         // That's what the original does!
@@ -27,13 +27,11 @@ pub fn config_java_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, options:
         index = key_end;
 
         if key_start == key_end {
-            if options.debug {
+            if options.diagnostics_enabled() {
                 if let Some(ch) = line[key_start..].chars().next() {
-                    eprintln!("{DEBUG_PREFIX}{path_str}:{}: expected variable name, found {:?}: {}",
-                        lineno, ch, line);
+                    options.report(Diagnostic::new(DiagnosticCode::SyntaxError, format!("expected variable name, found {:?}: {}", ch, line)).at_line(lineno));
                 } else {
-                    eprintln!("{DEBUG_PREFIX}{path_str}:{}: unexpected end of file: {}",
-                        lineno, line);
+                    options.report(Diagnostic::new(DiagnosticCode::SyntaxError, format!("unexpected end of file: {}", line)).at_line(lineno));
                 }
             }
             if options.strict {
@@ -45,9 +43,8 @@ pub fn config_java_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, options:
         index = skip_ws(line, index);
 
         let Some(ch) = line[index..].chars().next() else {
-            if options.debug {
-                eprintln!("{DEBUG_PREFIX}{path_str}:{}: unexpected end of line: {}",
-                    lineno, line);
+            if options.diagnostics_enabled() {
+                options.report(Diagnostic::new(DiagnosticCode::SyntaxError, format!("unexpected end of line: {}", line)).at_line(lineno));
             }
             if options.strict {
                 return Err(Error::syntax_error(lineno, 1));
@@ -56,9 +53,8 @@ pub fn config_java_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, options:
         };
 
         if ch != '=' {
-            if options.debug {
-                eprintln!("{DEBUG_PREFIX}{path_str}:{}: expected '=', found {:?}: {}",
-                    lineno, ch, line);
+            if options.diagnostics_enabled() {
+                options.report(Diagnostic::new(DiagnosticCode::SyntaxError, format!("expected '=', found {:?}: {}", ch, line)).at_line(lineno));
             }
             if options.strict {
                 return Err(Error::syntax_error(lineno, 1));
@@ -91,9 +87,8 @@ pub fn config_java_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, options:
             index = skip_ws(line, value_end);
             if let Some(ch) = line[index..].chars().next() {
                 if ch != '#' {
-                    if options.debug {
-                        eprintln!("{DEBUG_PREFIX}{path_str}:{}: expected line end or '#', found {:?}, fallback to unquoted string: {}",
-                            lineno, ch, line);
+                    if options.diagnostics_enabled() {
+                        options.report(Diagnostic::new(DiagnosticCode::SyntaxError, format!("expected line end or '#', found {:?}, fallback to unquoted string: {}", ch, line)).at_line(lineno));
                     }
                     if options.strict {
                         return Err(Error::syntax_error(lineno, 1));
@@ -111,17 +106,19 @@ pub fn config_java_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, options:
         let key = &line[key_start..key_end];
         let value = &line[value_start..value_end];
         if value == "\"" {
-            if options.debug {
-                eprintln!("{DEBUG_PREFIX}{path_str}:{}: value is a single double quote, this would have crashed the original: {}",
-                    lineno, line);
+            if options.diagnostics_enabled() {
+                options.report(Diagnostic::new(DiagnosticCode::SyntaxError, format!("value is a single double quote, this would have crashed the original: {}", line)).at_line(lineno));
             }
             if options.strict {
                 return Err(Error::syntax_error(lineno, 1));
             }
             continue;
         }
+        let trimmed_value = trim(value);
+        let quoted = trimmed_value.len() > 1 && is_quoted(trimmed_value);
         let value = normalize_value(value);
-        options.set_var_cut_null(env, key, value);
+        let origin = Origin::new(lineno, indent + key_start + 1).quoted(quoted);
+        options.set_var_cut_null(env, key, value, origin);
     }
 
     Ok(())
@@ -0,0 +1,280 @@
+// trying to emulate java.util.Properties.load():
+// https://github.com/openjdk/jdk/blob/jdk-21-ga/src/java.base/share/classes/java/util/Properties.java
+use std::{collections::HashMap, io::BufRead, path::Path};
+
+use crate::{diagnostic::Severity, parse::LineTracker, Diagnostic, DiagnosticCode, Encoding, Env, Error, Options, Origin, Result};
+
+pub fn config_java_properties(reader: &mut dyn BufRead, env: &mut dyn Env, options: &Options<&Path>) -> Result<()> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+
+    // Properties.load(InputStream) always reads ISO-8859-1, but
+    // PropertyResourceBundle and most tools read UTF-8 and fall back to
+    // ISO-8859-1 if the file isn't valid UTF-8. Do the same unless another
+    // encoding is configured.
+    let encoding = if options.encoding == Encoding::UTF8 && std::str::from_utf8(&bytes).is_err() {
+        Encoding::Latin1
+    } else {
+        options.encoding
+    };
+
+    let mut src = String::new();
+    encoding.read_to_string(&mut bytes.as_slice(), &mut src)?;
+
+    let mut tracker = LineTracker::new();
+
+    // Properties is a Hashtable, so the last definition of a key wins.
+    let mut entries: Vec<(String, String, Origin)> = Vec::new();
+    let mut indices: HashMap<String, usize> = HashMap::new();
+
+    for (offset, line) in logical_lines(&src) {
+        let origin = tracker.origin_at(&src, offset);
+        let location = origin.location();
+        let (key, value) = split_key_value(&line);
+
+        let key = match load_convert(key) {
+            Ok(key) => key,
+            Err(message) => {
+                if options.diagnostics_enabled() {
+                    options.report(Diagnostic::new(DiagnosticCode::IllegalEscape, message).
+                        at(location.lineno(), location.column()));
+                }
+                if options.strict {
+                    return Err(Error::syntax_error(location.lineno(), location.column()));
+                }
+                continue;
+            }
+        };
+
+        let value = match load_convert(value) {
+            Ok(value) => value,
+            Err(message) => {
+                if options.diagnostics_enabled() {
+                    options.report(Diagnostic::new(DiagnosticCode::IllegalEscape, format!("{key:?}: {message}")).
+                        at(location.lineno(), location.column()));
+                }
+                if options.strict {
+                    return Err(Error::syntax_error(location.lineno(), location.column()));
+                }
+                continue;
+            }
+        };
+
+        let key = decode_utf16(key, options, origin);
+        let value = decode_utf16(value, options, origin);
+
+        let name = options.key_mapping.map(&key);
+        if name.is_empty() || name.contains(['=', '\0']) {
+            if options.diagnostics_enabled() {
+                options.report(Diagnostic::new(DiagnosticCode::SyntaxError,
+                    format!("property {key:?} maps to an illegal variable name: {name:?}")).
+                    at(location.lineno(), location.column()));
+            }
+            if options.strict {
+                return Err(Error::syntax_error(location.lineno(), location.column()));
+            }
+            continue;
+        }
+
+        if let Some(&index) = indices.get(&name) {
+            entries[index] = (name, value, origin);
+        } else {
+            indices.insert(name.clone(), entries.len());
+            entries.push((name, value, origin));
+        }
+    }
+
+    for (name, value, origin) in entries {
+        options.set_var_cut_null(env, &name, &value, origin);
+    }
+
+    Ok(())
+}
+
+#[inline]
+fn is_whitespace(ch: char) -> bool {
+    matches!(ch, ' ' | '\t' | '\x0C')
+}
+
+/// Properties.LineReader: Joins lines ending in an odd number of backslashes
+/// with the following line (without its leading whitespace) and drops comment
+/// and blank lines. Yields the offset of the first character of each logical
+/// line.
+fn logical_lines(src: &str) -> Vec<(usize, Vec<char>)> {
+    let mut lines = Vec::new();
+    let mut line = Vec::new();
+    let mut start = 0;
+    let mut skip_whitespace = true;
+    let mut appended_line_begin = false;
+    let mut preceding_backslash = false;
+    let mut comment = false;
+    let mut skip_lf = false;
+
+    for (index, ch) in src.char_indices() {
+        if skip_lf {
+            skip_lf = false;
+            if ch == '\n' {
+                continue;
+            }
+        }
+
+        if comment {
+            if ch == '\n' || ch == '\r' {
+                comment = false;
+                skip_whitespace = true;
+            }
+            continue;
+        }
+
+        if skip_whitespace {
+            if is_whitespace(ch) || (!appended_line_begin && (ch == '\n' || ch == '\r')) {
+                continue;
+            }
+            skip_whitespace = false;
+            appended_line_begin = false;
+        }
+
+        // This is also checked after a line continuation, so a line that is
+        // just a backslash comments out the next line.
+        if line.is_empty() && (ch == '#' || ch == '!') {
+            comment = true;
+            continue;
+        }
+
+        if ch != '\n' && ch != '\r' {
+            if line.is_empty() {
+                start = index;
+            }
+            line.push(ch);
+            preceding_backslash = ch == '\\' && !preceding_backslash;
+        } else if line.is_empty() {
+            skip_whitespace = true;
+        } else if preceding_backslash {
+            line.pop();
+            skip_whitespace = true;
+            appended_line_begin = true;
+            preceding_backslash = false;
+            skip_lf = ch == '\r';
+        } else {
+            lines.push((start, std::mem::take(&mut line)));
+            skip_whitespace = true;
+        }
+    }
+
+    if !line.is_empty() && !comment {
+        if preceding_backslash {
+            line.pop();
+        }
+        lines.push((start, line));
+    }
+
+    lines
+}
+
+/// The key ends at the first unescaped `=`, `:` or whitespace. Whitespace and
+/// one `=` or `:` after it are skipped.
+fn split_key_value(line: &[char]) -> (&[char], &[char]) {
+    let mut key_len = 0;
+    let mut value_start = line.len();
+    let mut has_separator = false;
+    let mut preceding_backslash = false;
+
+    while key_len < line.len() {
+        let ch = line[key_len];
+        if !preceding_backslash {
+            if ch == '=' || ch == ':' {
+                value_start = key_len + 1;
+                has_separator = true;
+                break;
+            } else if is_whitespace(ch) {
+                value_start = key_len + 1;
+                break;
+            }
+        }
+        preceding_backslash = ch == '\\' && !preceding_backslash;
+        key_len += 1;
+    }
+
+    while value_start < line.len() {
+        let ch = line[value_start];
+        if !is_whitespace(ch) {
+            if !has_separator && (ch == '=' || ch == ':') {
+                has_separator = true;
+            } else {
+                break;
+            }
+        }
+        value_start += 1;
+    }
+
+    (&line[..key_len], &line[value_start..])
+}
+
+/// Resolves `\uXXXX`, `\t`, `\n`, `\r` and `\f`. Any other escaped character
+/// stands for itself. Works on UTF-16 code units, since that is what `\uXXXX`
+/// means.
+fn load_convert(chars: &[char]) -> std::result::Result<Vec<u16>, String> {
+    let mut buf = Vec::with_capacity(chars.len());
+    let mut index = 0;
+
+    while index < chars.len() {
+        let ch = chars[index];
+        index += 1;
+
+        if ch != '\\' {
+            let mut units = [0; 2];
+            buf.extend_from_slice(ch.encode_utf16(&mut units));
+            continue;
+        }
+
+        // a trailing backslash was already removed by logical_lines()
+        let Some(&ch) = chars.get(index) else {
+            break;
+        };
+        index += 1;
+
+        let unit = match ch {
+            'u' => {
+                let Some(digits) = chars.get(index..index + 4) else {
+                    return Err(format!("malformed \\uxxxx encoding: \\u{}", chars[index..].iter().collect::<String>()));
+                };
+                let digits: String = digits.iter().collect();
+                let Ok(unit) = u16::from_str_radix(&digits, 16) else {
+                    return Err(format!("malformed \\uxxxx encoding: \\u{digits}"));
+                };
+                index += 4;
+                unit
+            }
+            't' => '\t' as u16,
+            'r' => '\r' as u16,
+            'n' => '\n' as u16,
+            'f' => '\x0C' as u16,
+            _ => {
+                let mut units = [0; 2];
+                buf.extend_from_slice(ch.encode_utf16(&mut units));
+                continue;
+            }
+        };
+        buf.push(unit);
+    }
+
+    Ok(buf)
+}
+
+/// Java strings may contain unpaired surrogates, ours can't.
+fn decode_utf16(units: Vec<u16>, options: &Options<&Path>, origin: Origin) -> String {
+    match String::from_utf16(&units) {
+        Ok(string) => string,
+        Err(_) => {
+            let string = String::from_utf16_lossy(&units);
+            if options.diagnostics_enabled() {
+                let location = origin.location();
+                options.report(Diagnostic::new(DiagnosticCode::IllegalEscape,
+                    format!("unpaired surrogate replaced by U+FFFD: {string:?}")).
+                    with_severity(Severity::Warning).
+                    at(location.lineno(), location.column()));
+            }
+            string
+        }
+    }
+}
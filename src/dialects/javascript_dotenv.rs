@@ -1,12 +1,18 @@
 // trying to emulate: https://github.com/motdotla/dotenv/blob/8ab33066f90a20445d3c41e4fafba6c929c5e1a5/lib/main.js
 use std::{io::BufRead, path::Path};
 
-use crate::{Env, Error, Options, Result, DEBUG_PREFIX};
+use crate::{Diagnostic, DiagnosticCode, Env, Error, Options, Origin, Result};
 
 pub fn config_javascript_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, options: &Options<&Path>) -> Result<()> {
-    let path_str = options.path.to_string_lossy();
+    parse_javascript_dotenv(reader, options, &mut |key, value, origin| {
+        options.set_var_cut_null(env, key, value, origin);
+    })
+}
+
+/// Parses like `dotenv.parse()` and passes each definition to `set`.
+pub(crate) fn parse_javascript_dotenv(reader: &mut dyn BufRead, options: &Options<&Path>, set: &mut dyn FnMut(&str, &str, Origin)) -> Result<()> {
     let mut buf = String::new();
-    options.encoding.read_to_string(reader, &mut buf)?;
+    options.read_to_string(reader, &mut buf)?;
 
     fix_newlines(&mut buf);
 
@@ -31,6 +37,7 @@ pub fn config_javascript_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, opt
 
         let mut key_start = parser.index;
         let mut key_end = find_vardef_end(&parser.buf, parser.index);
+        let mut origin = Origin::new(parser.lineno, key_start - parser.line_start + 1);
 
         parser.index = key_end;
         parser.skip_ws();
@@ -38,6 +45,7 @@ pub fn config_javascript_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, opt
         if parser.buf[parser.index..].starts_with(is_vardef) && &parser.buf[key_start..key_end] == "export" {
             key_start = parser.index;
             key_end = find_vardef_end(&parser.buf, parser.index);
+            origin = Origin::new(parser.lineno, key_start - parser.line_start + 1);
             parser.index = key_end;
             parser.skip_ws();
         }
@@ -45,14 +53,12 @@ pub fn config_javascript_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, opt
         if key_start == key_end {
             let line_end = find_line_end(&parser.buf, parser.index);
             let column = parser.index - parser.line_start + 1;
-            if options.debug {
+            if options.diagnostics_enabled() {
                 let line = &parser.buf[parser.line_start..line_end];
                 if let Some(ch) = parser.buf[key_start..].chars().next() {
-                    eprintln!("{DEBUG_PREFIX}{path_str}:{}:{}: expected variable name, found {:?}: {}",
-                        parser.lineno, column, ch, line);
+                    options.report(Diagnostic::new(DiagnosticCode::SyntaxError, format!("expected variable name, found {:?}: {}", ch, line)).at(parser.lineno, column));
                 } else {
-                    eprintln!("{DEBUG_PREFIX}{path_str}:{}:{}: unexpected end of file: {}",
-                        parser.lineno, column, line);
+                    options.report(Diagnostic::new(DiagnosticCode::SyntaxError, format!("unexpected end of file: {}", line)).at(parser.lineno, column));
                 }
             }
             if options.strict {
@@ -63,19 +69,17 @@ pub fn config_javascript_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, opt
         }
 
         let tail = &parser.buf[parser.index..];
-        if !tail.starts_with(|ch| ch == '=' || ch == ':') {
+        if !tail.starts_with(['=', ':']) {
             let line_end = find_line_end(&parser.buf, parser.index);
             let column = parser.index - parser.line_start + 1;
             let ch = tail.chars().next();
 
-            if options.debug {
+            if options.diagnostics_enabled() {
                 let line = &parser.buf[parser.line_start..line_end];
                 if let Some(ch) = ch {
-                    eprintln!("{DEBUG_PREFIX}{path_str}:{}:{}: expected '=' or ':', found {:?}: {}",
-                        parser.lineno, column, ch, line);
+                    options.report(Diagnostic::new(DiagnosticCode::SyntaxError, format!("expected '=' or ':', found {:?}: {}", ch, line)).at(parser.lineno, column));
                 } else {
-                    eprintln!("{DEBUG_PREFIX}{path_str}:{}:{}: unexpected end of file: {}",
-                        parser.lineno, column, line);
+                    options.report(Diagnostic::new(DiagnosticCode::SyntaxError, format!("unexpected end of file: {}", line)).at(parser.lineno, column));
                 }
             }
 
@@ -95,10 +99,9 @@ pub fn config_javascript_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, opt
             let line_end = find_line_end(&parser.buf, parser.index);
             let column = parser.index - parser.line_start + 1;
 
-            if options.debug {
+            if options.diagnostics_enabled() {
                 let line = &parser.buf[parser.line_start..line_end];
-                eprintln!("{DEBUG_PREFIX}{path_str}:{}:{}: there may be no space between the variable name and ':': {}",
-                    parser.lineno, column, line);
+                options.report(Diagnostic::new(DiagnosticCode::SyntaxError, format!("there may be no space between the variable name and ':': {}", line)).at(parser.lineno, column));
             }
 
             if options.strict {
@@ -156,10 +159,11 @@ pub fn config_javascript_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, opt
         let quote = value_slice.chars().next().unwrap_or('\0');
 
         let mut value;
-        if value_slice.len() > 1 && matches!(quote, '"' | '\'' | '`') && value_slice.ends_with(quote) {
+        let quoted = value_slice.len() > 1 && matches!(quote, '"' | '\'' | '`') && value_slice.ends_with(quote);
+        if quoted {
             value = parser.buf[value_start + 1..value_end - 1].to_owned();
         } else {
-            value = value_slice.trim_end_matches(|ch| matches!(ch, '\t' | '\x0B' | '\x0C' | ' ')).to_owned();
+            value = value_slice.trim_end_matches(['\t', '\x0B', '\x0C', ' ']).to_owned();
         }
 
         if quote == '"' {
@@ -176,10 +180,9 @@ pub fn config_javascript_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, opt
                 let line_end = find_line_end(&parser.buf, parser.index);
                 let column = parser.index - parser.line_start + 1;
 
-                if options.debug {
+                if options.diagnostics_enabled() {
                     let line = &parser.buf[parser.line_start..line_end];
-                    eprintln!("{DEBUG_PREFIX}{path_str}:{}:{}: expected line end, found {:?}: {}",
-                        parser.lineno, column, ch, line);
+                    options.report(Diagnostic::new(DiagnosticCode::SyntaxError, format!("expected line end, found {:?}: {}", ch, line)).at(parser.lineno, column));
                 }
 
                 if options.strict {
@@ -193,7 +196,7 @@ pub fn config_javascript_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, opt
             }
         }
 
-        options.set_var_cut_null(env, parser.buf[key_start..key_end].as_ref(), value.as_ref());
+        set(&parser.buf[key_start..key_end], &value, origin.quoted(quoted));
     }
 
     Ok(())
@@ -254,11 +257,7 @@ impl Parser {
 
         // Count newlines in the parsed string and set the line_start offset.
         let mut slice = &slice[..end_index];
-        loop {
-            let Some(index) = slice.find('\n') else {
-                break;
-            };
-
+        while let Some(index) = slice.find('\n') {
             self.lineno += 1;
             self.line_start += index + 1;
             slice = &slice[index + 1..];
@@ -291,7 +290,7 @@ fn find_value_end(src: &str, index: usize) -> usize {
     let Some(slice) = src.get(index..) else {
         return src.len();
     };
-    let Some(pos) = slice.find(|ch| ch == '\n' || ch == '#') else {
+    let Some(pos) = slice.find(['\n', '#']) else {
         return src.len();
     };
     pos + index
@@ -310,10 +309,7 @@ fn find_line_end(src: &str, index: usize) -> usize {
 
 fn fix_newlines(buf: &mut String) {
     let mut index = 0;
-    loop {
-        let Some(pos) = buf[index..].find('\r') else {
-            break;
-        };
+    while let Some(pos) = buf[index..].find('\r') {
         let cr_index = pos + index;
 
         if buf[cr_index..].starts_with("\r\n") {
@@ -1,10 +1,182 @@
 pub mod punktum;
 pub mod javascript_dotenv;
+pub mod dotenv_expand;
+pub mod dotenvx;
 pub mod python_dotenv_cli;
 pub mod python_dotenv;
 pub mod nodejs;
+pub mod nodejs20;
+pub mod nodejs24;
 pub mod composego;
+pub mod docker;
 pub mod go_dotenv;
 pub mod binary;
 pub mod ruby_dotenv;
+pub mod php_dotenv;
 pub mod java_dotenv;
+pub mod java_properties;
+pub mod dotenvy;
+pub mod systemd;
+pub mod posix_shell;
+pub mod auto;
+
+use std::{io::BufRead, path::Path};
+
+use crate::{dialect::DialectParser, env::GetEnv, Env, Options, Result};
+
+type ParseFn = fn(&mut dyn BufRead, &mut dyn Env, &dyn GetEnv, &Options<&Path>) -> Result<()>;
+
+pub(crate) struct BuiltinDialect {
+    name: &'static str,
+    aliases: &'static [&'static str],
+    parse: ParseFn,
+}
+
+impl DialectParser for BuiltinDialect {
+    #[inline]
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    #[inline]
+    fn aliases(&self) -> &[&str] {
+        self.aliases
+    }
+
+    #[inline]
+    fn parse(&self, reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dyn GetEnv, options: &Options<&Path>) -> Result<()> {
+        (self.parse)(reader, env, parent, options)
+    }
+}
+
+pub(crate) static PUNKTUM: BuiltinDialect = BuiltinDialect {
+    name: "Punktum",
+    aliases: &[],
+    parse: punktum::config_punktum,
+};
+
+pub(crate) static NODEJS: BuiltinDialect = BuiltinDialect {
+    name: "NodeJS",
+    aliases: &["NodeJS22"],
+    parse: |reader, env, _parent, options| nodejs::config_nodejs(reader, env, options),
+};
+
+pub(crate) static NODEJS20: BuiltinDialect = BuiltinDialect {
+    name: "NodeJS20",
+    aliases: &[],
+    parse: |reader, env, _parent, options| nodejs20::config_nodejs20(reader, env, options),
+};
+
+pub(crate) static NODEJS24: BuiltinDialect = BuiltinDialect {
+    name: "NodeJS24",
+    aliases: &[],
+    parse: |reader, env, _parent, options| nodejs24::config_nodejs24(reader, env, options),
+};
+
+pub(crate) static JAVASCRIPT_DOTENV: BuiltinDialect = BuiltinDialect {
+    name: "JavaScriptDotenv",
+    aliases: &["jsdotenv", "javascript-dotenv", "js-dotenv"],
+    parse: |reader, env, _parent, options| javascript_dotenv::config_javascript_dotenv(reader, env, options),
+};
+
+pub(crate) static DOTENV_EXPAND: BuiltinDialect = BuiltinDialect {
+    name: "DotenvExpand",
+    aliases: &["dotenv-expand"],
+    parse: dotenv_expand::config_dotenv_expand,
+};
+
+pub(crate) static DOTENVX: BuiltinDialect = BuiltinDialect {
+    name: "Dotenvx",
+    aliases: &[],
+    parse: dotenvx::config_dotenvx,
+};
+
+pub(crate) static PYTHON_DOTENV: BuiltinDialect = BuiltinDialect {
+    name: "PythonDotenv",
+    aliases: &["pydotenv", "python-dotenv", "py-dotenv"],
+    parse: |reader, env, _parent, options| python_dotenv::config_python_dotenv(reader, env, options),
+};
+
+pub(crate) static PYTHON_DOTENV_CLI: BuiltinDialect = BuiltinDialect {
+    name: "PythonDotenvCLI",
+    aliases: &["pydotenvcli", "python-dotenv-cli", "py-dotenv-cli"],
+    parse: |reader, env, _parent, options| python_dotenv_cli::config_python_dotenv_cli(reader, env, options),
+};
+
+pub(crate) static COMPOSEGO: BuiltinDialect = BuiltinDialect {
+    name: "ComposeGo",
+    aliases: &["compose-go"],
+    parse: composego::config_composego,
+};
+
+pub(crate) static DOCKER_CLI: BuiltinDialect = BuiltinDialect {
+    name: "DockerCLI",
+    aliases: &["docker", "docker-cli"],
+    parse: docker::config_docker,
+};
+
+pub(crate) static COMPOSE_RAW: BuiltinDialect = BuiltinDialect {
+    name: "ComposeRaw",
+    aliases: &["compose-raw"],
+    parse: docker::config_docker,
+};
+
+pub(crate) static GO_DOTENV: BuiltinDialect = BuiltinDialect {
+    name: "GoDotenv",
+    aliases: &["go-dotenv"],
+    parse: |reader, env, _parent, options| go_dotenv::config_go_dotenv(reader, env, options),
+};
+
+pub(crate) static RUBY_DOTENV: BuiltinDialect = BuiltinDialect {
+    name: "RubyDotenv",
+    aliases: &["ruby-dotenv"],
+    parse: ruby_dotenv::config_ruby_dotenv,
+};
+
+pub(crate) static PHP_DOTENV: BuiltinDialect = BuiltinDialect {
+    name: "PhpDotenv",
+    aliases: &["php-dotenv"],
+    parse: php_dotenv::config_php_dotenv,
+};
+
+pub(crate) static JAVA_DOTENV: BuiltinDialect = BuiltinDialect {
+    name: "JavaDotenv",
+    aliases: &["java-dotenv"],
+    parse: |reader, env, _parent, options| java_dotenv::config_java_dotenv(reader, env, options),
+};
+
+pub(crate) static JAVA_PROPERTIES: BuiltinDialect = BuiltinDialect {
+    name: "JavaProperties",
+    aliases: &["properties"],
+    parse: |reader, env, _parent, options| java_properties::config_java_properties(reader, env, options),
+};
+
+pub(crate) static DOTENVY: BuiltinDialect = BuiltinDialect {
+    name: "Dotenvy",
+    aliases: &[],
+    parse: dotenvy::config_dotenvy,
+};
+
+pub(crate) static SYSTEMD: BuiltinDialect = BuiltinDialect {
+    name: "Systemd",
+    aliases: &["EnvironmentFile"],
+    parse: |reader, env, _parent, options| systemd::config_systemd(reader, env, options),
+};
+
+pub(crate) static POSIX_SHELL: BuiltinDialect = BuiltinDialect {
+    name: "PosixShell",
+    aliases: &["sh"],
+    parse: posix_shell::config_posix_shell,
+};
+
+pub(crate) static BINARY: BuiltinDialect = BuiltinDialect {
+    name: "Binary",
+    aliases: &[],
+    parse: |reader, env, _parent, options| binary::config_binary(reader, env, options),
+};
+
+pub(crate) static AUTO: BuiltinDialect = BuiltinDialect {
+    name: "Auto",
+    aliases: &["detect"],
+    parse: auto::config_auto,
+};
@@ -1,14 +1,15 @@
 use std::{io::BufRead, path::Path};
 
-use crate::{Env, Options, Result};
+use crate::{parse::LineTracker, Env, Options, Result};
 
 // Trying to emulate: https://github.com/nodejs/node/blob/v22.x/src/node_dotenv.cc
 pub fn config_nodejs(reader: &mut dyn BufRead, env: &mut dyn Env, options: &Options<&Path>) -> Result<()> {
     let mut lines = String::new();
-    options.encoding.read_to_string(reader, &mut lines)?;
+    options.read_to_string(reader, &mut lines)?;
 
     lines.retain(|ch| ch != '\r');
     let mut content = lines.trim_matches(' ');
+    let mut tracker = LineTracker::new();
 
     while !content.is_empty() {
         // skip empty lines and comments
@@ -36,11 +37,13 @@ pub fn config_nodejs(reader: &mut dyn BufRead, env: &mut dyn Env, options: &Opti
             key = &key[7..];
         }
 
+        let origin = tracker.origin_of(&lines, key);
+
         // SAFETY: Content is guaranteed to have at least one character
         if content.is_empty() {
             // In case the last line is a single key without value
             // Example: KEY= (without a newline at the EOF)
-            options.set_var(env, key.split('\0').next().unwrap().as_ref(), "".as_ref());
+            options.set_var(env, key.split('\0').next().unwrap().as_ref(), "".as_ref(), origin);
             break;
         }
 
@@ -52,7 +55,8 @@ pub fn config_nodejs(reader: &mut dyn BufRead, env: &mut dyn Env, options: &Opti
                 let multi_line_value = value.replace("\\n", "\n");
                 options.set_var(env,
                     key.split('\0').next().unwrap().as_ref(),
-                    multi_line_value.split('\0').next().unwrap().as_ref());
+                    multi_line_value.split('\0').next().unwrap().as_ref(),
+                    origin.quoted(true));
                 content = &content[closing_quote + 1..];
                 let newline = content.find('\n').unwrap_or(content.len());
                 content = &content[newline..];
@@ -70,7 +74,8 @@ pub fn config_nodejs(reader: &mut dyn BufRead, env: &mut dyn Env, options: &Opti
                 let value = &content[1..closing_quote + 1];
                 options.set_var(env,
                     key.split('\0').next().unwrap().as_ref(),
-                    value.split('\0').next().unwrap().as_ref());
+                    value.split('\0').next().unwrap().as_ref(),
+                    origin.quoted(true));
                 // Select the first newline after the closing quotation mark
                 // since there could be newline characters inside the value.
                 content = &content[closing_quote + 1..];
@@ -84,7 +89,8 @@ pub fn config_nodejs(reader: &mut dyn BufRead, env: &mut dyn Env, options: &Opti
                     let value = &content[..newline];
                     options.set_var(env,
                         key.split('\0').next().unwrap().as_ref(),
-                        value.split('\0').next().unwrap().as_ref());
+                        value.split('\0').next().unwrap().as_ref(),
+                        origin);
                     content = &content[newline..];
                 }
             }
@@ -111,7 +117,8 @@ pub fn config_nodejs(reader: &mut dyn BufRead, env: &mut dyn Env, options: &Opti
             value = value.trim_matches(' ');
             options.set_var(env,
                 key.split('\0').next().unwrap().as_ref(),
-                value.split('\0').next().unwrap().as_ref());
+                value.split('\0').next().unwrap().as_ref(),
+                origin);
         }
     }
 
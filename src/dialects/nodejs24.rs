@@ -0,0 +1,147 @@
+use std::{io::BufRead, path::Path};
+
+use crate::{parse::LineTracker, Env, Options, Result};
+
+// Trying to emulate: https://github.com/nodejs/node/blob/v24.x/src/node_dotenv.cc
+pub fn config_nodejs24(reader: &mut dyn BufRead, env: &mut dyn Env, options: &Options<&Path>) -> Result<()> {
+    let mut lines = String::new();
+    options.read_to_string(reader, &mut lines)?;
+
+    lines.retain(|ch| ch != '\r');
+    let mut content = trim_spaces(&lines);
+    let mut tracker = LineTracker::new();
+
+    while !content.is_empty() {
+        // skip empty lines and comments
+        if content.starts_with('\n') || content.starts_with('#') {
+            content = match content.find('\n') {
+                Some(newline) => &content[newline + 1..],
+                None => "",
+            };
+            continue;
+        }
+
+        // If we found nothing or found a newline before equals, the line is invalid
+        let Some(equal) = content.find(['=', '\n']).filter(|&index| content.as_bytes()[index] == b'=') else {
+            if let Some(newline) = content.find('\n') {
+                content = trim_spaces(&content[newline + 1..]);
+                continue;
+            }
+            break;
+        };
+
+        let raw_key = &content[..equal];
+        content = &content[equal + 1..];
+        let mut key = trim_spaces(raw_key);
+
+        // Skip lines with empty keys after trimming spaces.
+        if key.is_empty() {
+            if let Some(newline) = content.find('\n') {
+                content = trim_spaces(&content[newline + 1..]);
+                continue;
+            }
+            break;
+        }
+
+        // remove export prefix from key and ensure proper spacing
+        if let Some(rest) = key.strip_prefix("export ") {
+            key = trim_spaces(rest);
+        }
+
+        let origin = tracker.origin_of(&lines, key);
+
+        // If the value is not present (e.g. KEY=) set it to an empty string
+        if content.is_empty() || content.starts_with('\n') {
+            options.set_var(env, key.split('\0').next().unwrap().as_ref(), "".as_ref(), origin);
+            continue;
+        }
+
+        content = trim_spaces(content);
+
+        if content.is_empty() {
+            // In case the last line is a single key without value
+            // Example: KEY=  (without a newline at the EOF)
+            options.set_var(env, key.split('\0').next().unwrap().as_ref(), "".as_ref(), origin);
+            break;
+        }
+
+        // Check if the value is wrapped in quotes, single quotes or backticks
+        let front = content.chars().next().unwrap();
+        if front == '\'' || front == '"' || front == '`' {
+            if let Some(closing_quote) = content[1..].find(front) {
+                // Example: KEY="value"
+                let value = &content[1..closing_quote + 1];
+                let value = if front == '"' {
+                    // Expand new line if \n it's inside double quotes
+                    value.replace("\\n", "\n").into()
+                } else {
+                    std::borrow::Cow::Borrowed(value)
+                };
+                options.set_var(env,
+                    key.split('\0').next().unwrap().as_ref(),
+                    value.split('\0').next().unwrap().as_ref(),
+                    origin.quoted(true));
+                // Anything after the closing quote up to the end of the line is
+                // ignored. There could be newline characters inside the value.
+                content = &content[closing_quote + 2..];
+                content = match content.find('\n') {
+                    Some(newline) => &content[newline + 1..],
+                    None => "",
+                };
+            } else if let Some(newline) = content.find('\n') {
+                // Check if newline exist. If it does, take the entire line as the value
+                // Example: KEY="value\nKEY2=value2
+                // The value pair should be `"value`
+                let value = &content[..newline];
+                options.set_var(env,
+                    key.split('\0').next().unwrap().as_ref(),
+                    value.split('\0').next().unwrap().as_ref(),
+                    origin);
+                content = &content[newline..];
+            } else {
+                // No newline - take rest of content
+                options.set_var(env,
+                    key.split('\0').next().unwrap().as_ref(),
+                    content.split('\0').next().unwrap().as_ref(),
+                    origin);
+                break;
+            }
+        } else {
+            // Regular key value pair.
+            // Example: `KEY=this is value`
+            let mut value;
+            if let Some(newline) = content.find('\n') {
+                value = &content[..newline];
+                content = &content[newline + 1..];
+            } else {
+                // In case the last line is a single key/value pair
+                // Example: KEY=VALUE (without a newline at the EOF)
+                value = content;
+                content = "";
+            }
+
+            // Check if there is a comment in the line
+            // Example: KEY=value # comment
+            // The value pair should be `value`
+            if let Some(hash_character) = value.find('#') {
+                value = &value[..hash_character];
+            }
+
+            value = trim_spaces(value);
+            options.set_var(env,
+                key.split('\0').next().unwrap().as_ref(),
+                value.split('\0').next().unwrap().as_ref(),
+                origin);
+        }
+
+        content = trim_spaces(content);
+    }
+
+    Ok(())
+}
+
+/// Only trims spaces, not tabs or newlines.
+#[inline]
+fn trim_spaces(input: &str) -> &str {
+    input.trim_matches(' ')
+}
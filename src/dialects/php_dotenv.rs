@@ -0,0 +1,412 @@
+// trying to emulate: https://github.com/vlucas/phpdotenv/tree/v5.6.1/src
+// using an immutable repository, like Laravel does
+use std::{collections::HashSet, io::BufRead, path::Path};
+
+use crate::{env::GetEnv, error::SourceLocation, parse::LineTracker, Diagnostic, DiagnosticCode, Env, Error, Options, Origin, Result};
+
+/// Characters removed by PHP's `trim()`.
+const PHP_TRIM: &[char] = &[' ', '\t', '\n', '\r', '\0', '\x0B'];
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum State {
+    Initial,
+    Unquoted,
+    SingleQuoted,
+    DoubleQuoted,
+    EscapeSequence,
+    Whitespace,
+    Comment,
+}
+
+#[derive(Debug, Default)]
+struct Value {
+    chars: String,
+    /// Offsets of `$` characters that may start a `${NAME}` reference.
+    vars: Vec<usize>,
+    quoted: bool,
+}
+
+struct Entry {
+    name: String,
+    value: Option<Value>,
+    origin: Origin,
+}
+
+pub fn config_php_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dyn GetEnv, options: &Options<&Path>) -> Result<()> {
+    let mut buf = String::new();
+    options.read_to_string(reader, &mut buf)?;
+    let src = buf.strip_prefix('\u{FEFF}').unwrap_or(&buf);
+
+    let mut tracker = LineTracker::new();
+    let mut entries = Vec::new();
+
+    let (lines, unterminated) = process_lines(src);
+
+    // phpdotenv parses the whole file before it loads anything
+    for (offset, line) in lines {
+        match parse_entry(&line) {
+            Ok((name, name_offset, value)) => {
+                let origin = tracker.origin_at(src, offset + name_offset).
+                    quoted(value.as_ref().is_some_and(|value| value.quoted));
+                entries.push(Entry { name, value, origin });
+            }
+            Err(message) => {
+                let location = tracker.origin_at(src, offset).location();
+                if options.diagnostics_enabled() {
+                    options.report(Diagnostic::new(DiagnosticCode::SyntaxError,
+                        format!("Failed to parse dotenv file. {message}")).
+                        at(location.lineno(), location.column()));
+                }
+                if options.strict {
+                    return Err(Error::syntax_error(location.lineno(), location.column()));
+                }
+            }
+        }
+    }
+
+    if let Some(offset) = unterminated {
+        // phpdotenv silently drops the unterminated value
+        let location = tracker.origin_at(src, offset).location();
+        if options.diagnostics_enabled() {
+            options.report(Diagnostic::new(DiagnosticCode::UnterminatedString,
+                "unterminated multi-line value is ignored").
+                at(location.lineno(), location.column()));
+        }
+        if options.strict {
+            return Err(Error::syntax_error(location.lineno(), location.column()));
+        }
+    }
+
+    // The immutable repository may still overwrite what it loaded itself.
+    let mut loaded = HashSet::new();
+
+    for entry in entries {
+        let name = entry.name.as_str();
+        let writable = options.override_env || loaded.contains(name) || env.get(name.as_ref()).is_none();
+
+        match entry.value {
+            Some(value) => {
+                let substituted = !value.vars.is_empty();
+                let value = resolve(value, env, parent, options, entry.origin.location())?;
+                let origin = entry.origin.substituted(substituted);
+
+                if writable {
+                    let value = value.split('\0').next().unwrap();
+                    env.set_from(name.as_ref(), value.as_ref(), &origin);
+                    loaded.insert(entry.name);
+                } else {
+                    // reports that it wasn't overwritten
+                    options.set_var_cut_null(env, name, &value, origin);
+                }
+            }
+            None => {
+                // `NAME` without `=` unsets the variable
+                if writable {
+                    env.remove(name.as_ref());
+                    loaded.remove(name);
+                } else if options.diagnostics_enabled() {
+                    let location = entry.origin.location();
+                    options.report(Diagnostic::new(DiagnosticCode::NotOverwritten,
+                        format!("{name:?} is already defined and was NOT unset")).
+                        at(location.lineno(), location.column()));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Emulates `Regex::split("/(\r\n|\n|\r)/", $content)`.
+fn split_lines(src: &str) -> Vec<(usize, &str)> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let bytes = src.as_bytes();
+    let mut index = 0;
+
+    while index < bytes.len() {
+        match bytes[index] {
+            b'\r' => {
+                lines.push((start, &src[start..index]));
+                if bytes.get(index + 1) == Some(&b'\n') {
+                    index += 1;
+                }
+                start = index + 1;
+            }
+            b'\n' => {
+                lines.push((start, &src[start..index]));
+                start = index + 1;
+            }
+            _ => {}
+        }
+        index += 1;
+    }
+    lines.push((start, &src[start..]));
+
+    lines
+}
+
+/// Emulates `Lines::process()`, which joins multi-line double quoted values
+/// and drops comments and empty lines. Also returns the offset of an
+/// unterminated multi-line value.
+fn process_lines(src: &str) -> (Vec<(usize, String)>, Option<usize>) {
+    let mut output = Vec::new();
+    let mut multiline = false;
+    let mut buffer = Vec::new();
+    let mut buffer_offset = 0;
+
+    for (offset, line) in split_lines(src) {
+        let starts_on_current_line = !multiline && looks_like_multiline_start(line);
+
+        if starts_on_current_line {
+            multiline = true;
+            buffer_offset = offset;
+        }
+
+        if multiline {
+            buffer.push(line);
+
+            if looks_like_multiline_stop(line, starts_on_current_line) {
+                multiline = false;
+                let line = buffer.join("\n");
+                buffer.clear();
+                if !is_comment_or_whitespace(&line) {
+                    output.push((buffer_offset, line));
+                }
+            }
+        } else if !is_comment_or_whitespace(line) {
+            output.push((offset, line.to_owned()));
+        }
+    }
+
+    (output, if multiline { Some(buffer_offset) } else { None })
+}
+
+#[inline]
+fn looks_like_multiline_start(line: &str) -> bool {
+    line.contains("=\"") && !looks_like_multiline_stop(line, true)
+}
+
+fn looks_like_multiline_stop(line: &str, started: bool) -> bool {
+    if line == "\"" {
+        return true;
+    }
+
+    // counts matches of /(?=([^\\]"))/ after removing all `\\`
+    let line = line.replace("\\\\", "");
+    let count = line.as_bytes().windows(2).
+        filter(|pair| pair[0] != b'\\' && pair[1] == b'"').
+        count();
+
+    if started { count > 1 } else { count >= 1 }
+}
+
+#[inline]
+fn is_comment_or_whitespace(line: &str) -> bool {
+    let line = line.trim_matches(PHP_TRIM);
+    line.is_empty() || line.starts_with('#')
+}
+
+#[inline]
+fn error_message(cause: &str, subject: &str) -> String {
+    let subject = subject.split('\n').next().unwrap_or_default();
+    format!("Encountered {cause} at [{subject}].")
+}
+
+/// Emulates `EntryParser::parse()`. Returns the name, the offset of the
+/// name in `line` and the value, which is `None` for a line without `=`.
+fn parse_entry(line: &str) -> std::result::Result<(String, usize, Option<Value>), String> {
+    let (mut name, value) = match line.split_once('=') {
+        Some((name, value)) => (name.trim_matches(PHP_TRIM), Some(value.trim_matches(PHP_TRIM))),
+        None => (line, None),
+    };
+
+    if name.is_empty() {
+        return Err(error_message("an unexpected equals", line));
+    }
+
+    if name.chars().count() > 8 && name.starts_with("export") && name[6..].starts_with(is_ctype_space) {
+        name = name[6..].trim_start_matches(PHP_TRIM);
+    }
+
+    if is_quoted_name(name) {
+        name = &name[1..name.len() - 1];
+    }
+
+    if !is_valid_name(name) {
+        return Err(error_message("an invalid name", name));
+    }
+
+    let name_offset = name.as_ptr() as usize - line.as_ptr() as usize;
+
+    let value = match value {
+        Some(value) => Some(parse_value(value).map_err(|cause| error_message(cause, value))?),
+        None => None,
+    };
+
+    Ok((name.to_owned(), name_offset, value))
+}
+
+#[inline]
+fn is_ctype_space(ch: char) -> bool {
+    matches!(ch, ' ' | '\t' | '\n' | '\r' | '\x0B' | '\x0C')
+}
+
+fn is_quoted_name(name: &str) -> bool {
+    if name.chars().count() < 3 {
+        return false;
+    }
+
+    (name.starts_with('"') && name.ends_with('"')) ||
+    (name.starts_with('\'') && name.ends_with('\''))
+}
+
+/// Emulates `/\A[\p{Ll}\p{Lu}\p{M}\p{N}_.]+\z/u`, with the combining mark
+/// blocks standing in for `\p{M}`.
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|ch|
+        ch == '_' || ch == '.' ||
+        ch.is_lowercase() || ch.is_uppercase() || ch.is_numeric() ||
+        matches!(ch, '\u{0300}'..='\u{036F}' | '\u{1AB0}'..='\u{1AFF}' | '\u{1DC0}'..='\u{1DFF}' | '\u{20D0}'..='\u{20FF}' | '\u{FE20}'..='\u{FE2F}'))
+}
+
+/// Emulates `Lexer::lex()`.
+fn lex(value: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut rest = value;
+
+    while let Some(ch) = rest.chars().next() {
+        let len = match ch {
+            '\r' | '\n' => rest.find(|ch| ch != '\r' && ch != '\n').unwrap_or(rest.len()),
+            ' ' | '\t' | '\x0B' | '\x0C' => rest.find(|ch| !matches!(ch, ' ' | '\t' | '\x0B' | '\x0C')).unwrap_or(rest.len()),
+            '\\' | '\'' | '"' | '#' | '$' => 1,
+            _ => rest.find(|ch: char| is_ctype_space(ch) || matches!(ch, '\\' | '\'' | '"' | '#' | '$')).unwrap_or(rest.len()),
+        };
+        tokens.push(&rest[..len]);
+        rest = &rest[len..];
+    }
+
+    tokens
+}
+
+/// Emulates `EntryParser::parseValue()`.
+fn parse_value(value: &str) -> std::result::Result<Value, &'static str> {
+    let mut result = Value::default();
+
+    if value.trim_matches(PHP_TRIM).is_empty() {
+        return Ok(result);
+    }
+
+    let mut state = State::Initial;
+
+    for token in lex(value) {
+        let is_space = token.starts_with(is_ctype_space);
+
+        state = match state {
+            State::Initial | State::Unquoted => {
+                match token {
+                    "'" if state == State::Initial => {
+                        result.quoted = true;
+                        State::SingleQuoted
+                    }
+                    "\"" if state == State::Initial => {
+                        result.quoted = true;
+                        State::DoubleQuoted
+                    }
+                    "#" => State::Comment,
+                    _ if is_space && state == State::Unquoted => State::Whitespace,
+                    _ => {
+                        if token == "$" {
+                            result.vars.push(result.chars.len());
+                        }
+                        result.chars.push_str(token);
+                        State::Unquoted
+                    }
+                }
+            }
+            State::SingleQuoted => {
+                if token == "'" {
+                    State::Whitespace
+                } else {
+                    result.chars.push_str(token);
+                    State::SingleQuoted
+                }
+            }
+            State::DoubleQuoted => {
+                match token {
+                    "\"" => State::Whitespace,
+                    "\\" => State::EscapeSequence,
+                    _ => {
+                        if token == "$" {
+                            result.vars.push(result.chars.len());
+                        }
+                        result.chars.push_str(token);
+                        State::DoubleQuoted
+                    }
+                }
+            }
+            State::EscapeSequence => {
+                match token {
+                    "\"" | "\\" | "$" => result.chars.push_str(token),
+                    _ => {
+                        let unescaped = match token.as_bytes()[0] {
+                            b'f' => '\x0C',
+                            b'n' => '\n',
+                            b'r' => '\r',
+                            b't' => '\t',
+                            b'v' => '\x0B',
+                            _ => return Err("an unexpected escape sequence"),
+                        };
+                        result.chars.push(unescaped);
+                        result.chars.push_str(&token[1..]);
+                    }
+                }
+                State::DoubleQuoted
+            }
+            State::Whitespace => {
+                if token == "#" {
+                    State::Comment
+                } else if !is_space {
+                    return Err("unexpected whitespace");
+                } else {
+                    State::Whitespace
+                }
+            }
+            State::Comment => State::Comment,
+        };
+    }
+
+    if matches!(state, State::SingleQuoted | State::DoubleQuoted | State::EscapeSequence) {
+        return Err("a missing closing quote");
+    }
+
+    Ok(result)
+}
+
+/// Emulates `Resolver::resolve()`. References are resolved from the last to
+/// the first, each only matching `${NAME}` right at its `$`.
+fn resolve(value: Value, env: &dyn Env, parent: &dyn GetEnv, options: &Options<&Path>, location: SourceLocation) -> Result<String> {
+    let mut chars = value.chars;
+
+    for &index in value.vars.iter().rev() {
+        let tail = &chars[index..];
+        let Some(name) = tail.strip_prefix("${") else {
+            continue;
+        };
+        let name_len = name.find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_' || ch == '.')).unwrap_or(name.len());
+        if name_len == 0 || !name[name_len..].starts_with('}') {
+            continue;
+        }
+        let name = &name[..name_len];
+
+        let Some(replacement) = env.get(name.as_ref()).or_else(|| parent.get(name.as_ref())) else {
+            continue;
+        };
+        let replacement = options.var_value_str(name, &replacement, location)?.into_owned();
+
+        let end = index + 2 + name_len + 1;
+        chars.replace_range(index..end, &replacement);
+    }
+
+    Ok(chars)
+}
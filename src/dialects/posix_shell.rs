@@ -0,0 +1,851 @@
+// Evaluates the static subset of POSIX sh that is used by files which are
+// meant to be loaded via `set -a; . ./.env`. Anything that would need to run
+// a program or depends on the state of the shell (command substitution,
+// arithmetic, special parameters, any command other than assignments and
+// `export`) is refused with an error instead of being guessed at.
+//
+// Where POSIX leaves things unspecified the behavior of dash is emulated.
+use std::{io::BufRead, path::Path};
+
+use crate::{env::GetEnv, parse::LineTracker, Diagnostic, DiagnosticCode, Env, Error, ErrorKind, Options, Result};
+
+const OPERATORS: &[char] = &['&', '|', '<', '>', '(', ')'];
+
+pub fn config_posix_shell(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dyn GetEnv, options: &Options<&Path>) -> Result<()> {
+    let mut src = String::new();
+    options.read_to_string(reader, &mut src)?;
+
+    let mut parser = Parser {
+        src: &src,
+        index: 0,
+    };
+    let mut tracker = LineTracker::new();
+
+    while parser.index < src.len() {
+        let command = match parser.parse_command() {
+            Ok(command) => command,
+            Err(error) => {
+                let offset = error.offset;
+                report(&mut tracker, &src, options, error)?;
+                // A shell can't recover from a syntax error. Be lenient and
+                // continue with the next line.
+                parser.skip_line(offset);
+                continue;
+            }
+        };
+
+        let mut eval = Eval { env: &mut *env, parent, options, tracker: &mut tracker, src: &src };
+        if let Err(error) = eval.command(&command) {
+            report(&mut tracker, &src, options, error)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn report(tracker: &mut LineTracker, src: &str, options: &Options<&Path>, error: ShellError) -> Result<()> {
+    if let Some(error) = error.reported {
+        return Err(error);
+    }
+
+    let location = tracker.origin_at(src, error.offset).location();
+    if options.diagnostics_enabled() {
+        options.report(Diagnostic::new(error.code, error.message.clone()).
+            at(location.lineno(), location.column()));
+    }
+    if options.strict {
+        return Err(Error::new(error.kind, error.message, location));
+    }
+    Ok(())
+}
+
+#[derive(Debug)]
+struct ShellError {
+    kind: ErrorKind,
+    code: DiagnosticCode,
+    message: String,
+    offset: usize,
+    /// An error that was already reported, e.g. by [`Options::var_value_str`].
+    reported: Option<Error>,
+}
+
+impl ShellError {
+    #[inline]
+    fn syntax(offset: usize, message: impl Into<String>) -> Self {
+        Self { kind: ErrorKind::SyntaxError, code: DiagnosticCode::SyntaxError, message: message.into(), offset, reported: None }
+    }
+
+    #[inline]
+    fn unterminated(offset: usize) -> Self {
+        Self {
+            kind: ErrorKind::SyntaxError,
+            code: DiagnosticCode::UnterminatedString,
+            message: "unterminated quoted string".into(),
+            offset,
+            reported: None,
+        }
+    }
+
+    #[inline]
+    fn substitution(offset: usize, message: impl Into<String>) -> Self {
+        Self { kind: ErrorKind::SubstitutionError, code: DiagnosticCode::SubstitutionError, message: message.into(), offset, reported: None }
+    }
+
+    #[inline]
+    fn command(offset: usize, message: impl Into<String>) -> Self {
+        Self { kind: ErrorKind::SubstitutionError, code: DiagnosticCode::CommandSubstitution, message: message.into(), offset, reported: None }
+    }
+
+    #[inline]
+    fn reported(offset: usize, error: Error) -> Self {
+        Self {
+            kind: error.kind(),
+            code: DiagnosticCode::EncodingError,
+            message: error.to_string(),
+            offset,
+            reported: Some(error),
+        }
+    }
+}
+
+type ShellResult<T> = std::result::Result<T, ShellError>;
+
+#[derive(Debug)]
+enum Part {
+    /// `quoted` text is matched literally when used in a pattern.
+    Text { text: String, quoted: bool },
+    /// A `~` tilde-prefix without user name.
+    Tilde { offset: usize },
+    Param(Box<Param>),
+}
+
+type Word = Vec<Part>;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Op {
+    Plain,
+    Length,
+    /// `${NAME:-word}` and `${NAME-word}`
+    Default { colon: bool },
+    /// `${NAME:=word}` and `${NAME=word}`
+    Assign { colon: bool },
+    /// `${NAME:?word}` and `${NAME?word}`
+    Error { colon: bool },
+    /// `${NAME:+word}` and `${NAME+word}`
+    Alternative { colon: bool },
+    /// `${NAME%word}`
+    RemoveSmallestSuffix,
+    /// `${NAME%%word}`
+    RemoveLargestSuffix,
+    /// `${NAME#word}`
+    RemoveSmallestPrefix,
+    /// `${NAME##word}`
+    RemoveLargestPrefix,
+}
+
+#[derive(Debug)]
+struct Param {
+    name: String,
+    op: Op,
+    word: Word,
+    quoted: bool,
+    offset: usize,
+}
+
+#[derive(Debug)]
+enum Item {
+    Assignment { name: String, value: Word, offset: usize },
+    Word { word: Word, offset: usize },
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Context {
+    /// A top-level word, terminated by blanks and operators.
+    Unquoted { assignment: bool },
+    /// The word of a `${NAME<op>word}` expansion, terminated by `}`.
+    /// Inside of double quotes single quotes only quote in patterns.
+    Brace { dquote: bool, pattern: bool },
+}
+
+#[inline]
+fn is_name_start(ch: char) -> bool {
+    ch.is_ascii_alphabetic() || ch == '_'
+}
+
+#[inline]
+fn is_name_char(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || ch == '_'
+}
+
+#[inline]
+fn is_special_param(ch: char) -> bool {
+    ch.is_ascii_digit() || "@*#?-$!".contains(ch)
+}
+
+#[inline]
+fn is_blank(ch: char) -> bool {
+    ch == ' ' || ch == '\t'
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    index: usize,
+}
+
+impl<'a> Parser<'a> {
+    #[inline]
+    fn peek(&self) -> Option<char> {
+        self.src[self.index..].chars().next()
+    }
+
+    #[inline]
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.src[self.index..].chars().nth(offset)
+    }
+
+    #[inline]
+    fn next(&mut self) -> Option<char> {
+        let ch = self.peek()?;
+        self.index += ch.len_utf8();
+        Some(ch)
+    }
+
+    fn skip_line(&mut self, offset: usize) {
+        self.index = match self.src[offset..].find('\n') {
+            Some(index) => offset + index + 1,
+            None => self.src.len(),
+        };
+    }
+
+    /// Skip `\<newline>` line continuations.
+    #[inline]
+    fn skip_continuations(&mut self) {
+        while self.src[self.index..].starts_with("\\\n") {
+            self.index += 2;
+        }
+    }
+
+    fn skip_blanks(&mut self) {
+        loop {
+            self.skip_continuations();
+            match self.peek() {
+                Some(ch) if is_blank(ch) => { self.index += 1; }
+                _ => break,
+            }
+        }
+    }
+
+    /// Parses one simple command up to and including its terminating
+    /// newline or `;`. An empty line yields an empty command.
+    fn parse_command(&mut self) -> ShellResult<Vec<Item>> {
+        let mut items = Vec::new();
+
+        loop {
+            self.skip_blanks();
+            let offset = self.index;
+            match self.peek() {
+                None => break,
+                Some('\n') => {
+                    self.index += 1;
+                    break;
+                }
+                Some(';') => {
+                    if items.is_empty() {
+                        return Err(ShellError::syntax(offset, "syntax error: \";\" unexpected"));
+                    }
+                    self.index += 1;
+                    break;
+                }
+                Some('#') => {
+                    self.skip_line(offset);
+                    break;
+                }
+                Some(ch) if OPERATORS.contains(&ch) => {
+                    return Err(ShellError::syntax(offset, format!("{ch:?} is not supported, only variable assignments are allowed")));
+                }
+                Some(_) => {
+                    let name_end = self.src[offset..].
+                        find(|ch: char| !is_name_char(ch)).
+                        map(|index| offset + index).
+                        unwrap_or(self.src.len());
+
+                    let name = &self.src[offset..name_end];
+                    if !name.is_empty() && name.starts_with(is_name_start) && self.src[name_end..].starts_with('=') {
+                        self.index = name_end + 1;
+                        let value = self.parse_word(Context::Unquoted { assignment: true })?;
+                        items.push(Item::Assignment { name: name.to_owned(), value, offset });
+                    } else {
+                        let word = self.parse_word(Context::Unquoted { assignment: false })?;
+                        items.push(Item::Word { word, offset });
+                    }
+                }
+            }
+        }
+
+        Ok(items)
+    }
+
+    /// Parses a word in the given context.
+    fn parse_word(&mut self, context: Context) -> ShellResult<Word> {
+        let mut word = Word::new();
+        let mut tilde_ok = true;
+
+        loop {
+            self.skip_continuations();
+            let offset = self.index;
+            let Some(ch) = self.peek() else {
+                if let Context::Brace { .. } = context {
+                    return Err(ShellError::syntax(offset, "bad substitution: missing \"}\""));
+                }
+                break;
+            };
+
+            match context {
+                Context::Unquoted { .. } => {
+                    if is_blank(ch) || ch == '\n' || ch == ';' || OPERATORS.contains(&ch) {
+                        break;
+                    }
+                }
+                Context::Brace { .. } => {
+                    if ch == '}' {
+                        self.index += 1;
+                        break;
+                    }
+                }
+            }
+
+            // no tilde expansion inside of double quotes, except for patterns
+            if ch == '~' && tilde_ok && context != (Context::Brace { dquote: true, pattern: false }) {
+                tilde_ok = false;
+                let offset = self.index;
+                if self.parse_tilde(context)? {
+                    word.push(Part::Tilde { offset });
+                    continue;
+                }
+            }
+            tilde_ok = false;
+
+            match ch {
+                '\\' => {
+                    if let Context::Brace { dquote: true, pattern } = context {
+                        match self.peek_at(1) {
+                            Some(ch) if "$`\"\\}".contains(ch) || (pattern && ch == '\'') => {
+                                self.index += 2;
+                                push_text(&mut word, ch, true);
+                            }
+                            _ => {
+                                // the backslash is kept and escapes the next
+                                // character in a pattern
+                                self.index += 1;
+                                push_text(&mut word, '\\', false);
+                            }
+                        }
+                    } else {
+                        self.index += 1;
+                        match self.next() {
+                            None => push_text(&mut word, '\\', false),
+                            Some(ch) => push_text(&mut word, ch, true),
+                        }
+                    }
+                }
+                '\'' if context != (Context::Brace { dquote: true, pattern: false }) => {
+                    self.index += 1;
+                    let Some(end) = self.src[self.index..].find('\'') else {
+                        return Err(ShellError::unterminated(offset));
+                    };
+                    let text = &self.src[self.index..self.index + end];
+                    word.push(Part::Text { text: text.to_owned(), quoted: true });
+                    self.index += end + 1;
+                }
+                '"' => {
+                    self.index += 1;
+                    self.parse_dquote(&mut word)?;
+                }
+                '$' => {
+                    self.index += 1;
+                    self.parse_dollar(&mut word, false)?;
+                }
+                '`' => {
+                    return Err(ShellError::command(offset, "command substitution is not supported"));
+                }
+                _ => {
+                    self.index += ch.len_utf8();
+                    push_text(&mut word, ch, false);
+                    if ch == ':' && context == (Context::Unquoted { assignment: true }) {
+                        tilde_ok = true;
+                    }
+                }
+            }
+        }
+
+        Ok(word)
+    }
+
+    /// Returns `true` and consumes the tilde-prefix if the `~` at the
+    /// current position is expanded.
+    fn parse_tilde(&mut self, context: Context) -> ShellResult<bool> {
+        let offset = self.index;
+        let mut user = String::new();
+        let mut tail = &self.src[offset + 1..];
+        loop {
+            if let Some(rest) = tail.strip_prefix("\\\n") {
+                tail = rest;
+                continue;
+            }
+            let Some(ch) = tail.chars().next() else {
+                break;
+            };
+            let end = match context {
+                Context::Unquoted { assignment } =>
+                    ch == '/' || (assignment && ch == ':') || is_blank(ch) || ch == '\n' || ch == ';' || OPERATORS.contains(&ch),
+                Context::Brace { .. } => ch == '/' || ch == '}',
+            };
+            if end {
+                break;
+            }
+            user.push(ch);
+            tail = &tail[ch.len_utf8()..];
+        }
+
+        if user.contains(['\\', '\'', '"', '$', '`']) {
+            // a quoted tilde-prefix isn't expanded
+            return Ok(false);
+        }
+
+        if !user.is_empty() {
+            return Err(ShellError::substitution(offset, format!("~{user} expansion is not supported")));
+        }
+
+        self.index += 1;
+        Ok(true)
+    }
+
+    /// Parses the rest of a double quoted string after the opening `"`.
+    fn parse_dquote(&mut self, word: &mut Word) -> ShellResult<()> {
+        let start = self.index - 1;
+
+        loop {
+            let offset = self.index;
+            let Some(ch) = self.next() else {
+                return Err(ShellError::unterminated(start));
+            };
+
+            match ch {
+                '"' => {
+                    // make sure that `""` is recorded as being quoted
+                    word.push(Part::Text { text: String::new(), quoted: true });
+                    break;
+                }
+                '\\' => {
+                    match self.peek() {
+                        None => return Err(ShellError::unterminated(start)),
+                        Some('\n') => { self.index += 1; }
+                        Some(ch) if "$`\"\\".contains(ch) => {
+                            self.index += 1;
+                            push_text(word, ch, true);
+                        }
+                        Some(_) => push_text(word, '\\', true),
+                    }
+                }
+                '$' => self.parse_dollar(word, true)?,
+                '`' => {
+                    return Err(ShellError::command(offset, "command substitution is not supported"));
+                }
+                _ => push_text(word, ch, true),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Parses a parameter expansion after the `$`.
+    fn parse_dollar(&mut self, word: &mut Word, quoted: bool) -> ShellResult<()> {
+        let offset = self.index - 1;
+
+        match self.peek() {
+            Some('{') => {
+                self.index += 1;
+                let param = self.parse_brace(offset, quoted)?;
+                word.push(Part::Param(Box::new(param)));
+            }
+            Some('(') => {
+                if self.peek_at(1) == Some('(') {
+                    return Err(ShellError::substitution(offset, "arithmetic expansion is not supported"));
+                }
+                return Err(ShellError::command(offset, "command substitution is not supported"));
+            }
+            Some(ch) if is_name_start(ch) => {
+                let name = self.parse_name();
+                word.push(Part::Param(Box::new(Param { name, op: Op::Plain, word: Word::new(), quoted, offset })));
+            }
+            Some(ch) if is_special_param(ch) => {
+                return Err(ShellError::substitution(offset, format!("special parameter ${ch} is not supported")));
+            }
+            _ => push_text(word, '$', quoted),
+        }
+
+        Ok(())
+    }
+
+    fn parse_name(&mut self) -> String {
+        let tail = &self.src[self.index..];
+        let end = tail.find(|ch: char| !is_name_char(ch)).unwrap_or(tail.len());
+        self.index += end;
+        tail[..end].to_owned()
+    }
+
+    /// Parses a `${...}` expansion after the `{`.
+    fn parse_brace(&mut self, offset: usize, quoted: bool) -> ShellResult<Param> {
+        let mut op = Op::Plain;
+
+        if self.peek() == Some('#') && self.peek_at(1).is_some_and(is_name_start) {
+            self.index += 1;
+            op = Op::Length;
+        }
+
+        let name = match self.peek() {
+            Some(ch) if is_name_start(ch) => self.parse_name(),
+            Some(ch) if is_special_param(ch) => {
+                return Err(ShellError::substitution(offset, format!("special parameter ${ch} is not supported")));
+            }
+            _ => return Err(ShellError::syntax(offset, "bad substitution")),
+        };
+
+        if op == Op::Length {
+            if self.next() != Some('}') {
+                return Err(ShellError::syntax(offset, "bad substitution"));
+            }
+            return Ok(Param { name, op, word: Word::new(), quoted, offset });
+        }
+
+        let colon = self.peek() == Some(':');
+        if colon {
+            self.index += 1;
+        }
+
+        op = match (self.next(), colon) {
+            (Some('}'), false) => {
+                return Ok(Param { name, op: Op::Plain, word: Word::new(), quoted, offset });
+            }
+            (Some('-'), _) => Op::Default { colon },
+            (Some('='), _) => Op::Assign { colon },
+            (Some('?'), _) => Op::Error { colon },
+            (Some('+'), _) => Op::Alternative { colon },
+            (Some('%'), false) => {
+                if self.peek() == Some('%') {
+                    self.index += 1;
+                    Op::RemoveLargestSuffix
+                } else {
+                    Op::RemoveSmallestSuffix
+                }
+            }
+            (Some('#'), false) => {
+                if self.peek() == Some('#') {
+                    self.index += 1;
+                    Op::RemoveLargestPrefix
+                } else {
+                    Op::RemoveSmallestPrefix
+                }
+            }
+            _ => return Err(ShellError::syntax(offset, "bad substitution")),
+        };
+
+        let pattern = matches!(op,
+            Op::RemoveSmallestSuffix | Op::RemoveLargestSuffix |
+            Op::RemoveSmallestPrefix | Op::RemoveLargestPrefix);
+        let word = self.parse_word(Context::Brace { dquote: quoted, pattern })?;
+
+        Ok(Param { name, op, word, quoted, offset })
+    }
+}
+
+#[inline]
+fn push_text(word: &mut Word, ch: char, quoted: bool) {
+    if let Some(Part::Text { text, quoted: last_quoted }) = word.last_mut() {
+        if *last_quoted == quoted {
+            text.push(ch);
+            return;
+        }
+    }
+    word.push(Part::Text { text: ch.into(), quoted });
+}
+
+/// Expanded text with a flag per character whether it is matched literally
+/// when used as a pattern.
+type Expanded = Vec<(char, bool)>;
+
+struct Eval<'a> {
+    env: &'a mut dyn Env,
+    parent: &'a dyn GetEnv,
+    options: &'a Options<&'a Path>,
+    tracker: &'a mut LineTracker,
+    src: &'a str,
+}
+
+impl<'a> Eval<'a> {
+    fn get(&mut self, name: &str, offset: usize) -> ShellResult<Option<String>> {
+        let Some(value) = self.env.get(name.as_ref()).or_else(|| self.parent.get(name.as_ref())) else {
+            return Ok(None);
+        };
+        let location = self.tracker.origin_at(self.src, offset).location();
+        let value = self.options.var_value_str(name, &value, location).
+            map_err(|error| ShellError::reported(offset, error))?;
+        // the shell can't represent null bytes
+        let value = match value.find('\0') {
+            Some(index) => &value[..index],
+            None => &value,
+        };
+        Ok(Some(value.to_owned()))
+    }
+
+    fn set(&mut self, name: &str, value: &str, offset: usize, quoted: bool, substituted: bool) {
+        let origin = self.tracker.origin_at(self.src, offset).quoted(quoted).substituted(substituted);
+        self.options.set_var_cut_null(self.env, name, value, origin);
+    }
+
+    fn command(&mut self, items: &[Item]) -> ShellResult<()> {
+        let export = matches!(items.first(), Some(Item::Word { word, .. }) if literal(word) == Some("export"));
+        let items = if export { &items[1..] } else { items };
+
+        for item in items {
+            if let Item::Word { word, offset } = item {
+                if !export {
+                    return Err(ShellError::syntax(*offset, "commands are not supported, only variable assignments are allowed"));
+                }
+                // With `set -a` every variable is exported anyway.
+                let valid = literal(word).is_some_and(|name|
+                    name.starts_with(is_name_start) && name.chars().all(is_name_char));
+                if !valid {
+                    return Err(ShellError::syntax(*offset, "export: only variable names and assignments are supported"));
+                }
+            }
+        }
+
+        for item in items {
+            if let Item::Assignment { name, value: word, offset } = item {
+                let value = self.expand_word(word)?;
+                self.set(name, &value, *offset, is_quoted(word), is_substituted(word));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn expand(&mut self, word: &Word, out: &mut Expanded) -> ShellResult<()> {
+        for part in word {
+            match part {
+                Part::Text { text, quoted } => {
+                    out.extend(text.chars().map(|ch| (ch, *quoted)));
+                }
+                Part::Tilde { offset } => {
+                    match self.get("HOME", *offset)? {
+                        Some(home) => out.extend(home.chars().map(|ch| (ch, true))),
+                        None => out.push(('~', true)),
+                    }
+                }
+                Part::Param(param) => {
+                    self.expand_param(param, out)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn expand_word(&mut self, word: &Word) -> ShellResult<String> {
+        let mut expanded = Expanded::new();
+        self.expand(word, &mut expanded)?;
+        Ok(expanded.into_iter().map(|(ch, _)| ch).collect())
+    }
+
+    fn expand_param(&mut self, param: &Param, out: &mut Expanded) -> ShellResult<()> {
+        let value = self.get(&param.name, param.offset)?;
+        let is_set = |colon: bool| match &value {
+            Some(value) => !colon || !value.is_empty(),
+            None => false,
+        };
+
+        let result = match param.op {
+            Op::Plain => value.unwrap_or_default(),
+            Op::Length => value.unwrap_or_default().chars().count().to_string(),
+            Op::Default { colon } => {
+                if is_set(colon) {
+                    value.unwrap_or_default()
+                } else {
+                    // keep the pattern flags of the default word
+                    return self.expand(&param.word, out);
+                }
+            }
+            Op::Assign { colon } => {
+                if is_set(colon) {
+                    value.unwrap_or_default()
+                } else {
+                    let value = self.expand_word(&param.word)?;
+                    self.set(&param.name, &value, param.offset, false, true);
+                    // not overwritten values are still seen by the shell, so read it back
+                    self.get(&param.name, param.offset)?.unwrap_or(value)
+                }
+            }
+            Op::Error { colon } => {
+                if is_set(colon) {
+                    value.unwrap_or_default()
+                } else {
+                    let mut message = self.expand_word(&param.word)?;
+                    if message.is_empty() {
+                        message = if colon { "parameter null or not set" } else { "parameter not set" }.to_owned();
+                    }
+                    return Err(ShellError::substitution(param.offset, format!("{}: {message}", param.name)));
+                }
+            }
+            Op::Alternative { colon } => {
+                if is_set(colon) {
+                    return self.expand(&param.word, out);
+                }
+                String::new()
+            }
+            Op::RemoveSmallestSuffix |
+            Op::RemoveLargestSuffix |
+            Op::RemoveSmallestPrefix |
+            Op::RemoveLargestPrefix => {
+                // Like dash the pattern isn't even expanded if the variable is
+                // unset, so any `${NAME=word}` in it has no effect.
+                let Some(value) = value else {
+                    return Ok(());
+                };
+                let value: Vec<char> = value.chars().collect();
+                let mut pattern = Expanded::new();
+                self.expand(&param.word, &mut pattern)?;
+                let len = value.len();
+
+                let range = match param.op {
+                    Op::RemoveSmallestSuffix => (0..=len).rev().find(|&index| matches(&pattern, &value[index..])).map(|index| 0..index),
+                    Op::RemoveLargestSuffix  => (0..=len).find(|&index| matches(&pattern, &value[index..])).map(|index| 0..index),
+                    Op::RemoveSmallestPrefix => (0..=len).find(|&index| matches(&pattern, &value[..index])).map(|index| index..len),
+                    _                        => (0..=len).rev().find(|&index| matches(&pattern, &value[..index])).map(|index| index..len),
+                };
+
+                match range {
+                    Some(range) => value[range].iter().collect(),
+                    None => value.iter().collect(),
+                }
+            }
+        };
+
+        // The result of an unquoted expansion is active in patterns.
+        out.extend(result.chars().map(|ch| (ch, param.quoted)));
+
+        Ok(())
+    }
+}
+
+fn is_quoted(word: &Word) -> bool {
+    word.iter().any(|part| matches!(part, Part::Text { quoted: true, .. }))
+}
+
+fn is_substituted(word: &Word) -> bool {
+    word.iter().any(|part| matches!(part, Part::Param(_) | Part::Tilde { .. }))
+}
+
+/// The text of a word without any quoting or expansions.
+fn literal(word: &Word) -> Option<&str> {
+    match word.as_slice() {
+        [Part::Text { text, quoted: false }] => Some(text),
+        _ => None,
+    }
+}
+
+/// Shell pattern matching (`*`, `?` and bracket expressions).
+fn matches(pattern: &[(char, bool)], value: &[char]) -> bool {
+    let Some(&(ch, quoted)) = pattern.first() else {
+        return value.is_empty();
+    };
+
+    if !quoted {
+        match ch {
+            '*' => {
+                let rest = &pattern[1..];
+                return (0..=value.len()).any(|index| matches(rest, &value[index..]));
+            }
+            '?' => {
+                return !value.is_empty() && matches(&pattern[1..], &value[1..]);
+            }
+            '\\' if pattern.len() > 1 => {
+                return value.first() == Some(&pattern[1].0) && matches(&pattern[2..], &value[1..]);
+            }
+            '[' => {
+                if let Some((matched, len)) = match_bracket(&pattern[1..], value.first().copied()) {
+                    return matched && matches(&pattern[1 + len..], &value[1..]);
+                }
+                // not a valid bracket expression, so `[` is a literal
+            }
+            _ => {}
+        }
+    }
+
+    value.first() == Some(&ch) && matches(&pattern[1..], &value[1..])
+}
+
+/// Matches `ch` against the bracket expression after the `[`. Returns
+/// whether it matched and the length of the expression including the `]`,
+/// or `None` if it isn't a valid bracket expression.
+fn match_bracket(pattern: &[(char, bool)], ch: Option<char>) -> Option<(bool, usize)> {
+    let mut index = 0;
+    let negate = matches!(pattern.first(), Some(('!' | '^', false)));
+    if negate {
+        index += 1;
+    }
+
+    let mut matched = false;
+    let mut first = true;
+    loop {
+        let &(start, quoted) = pattern.get(index)?;
+        if start == ']' && !quoted && !first {
+            index += 1;
+            break;
+        }
+        first = false;
+
+        if start == '[' && !quoted && matches!(pattern.get(index + 1), Some((':', false))) {
+            let class: String = pattern[index + 2..].iter().
+                take_while(|(ch, _)| ch.is_ascii_alphabetic()).
+                map(|(ch, _)| *ch).
+                collect();
+            let end = index + 2 + class.len();
+            if matches!(pattern.get(end..end + 2), Some([(':', false), (']', false)])) {
+                if let Some(ch) = ch {
+                    matched |= match class.as_str() {
+                        "alnum"  => ch.is_alphanumeric(),
+                        "alpha"  => ch.is_alphabetic(),
+                        "blank"  => ch == ' ' || ch == '\t',
+                        "cntrl"  => ch.is_control(),
+                        "digit"  => ch.is_ascii_digit(),
+                        "graph"  => !ch.is_control() && !ch.is_whitespace(),
+                        "lower"  => ch.is_lowercase(),
+                        "print"  => !ch.is_control(),
+                        "punct"  => ch.is_ascii_punctuation(),
+                        "space"  => ch.is_whitespace(),
+                        "upper"  => ch.is_uppercase(),
+                        "xdigit" => ch.is_ascii_hexdigit(),
+                        _ => false,
+                    };
+                }
+                index = end + 2;
+                continue;
+            }
+        }
+
+        index += 1;
+        if let (Some(('-', false)), Some(&(end, end_quoted))) = (pattern.get(index), pattern.get(index + 1)) {
+            if end != ']' || end_quoted {
+                if let Some(ch) = ch {
+                    matched |= start <= ch && ch <= end;
+                }
+                index += 2;
+                continue;
+            }
+        }
+
+        matched |= ch == Some(start);
+    }
+
+    Some((ch.is_some() && matched != negate, index))
+}
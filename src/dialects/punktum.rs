@@ -1,6 +1,6 @@
-use std::{borrow::Cow, io::BufRead, path::Path};
+use std::{borrow::Cow, cell::{Cell, RefCell}, ffi::OsStr, fs::File, io::{BufRead, BufReader}, path::{Path, PathBuf}};
 
-use crate::{env::{EmptyEnv, GetEnv}, error::SourceLocation, Encoding, Env, Error, ErrorKind, Options, Result, DEBUG_PREFIX};
+use crate::{diagnostic::Diagnostics, env::{os_str_as_bytes, os_str_from_bytes, EmptyEnv, GetEnv}, error::{Include, SourceLocation}, DecodingReader, Diagnostic, DiagnosticCode, Encoding, Env, Error, ErrorKind, Options, Origin, Result};
 
 #[inline]
 fn is_word(ch: char) -> bool {
@@ -35,223 +35,387 @@ fn char_at(src: &str, index: usize) -> Option<char> {
 }
 
 pub fn config_punktum(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dyn GetEnv, options: &Options<&Path>) -> Result<()> {
-    let path_str = options.path.to_string_lossy();
-
-    let mut key = String::new();
-    let mut value = String::new();
-    let mut parser = Parser {
-        path: path_str,
-        lineno: 0,
-        debug: options.debug,
-        strict: options.strict,
-        encoding: options.encoding,
-        linebuf: String::new(),
-        reader,
+    if !matches!(options.encoding, Encoding::UTF8 | Encoding::Auto) {
+        // one decoder for the whole input, so its errors have the right
+        // locations
+        let mut reader = DecodingReader::new(reader, options.encoding);
+        let options = Options { encoding: Encoding::UTF8, ..options.clone() };
+        return config_punktum(&mut reader, env, parent, &options);
+    }
+
+    let mut statements = Statements::default();
+    while statements.parse_next(reader, env, parent, options)? {}
+    Ok(())
+}
+
+/// The state of parsing a file one line at a time, so [`crate::parse()`] can
+/// yield the entries as they are read.
+#[derive(Debug, Default)]
+pub(crate) struct Statements {
+    lineno: usize,
+    linebuf: String,
+    key: String,
+    value: Vec<u8>,
+}
+
+impl Statements {
+    /// Parse the next line and any further lines its value spans. Returns
+    /// `false` at the end of the input.
+    pub(crate) fn parse_next(&mut self, reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dyn GetEnv, options: &Options<&Path>) -> Result<bool> {
+        let mut parser = Parser {
+            lineno: self.lineno,
+            options,
+            strict: options.strict,
+            linebuf: std::mem::take(&mut self.linebuf),
+            reader,
+            quoted: false,
+            substituted: false,
+        };
+
+        let result = parser.parse_line(&mut self.key, &mut self.value, env, parent);
+
+        self.lineno = parser.lineno;
+        self.linebuf = parser.linebuf;
+
+        result
+    }
+}
+
+/// How deep `include` statements may be nested.
+pub const MAX_INCLUDE_DEPTH: usize = 16;
+
+thread_local! {
+    /// Canonical paths of the file that was loaded and of the files that are
+    /// being included by it, outermost first.
+    static INCLUDES: RefCell<Vec<PathBuf>> = const { RefCell::new(Vec::new()) };
+
+    /// How many includes deep the file that is being loaded is. Not the
+    /// length of `INCLUDES`, which lacks files that couldn't be canonicalized.
+    static INCLUDE_DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Load the file of the `include` statement at `location`. The path is
+/// relative to the including file.
+fn include(path: &[u8], optional: bool, location: SourceLocation, env: &mut dyn Env, parent: &dyn GetEnv, options: &Options<&Path>) -> Result<()> {
+    let base_len = INCLUDES.with_borrow_mut(|includes| {
+        let base_len = includes.len();
+        if base_len == 0 {
+            if let Ok(current) = std::fs::canonicalize(options.path) {
+                includes.push(current);
+            }
+        }
+        base_len
+    });
+    let depth = INCLUDE_DEPTH.get();
+
+    let result = include_file(path, optional, location, env, parent, options);
+
+    INCLUDES.with_borrow_mut(|includes| includes.truncate(base_len));
+    INCLUDE_DEPTH.set(depth);
+
+    result
+}
+
+fn include_file(path: &[u8], optional: bool, location: SourceLocation, env: &mut dyn Env, parent: &dyn GetEnv, options: &Options<&Path>) -> Result<()> {
+    let include_error = |code: DiagnosticCode, kind: ErrorKind, message: String| {
+        if options.diagnostics_enabled() {
+            options.report(Diagnostic::new(code, message.clone()).at(location.lineno(), location.column()));
+        }
+        if options.strict {
+            return Err(Error::new(kind, message, location));
+        }
+        Ok(())
     };
 
-    loop {
-        parser.linebuf.clear();
-        parser.lineno += 1;
-        if let Err(err) = options.encoding.read_line(&mut parser.reader, &mut parser.linebuf) {
-            if options.debug {
-                eprintln!("{DEBUG_PREFIX}{}:{}:1: {err}", parser.path, parser.lineno);
+    let Some(path) = os_str_from_bytes(path) else {
+        return include_error(DiagnosticCode::IncludeError, ErrorKind::IncludeError, "include path is not valid Unicode".into());
+    };
+
+    if path.is_empty() {
+        return include_error(DiagnosticCode::IncludeError, ErrorKind::IncludeError, "include without a path".into());
+    }
+
+    let path = options.path.parent().unwrap_or(Path::new("")).join(path);
+
+    let file = match File::open(&path) {
+        Err(err) if optional && err.kind() == std::io::ErrorKind::NotFound => {
+            if options.diagnostics_enabled() {
+                options.report(Diagnostic::new(DiagnosticCode::FileLookup,
+                    format!("optional include {} not found, skipped", path.display())).
+                    at(location.lineno(), location.column()));
+            }
+            return Ok(());
+        },
+        Err(err) => {
+            return include_error(DiagnosticCode::IOError, ErrorKind::IOError, format!("error including {}: {err}", path.display()));
+        },
+        Ok(file) => file,
+    };
+
+    let canonical = match std::fs::canonicalize(&path) {
+        Err(err) => {
+            return include_error(DiagnosticCode::IOError, ErrorKind::IOError, format!("error including {}: {err}", path.display()));
+        },
+        Ok(canonical) => canonical,
+    };
+
+    if let Some(root) = &options.include_root {
+        if !std::fs::canonicalize(root).is_ok_and(|root| canonical.starts_with(root)) {
+            return include_error(DiagnosticCode::IncludeError, ErrorKind::IncludeError,
+                format!("{} is outside of the include root {}", path.display(), root.display()));
+        }
+    }
+
+    let depth = INCLUDE_DEPTH.get();
+    let message = INCLUDES.with_borrow_mut(|includes| {
+        if includes.contains(&canonical) {
+            Some(format!("{} includes itself", path.display()))
+        } else if depth >= MAX_INCLUDE_DEPTH {
+            Some(format!("includes are nested deeper than {MAX_INCLUDE_DEPTH}"))
+        } else {
+            includes.push(canonical);
+            None
+        }
+    });
+
+    if let Some(message) = message {
+        return include_error(DiagnosticCode::IncludeError, ErrorKind::IncludeError, message);
+    }
+
+    let include = Include::new(&path, location);
+    let mut included_options = Options { path: path.as_path(), ..options.clone() };
+    if options.diagnostics.is_some() {
+        let diagnostics = options.diagnostics.clone();
+        let include = include.clone();
+        included_options.diagnostics = Diagnostics::new(move |diagnostic: Diagnostic|
+            diagnostics.report(diagnostic.included(include.clone())));
+    }
+
+    INCLUDE_DEPTH.set(depth + 1);
+    crate::config_with_dyn_reader(&mut BufReader::new(file), env, parent, included_options).
+        map_err(|err| err.included(include))
+}
+
+struct Parser<'c> {
+    lineno: usize,
+    options: &'c Options<&'c Path>,
+    strict: bool,
+    reader: &'c mut dyn BufRead,
+    linebuf: String,
+    quoted: bool,
+    substituted: bool,
+}
+
+macro_rules! parse_var_error {
+    ($self:expr, $index:expr, $buf:expr, $key:expr, $env:expr, $message:expr) => {
+        if $buf.is_skipped() {
+            $index = $self.parse_value($index, &mut NullStringBuffer(), &EmptyEnv(), true)?;
+        } else {
+            let lineno = $self.lineno;
+            let column = $index + 1;
+            if $self.options.diagnostics_enabled() {
+                // abusing the buffer so not to make yet another allocation
+                let key_index = $buf.len();
+                $buf.push_str($key);
+                let message_index = $buf.len();
+                $self.parse_value($index, $buf, $env, true)?;
+                let message = $buf.tail(message_index);
+                if message.is_empty() {
+                    $self.options.report(Diagnostic::new(DiagnosticCode::SubstitutionError, format!("variable ${} {}", $buf.slice(key_index, message_index), $message)).at(lineno, column));
+                } else {
+                    $self.options.report(Diagnostic::new(DiagnosticCode::SubstitutionError, message.to_string()).at(lineno, column));
+                }
+                $buf.truncate(key_index);
+            }
+            return Err(Error::substitution_error(lineno, column));
+        }
+    };
+}
+
+impl<'c> Parser<'c> {
+    fn parse_line(&mut self, key: &mut String, value: &mut Vec<u8>, env: &mut dyn Env, parent: &dyn GetEnv) -> Result<bool> {
+        let options = self.options;
+
+        self.linebuf.clear();
+        self.lineno += 1;
+        if let Err(err) = self.reader.read_line(&mut self.linebuf) {
+            if options.diagnostics_enabled() {
+                options.report(Diagnostic::new(DiagnosticCode::from(&err), err.to_string()).at(self.lineno, 1));
             }
             if options.strict {
-                return Err(Error::new(ErrorKind::IOError, err, SourceLocation::new(parser.lineno, 1)));
+                return Err(Error::new(ErrorKind::IOError, err, SourceLocation::new(self.lineno, 1)));
             }
             if err.kind() == std::io::ErrorKind::InvalidData {
-                continue;
+                return Ok(true);
             } else {
-                return Ok(());
+                return Ok(false);
             }
         }
 
-        if parser.linebuf.is_empty() {
-            break;
+        if self.linebuf.is_empty() {
+            return Ok(false);
         }
 
-        if parser.linebuf.ends_with("\r\n") {
+        if self.linebuf.ends_with("\r\n") {
             // convert DOS line endings to Unix
-            parser.linebuf.remove(parser.linebuf.len() - 2);
+            self.linebuf.remove(self.linebuf.len() - 2);
         }
 
-        let mut index = skip_ws(&parser.linebuf, 0);
+        let mut index = skip_ws(&self.linebuf, 0);
 
-        let Some(mut ch) = char_at(&parser.linebuf, index) else {
-            continue;
+        let Some(mut ch) = char_at(&self.linebuf, index) else {
+            return Ok(true);
         };
 
         if ch == '#' {
-            continue;
+            return Ok(true);
         }
 
         let prev_index = index;
 
         if !is_word(ch) {
             let column = prev_index + 1;
-            if options.debug {
-                let line = parser.linebuf.trim_end_matches('\n');
-                eprintln!("{DEBUG_PREFIX}{}:{}:{column}: syntax error: unexpected {ch:?}, expected variable name: {line}", parser.path, parser.lineno);
+            if options.diagnostics_enabled() {
+                let line = self.linebuf.trim_end_matches('\n');
+                options.report(Diagnostic::new(DiagnosticCode::SyntaxError, format!("syntax error: unexpected {ch:?}, expected variable name: {line}")).at(self.lineno, column));
             }
             if options.strict {
-                return Err(Error::syntax_error(parser.lineno, column));
+                return Err(Error::syntax_error(self.lineno, column));
             }
-            continue;
+            return Ok(true);
         }
 
-        index = find_word_end(&parser.linebuf, index);
+        index = find_word_end(&self.linebuf, index);
 
         if index == prev_index {
             let column = prev_index + 1;
-            if options.debug {
-                let line = parser.linebuf.trim_end_matches('\n');
-                eprintln!("{DEBUG_PREFIX}{}:{}:{column}: syntax error: unexpected end of line, expected variable name: {line}", parser.path, parser.lineno);
+            if options.diagnostics_enabled() {
+                let line = self.linebuf.trim_end_matches('\n');
+                options.report(Diagnostic::new(DiagnosticCode::SyntaxError, format!("syntax error: unexpected end of line, expected variable name: {line}")).at(self.lineno, column));
             }
             if options.strict {
-                return Err(Error::syntax_error(parser.lineno, column));
+                return Err(Error::syntax_error(self.lineno, column));
             }
-            continue;
+            return Ok(true);
         };
 
         key.clear();
-        key.push_str(&parser.linebuf[prev_index..index]);
+        key.push_str(&self.linebuf[prev_index..index]);
+        let mut origin = Origin::new(self.lineno, prev_index + 1);
 
         if key.is_empty() {
             let column = index + 1;
-            if options.debug {
-                let line = parser.linebuf.trim_end_matches('\n');
-                eprintln!("{DEBUG_PREFIX}{}:{}:{column}: syntax error: expected variable name: {line}", parser.path, parser.lineno);
+            if options.diagnostics_enabled() {
+                let line = self.linebuf.trim_end_matches('\n');
+                options.report(Diagnostic::new(DiagnosticCode::SyntaxError, format!("syntax error: expected variable name: {line}")).at(self.lineno, column));
             }
             if options.strict {
-                return Err(Error::syntax_error(parser.lineno, column));
+                return Err(Error::syntax_error(self.lineno, column));
             }
-            continue;
+            return Ok(true);
         }
 
-        index = skip_ws(&parser.linebuf, index);
+        let key_end = index;
+        index = skip_ws(&self.linebuf, index);
 
         {
-            let Some(next_ch) = char_at(&parser.linebuf, index) else {
+            let Some(next_ch) = char_at(&self.linebuf, index) else {
                 if let Some(value) = parent.get(key.as_ref()) {
-                    options.set_var(env, key.as_ref(), value.as_ref());
+                    options.set_var(env, key.as_ref(), value.as_ref(), origin.substituted(true));
                 }
-                continue;
+                return Ok(true);
             };
             ch = next_ch;
         }
 
         if ch == '#' {
             if let Some(value) = parent.get(key.as_ref()) {
-                options.set_var(env, key.as_ref(), value.as_ref());
+                options.set_var(env, key.as_ref(), value.as_ref(), origin.substituted(true));
+            }
+            return Ok(true);
+        }
+
+        if ch != '=' && key == "include" && (ch == '?' || index > key_end) {
+            // `include path` or `include? path`
+            let optional = ch == '?';
+            if optional {
+                index = skip_ws(&self.linebuf, index + 1);
             }
-            continue;
+            let location = SourceLocation::new(self.lineno, prev_index + 1);
+
+            value.clear();
+            self.parse_value(index, value, env.as_get_env(), false)?;
+            include(value, optional, location, env, parent, options)?;
+            return Ok(true);
         }
 
         if ch != '=' {
-            if !options.strict && key.eq("export") && is_word(ch) {
+            if !options.strict && *key == "export" && is_word(ch) {
                 // allow `export FOO=BAR`
                 key.clear();
 
                 let prev_index = index;
-                index = find_word_end(&parser.linebuf, index);
+                index = find_word_end(&self.linebuf, index);
 
-                key.push_str(&parser.linebuf[prev_index..index]);
+                key.push_str(&self.linebuf[prev_index..index]);
+                origin = Origin::new(self.lineno, prev_index + 1);
 
-                index = skip_ws(&parser.linebuf, index);
+                index = skip_ws(&self.linebuf, index);
                 {
-                    let Some(next_ch) = char_at(&parser.linebuf, index) else {
+                    let Some(next_ch) = char_at(&self.linebuf, index) else {
                         if let Some(value) = parent.get(key.as_ref()) {
-                            options.set_var(env, key.as_ref(), value.as_ref());
+                            options.set_var(env, key.as_ref(), value.as_ref(), origin.substituted(true));
                         }
-                        continue;
+                        return Ok(true);
                     };
                     ch = next_ch
                 }
 
                 if ch == '#' {
                     if let Some(value) = parent.get(key.as_ref()) {
-                        options.set_var(env, key.as_ref(), value.as_ref());
+                        options.set_var(env, key.as_ref(), value.as_ref(), origin.substituted(true));
                     }
-                    continue;
+                    return Ok(true);
                 }
 
                 if ch != '=' {
                     let column = index + 1;
-                    if options.debug {
-                        let line = parser.linebuf.trim_end_matches('\n');
-                        eprintln!("{DEBUG_PREFIX}{}:{}:{column}: syntax error: expected '=', actual {ch:?}: {line}", parser.path, parser.lineno);
+                    if options.diagnostics_enabled() {
+                        let line = self.linebuf.trim_end_matches('\n');
+                        options.report(Diagnostic::new(DiagnosticCode::SyntaxError, format!("syntax error: expected '=', actual {ch:?}: {line}")).at(self.lineno, column));
                     }
                     if options.strict {
-                        return Err(Error::syntax_error(parser.lineno, column));
+                        return Err(Error::syntax_error(self.lineno, column));
                     }
-                    continue;
+                    return Ok(true);
                 }
             } else {
                 let column = index + 1;
-                if options.debug {
-                    let line = parser.linebuf.trim_end_matches('\n');
-                    eprintln!("{DEBUG_PREFIX}{}:{}:{column}: syntax error: expected '=', actual {ch:?}: {line}", parser.path, parser.lineno);
+                if options.diagnostics_enabled() {
+                    let line = self.linebuf.trim_end_matches('\n');
+                    options.report(Diagnostic::new(DiagnosticCode::SyntaxError, format!("syntax error: expected '=', actual {ch:?}: {line}")).at(self.lineno, column));
                 }
                 if options.strict {
-                    return Err(Error::syntax_error(parser.lineno, column));
+                    return Err(Error::syntax_error(self.lineno, column));
                 }
-                continue;
+                return Ok(true);
             }
         }
 
-        index = skip_ws(&parser.linebuf, index + 1);
+        index = skip_ws(&self.linebuf, index + 1);
 
         value.clear();
-        parser.parse_value(index, &mut value, env.as_get_env(), false)?;
-
-        options.set_var(env, key.as_ref(), value.as_ref());
-    }
-
-    Ok(())
-}
+        self.quoted = false;
+        self.substituted = false;
+        self.parse_value(index, value, env.as_get_env(), false)?;
 
-struct Parser<'c> {
-    path: Cow<'c, str>,
-    lineno: usize,
-    debug: bool,
-    strict: bool,
-    encoding: Encoding,
-    reader: &'c mut dyn BufRead,
-    linebuf: String,
-}
+        // off Unix only valid UTF-8 is ever pushed to the value
+        let value = os_str_from_bytes(value).unwrap_or_default();
+        options.set_var(env, key.as_ref(), value, origin.quoted(self.quoted).substituted(self.substituted));
 
-macro_rules! parse_var_error {
-    ($self:expr, $index:expr, $buf:expr, $key:expr, $env:expr, $message:expr) => {
-        if $buf.is_skipped() {
-            $index = $self.parse_value($index, &mut NullStringBuffer(), &EmptyEnv(), true)?;
-        } else {
-            let lineno = $self.lineno;
-            let column = $index + 1;
-            if $self.debug {
-                // abusing the buffer so not to make yet another allocation
-                let key_index = $buf.len();
-                $buf.push_str($key);
-                let message_index = $buf.len();
-                $self.parse_value($index, $buf, $env, true)?;
-                let message = $buf.tail(message_index);
-                if message.is_empty() {
-                    eprintln!("{DEBUG_PREFIX}{}:{}: variable ${} {}",
-                        &$self.path, $self.lineno, $buf.slice(key_index, message_index),
-                        $message
-                    );
-                } else {
-                    eprintln!("{DEBUG_PREFIX}{}:{}: {}",
-                        &$self.path, $self.lineno, message
-                    );
-                }
-                $buf.truncate(key_index);
-            }
-            return Err(Error::substitution_error(lineno, column));
-        }
-    };
-}
+        Ok(true)
+    }
 
-impl<'c> Parser<'c> {
     fn parse_value(&mut self, mut index: usize, value: &mut dyn StringBuffer, env: &dyn GetEnv, nested: bool) -> Result<usize> {
         loop {
             if nested && index >= self.linebuf.len() {
@@ -259,9 +423,9 @@ impl<'c> Parser<'c> {
 
                 self.linebuf.clear();
                 self.lineno += 1;
-                if let Err(err) = self.encoding.read_line(self.reader, &mut self.linebuf) {
-                    if self.debug {
-                        eprintln!("{DEBUG_PREFIX}{}:{}:1: {err}", self.path, self.lineno);
+                if let Err(err) = self.reader.read_line(&mut self.linebuf) {
+                    if self.options.diagnostics_enabled() {
+                        self.options.report(Diagnostic::new(DiagnosticCode::from(&err), err.to_string()).at(self.lineno, 1));
                     }
                     if self.strict {
                         return Err(Error::new(ErrorKind::IOError, err, SourceLocation::new(self.lineno, 1)));
@@ -286,15 +450,16 @@ impl<'c> Parser<'c> {
             if ch == '"' || ch == '\'' {
                 let quote = ch;
                 index += 1;
+                self.quoted = true;
                 let mut prev_index = index;
 
                 loop {
                     {
                         let Some(next_ch) = char_at(&self.linebuf, index) else {
                             let column = prev_index + 1;
-                            if self.debug {
+                            if self.options.diagnostics_enabled() {
                                 let line = self.linebuf.trim_end_matches('\n');
-                                eprintln!("{DEBUG_PREFIX}{}:{}:{column}: syntax error: unterminated string literal: {line}", self.path, self.lineno);
+                                self.options.report(Diagnostic::new(DiagnosticCode::UnterminatedString, format!("syntax error: unterminated string literal: {line}")).at(self.lineno, column));
                             }
                             if self.strict {
                                 return Err(Error::syntax_error(self.lineno, column));
@@ -375,10 +540,10 @@ impl<'c> Parser<'c> {
                                             if hi >= 0xD800 && hi <= 0xDBFF {
                                                 if self.linebuf.len() < index + 10 || !self.linebuf[index + 4..].starts_with("\\u") {
                                                     let column = index - 1;
-                                                    if self.debug {
+                                                    if self.options.diagnostics_enabled() {
                                                         let escseq = &self.linebuf[(index - 2)..index + 4];
                                                         let line = self.linebuf.trim_end_matches('\n');
-                                                        eprintln!("{DEBUG_PREFIX}{}:{}:{column}: syntax error: illegal escape seqeunce {escseq:?}: {line}", self.path, self.lineno);
+                                                        self.options.report(Diagnostic::new(DiagnosticCode::IllegalEscape, format!("syntax error: illegal escape seqeunce {escseq:?}: {line}")).at(self.lineno, column));
                                                     }
                                                     if self.strict {
                                                         return Err(Error::syntax_error(self.lineno, column));
@@ -388,10 +553,10 @@ impl<'c> Parser<'c> {
                                                     if let Ok(lo) = u16::from_str_radix(unicode, 16) {
                                                         if lo < 0xDC00 || lo > 0xDFFF {
                                                             let column = index + 3;
-                                                            if self.debug {
+                                                            if self.options.diagnostics_enabled() {
                                                                 let escseq = &self.linebuf[(index + 2)..index + 10];
                                                                 let line = self.linebuf.trim_end_matches('\n');
-                                                                eprintln!("{DEBUG_PREFIX}{}:{}:{column}: syntax error: illegal escape seqeunce {escseq:?}: {line}", self.path, self.lineno);
+                                                                self.options.report(Diagnostic::new(DiagnosticCode::IllegalEscape, format!("syntax error: illegal escape seqeunce {escseq:?}: {line}")).at(self.lineno, column));
                                                             }
                                                             if self.strict {
                                                                 return Err(Error::syntax_error(self.lineno, column));
@@ -405,10 +570,10 @@ impl<'c> Parser<'c> {
                                                         }
                                                     } else {
                                                         let column = index + 1;
-                                                        if self.debug {
+                                                        if self.options.diagnostics_enabled() {
                                                             let escseq = &self.linebuf[(index - 2)..index + 10];
                                                             let line = self.linebuf.trim_end_matches('\n');
-                                                            eprintln!("{DEBUG_PREFIX}{}:{}:{column}: syntax error: illegal escape seqeunce {escseq:?}: {line}", self.path, self.lineno);
+                                                            self.options.report(Diagnostic::new(DiagnosticCode::IllegalEscape, format!("syntax error: illegal escape seqeunce {escseq:?}: {line}")).at(self.lineno, column));
                                                         }
                                                         if self.strict {
                                                             return Err(Error::syntax_error(self.lineno, column));
@@ -421,10 +586,10 @@ impl<'c> Parser<'c> {
                                                 prev_index = index;
                                             } else {
                                                 let column = index - 1;
-                                                if self.debug {
+                                                if self.options.diagnostics_enabled() {
                                                     let escseq = &self.linebuf[(index - 2)..index + 4];
                                                     let line = self.linebuf.trim_end_matches('\n');
-                                                    eprintln!("{DEBUG_PREFIX}{}:{}:{column}: syntax error: illegal escape seqeunce {escseq:?}: {line}", self.path, self.lineno);
+                                                    self.options.report(Diagnostic::new(DiagnosticCode::IllegalEscape, format!("syntax error: illegal escape seqeunce {escseq:?}: {line}")).at(self.lineno, column));
                                                 }
                                                 if self.strict {
                                                     return Err(Error::syntax_error(self.lineno, column));
@@ -432,10 +597,10 @@ impl<'c> Parser<'c> {
                                             }
                                         } else {
                                             let column = index - 1;
-                                            if self.debug {
+                                            if self.options.diagnostics_enabled() {
                                                 let escseq = &self.linebuf[(index - 2)..index + 4];
                                                 let line = self.linebuf.trim_end_matches('\n');
-                                                eprintln!("{DEBUG_PREFIX}{}:{}:{column}: syntax error: illegal escape seqeunce {escseq:?}: {line}", self.path, self.lineno);
+                                                self.options.report(Diagnostic::new(DiagnosticCode::IllegalEscape, format!("syntax error: illegal escape seqeunce {escseq:?}: {line}")).at(self.lineno, column));
                                             }
                                             if self.strict {
                                                 return Err(Error::syntax_error(self.lineno, column));
@@ -452,10 +617,10 @@ impl<'c> Parser<'c> {
                                                 prev_index = index;
                                             } else {
                                                 let column = index - 1;
-                                                if self.debug {
+                                                if self.options.diagnostics_enabled() {
                                                     let escseq = &self.linebuf[(index - 2)..index + 6];
                                                     let line = self.linebuf.trim_end_matches('\n');
-                                                    eprintln!("{DEBUG_PREFIX}{}:{}:{column}: syntax error: illegal escape seqeunce {escseq:?}: {line}", self.path, self.lineno);
+                                                    self.options.report(Diagnostic::new(DiagnosticCode::IllegalEscape, format!("syntax error: illegal escape seqeunce {escseq:?}: {line}")).at(self.lineno, column));
                                                 }
                                                 if self.strict {
                                                     return Err(Error::syntax_error(self.lineno, column));
@@ -463,10 +628,10 @@ impl<'c> Parser<'c> {
                                             }
                                         } else {
                                             let column = index - 1;
-                                            if self.debug {
+                                            if self.options.diagnostics_enabled() {
                                                 let escseq = &self.linebuf[(index - 2)..index + 6];
                                                 let line = self.linebuf.trim_end_matches('\n');
-                                                eprintln!("{DEBUG_PREFIX}{}:{}:{column}: syntax error: illegal escape seqeunce {escseq:?}: {line}", self.path, self.lineno);
+                                                self.options.report(Diagnostic::new(DiagnosticCode::IllegalEscape, format!("syntax error: illegal escape seqeunce {escseq:?}: {line}")).at(self.lineno, column));
                                             }
                                             if self.strict {
                                                 return Err(Error::syntax_error(self.lineno, column));
@@ -476,9 +641,9 @@ impl<'c> Parser<'c> {
                                     '\0' => {
                                         index += 1;
                                         let column = index - 1;
-                                        if self.debug {
+                                        if self.options.diagnostics_enabled() {
                                             let line = self.linebuf.trim_end_matches('\n');
-                                            eprintln!("{DEBUG_PREFIX}{}:{}:{column}: syntax error: illegal null byte: {line:?}", self.path, self.lineno);
+                                            self.options.report(Diagnostic::new(DiagnosticCode::IllegalNullByte, format!("syntax error: illegal null byte: {line:?}")).at(self.lineno, column));
                                         }
                                         if self.strict {
                                             return Err(Error::syntax_error(self.lineno, column));
@@ -492,9 +657,9 @@ impl<'c> Parser<'c> {
 
                                         self.linebuf.clear();
                                         self.lineno += 1;
-                                        if let Err(err) = self.encoding.read_line(&mut self.reader, &mut self.linebuf) {
-                                            if self.debug {
-                                                eprintln!("{DEBUG_PREFIX}{}:{}:1: {err}", self.path, self.lineno);
+                                        if let Err(err) = self.reader.read_line(&mut self.linebuf) {
+                                            if self.options.diagnostics_enabled() {
+                                                self.options.report(Diagnostic::new(DiagnosticCode::from(&err), err.to_string()).at(self.lineno, 1));
                                             }
                                             if self.strict {
                                                 return Err(Error::new(ErrorKind::IOError, err, SourceLocation::new(self.lineno, 1)));
@@ -507,9 +672,9 @@ impl<'c> Parser<'c> {
                                         }
 
                                         if self.linebuf.is_empty() {
-                                            if self.debug {
+                                            if self.options.diagnostics_enabled() {
                                                 let line = self.linebuf.trim_end_matches('\n');
-                                                eprintln!("{DEBUG_PREFIX}{}:{}:1: syntax error: unterminated string literal: {line}", self.path, self.lineno);
+                                                self.options.report(Diagnostic::new(DiagnosticCode::UnterminatedString, format!("syntax error: unterminated string literal: {line}")).at(self.lineno, 1));
                                             }
                                             if self.strict {
                                                 return Err(Error::syntax_error(self.lineno, 1));
@@ -521,10 +686,10 @@ impl<'c> Parser<'c> {
                                         let column = index + 1;
                                         prev_index = index - 1;
                                         index += ch.len_utf8();
-                                        if self.debug {
+                                        if self.options.diagnostics_enabled() {
                                             let escseq = &self.linebuf[(index - 1 - ch.len_utf8())..index];
                                             let line = self.linebuf.trim_end_matches('\n');
-                                            eprintln!("{DEBUG_PREFIX}{}:{}:{column}: syntax error: illegal escape seqeunce {escseq:?}: {line}", self.path, self.lineno);
+                                            self.options.report(Diagnostic::new(DiagnosticCode::IllegalEscape, format!("syntax error: illegal escape seqeunce {escseq:?}: {line}")).at(self.lineno, column));
                                         }
                                         if self.strict {
                                             return Err(Error::syntax_error(self.lineno, column));
@@ -533,9 +698,9 @@ impl<'c> Parser<'c> {
                                 }
                             } else { // no '\n' means EOF
                                 let column = index + 1;
-                                if self.debug {
+                                if self.options.diagnostics_enabled() {
                                     let line = self.linebuf.trim_end_matches('\n');
-                                    eprintln!("{DEBUG_PREFIX}{}:{}:{column}: syntax error: unexpected end of file within escape seqeunce: {line}", self.path, self.lineno);
+                                    self.options.report(Diagnostic::new(DiagnosticCode::UnterminatedString, format!("syntax error: unexpected end of file within escape seqeunce: {line}")).at(self.lineno, column));
                                 }
                                 if self.strict {
                                     return Err(Error::syntax_error(self.lineno, column));
@@ -557,9 +722,9 @@ impl<'c> Parser<'c> {
 
                             self.linebuf.clear();
                             self.lineno += 1;
-                            if let Err(err) = self.encoding.read_line(&mut self.reader, &mut self.linebuf) {
-                                if self.debug {
-                                    eprintln!("{DEBUG_PREFIX}{}:{}:1: {err}", self.path, self.lineno);
+                            if let Err(err) = self.reader.read_line(&mut self.linebuf) {
+                                if self.options.diagnostics_enabled() {
+                                    self.options.report(Diagnostic::new(DiagnosticCode::from(&err), err.to_string()).at(self.lineno, 1));
                                 }
                                 if self.strict {
                                     return Err(Error::new(ErrorKind::IOError, err, SourceLocation::new(self.lineno, 1)));
@@ -572,8 +737,8 @@ impl<'c> Parser<'c> {
                             }
 
                             if self.linebuf.is_empty() {
-                                if self.debug {
-                                    eprintln!("{DEBUG_PREFIX}{}:{}:1: syntax error: unexpected end of file in string literal", self.path, self.lineno);
+                                if self.options.diagnostics_enabled() {
+                                    self.options.report(Diagnostic::new(DiagnosticCode::UnterminatedString, "syntax error: unexpected end of file in string literal").at(self.lineno, 1));
                                 }
                                 if self.strict {
                                     return Err(Error::syntax_error(self.lineno, 1));
@@ -583,9 +748,9 @@ impl<'c> Parser<'c> {
                         }
                         '\0' => {
                             let column = index + 1;
-                            if self.debug {
+                            if self.options.diagnostics_enabled() {
                                 let line = self.linebuf.trim_end_matches('\n');
-                                eprintln!("{DEBUG_PREFIX}{}:{}:{column}: syntax error: illegal null byte: {line:?}", self.path, self.lineno);
+                                self.options.report(Diagnostic::new(DiagnosticCode::IllegalNullByte, format!("syntax error: illegal null byte: {line:?}")).at(self.lineno, column));
                             }
                             if self.strict {
                                 return Err(Error::syntax_error(self.lineno, column));
@@ -609,9 +774,9 @@ impl<'c> Parser<'c> {
                 index = self.parse_var(index + 1, value, env)?;
             } else if ch == '\0' {
                 let column = index + 1;
-                if self.debug {
+                if self.options.diagnostics_enabled() {
                     let line = self.linebuf.trim_end_matches('\n');
-                    eprintln!("{DEBUG_PREFIX}{}:{}:{column}: syntax error: illegal null byte: {line:?}", self.path, self.lineno);
+                    self.options.report(Diagnostic::new(DiagnosticCode::IllegalNullByte, format!("syntax error: illegal null byte: {line:?}")).at(self.lineno, column));
                 }
                 if self.strict {
                     return Err(Error::syntax_error(self.lineno, column));
@@ -630,9 +795,9 @@ impl<'c> Parser<'c> {
                         self.linebuf.clear();
                         self.lineno += 1;
 
-                        if let Err(err) = self.encoding.read_line(&mut self.reader, &mut self.linebuf) {
-                            if self.debug {
-                                eprintln!("{DEBUG_PREFIX}{}:{}:1: {err}", self.path, self.lineno);
+                        if let Err(err) = self.reader.read_line(&mut self.linebuf) {
+                            if self.options.diagnostics_enabled() {
+                                self.options.report(Diagnostic::new(DiagnosticCode::from(&err), err.to_string()).at(self.lineno, 1));
                             }
                             if self.strict {
                                 return Err(Error::new(ErrorKind::IOError, err, SourceLocation::new(self.lineno, 1)));
@@ -731,12 +896,12 @@ impl<'c> Parser<'c> {
             let column = var_start_index + 1;
             index = end_index;
 
-            if self.debug {
+            if self.options.diagnostics_enabled() {
                 let line = self.linebuf.trim_end_matches('\n');
                 if brace {
-                    eprintln!("{DEBUG_PREFIX}{}:{}:{column}: syntax error: ${{}} with empty variable name: {line}", self.path, self.lineno);
+                    self.options.report(Diagnostic::new(DiagnosticCode::SyntaxError, format!("syntax error: ${{}} with empty variable name: {line}")).at(self.lineno, column));
                 } else {
-                    eprintln!("{DEBUG_PREFIX}{}:{}:{column}: syntax error: single $ found: {line}", self.path, self.lineno);
+                    self.options.report(Diagnostic::new(DiagnosticCode::SyntaxError, format!("syntax error: single $ found: {line}")).at(self.lineno, column));
                 }
             }
 
@@ -756,6 +921,9 @@ impl<'c> Parser<'c> {
         let key = &self.linebuf[index..end_index];
         let value = env.get(key.as_ref());
         index = end_index;
+        if !buf.is_skipped() {
+            self.substituted = true;
+        }
         if brace {
             let tail = &self.linebuf[index..];
 
@@ -766,7 +934,7 @@ impl<'c> Parser<'c> {
                     if value.is_empty() {
                         parse_var_error!(self, index, buf, key, env, "may not be empty");
                     } else {
-                        buf.push_str(value.to_string_lossy().as_ref());
+                        self.push_var_value(buf, key, &value, var_start_index + 1)?;
                         index = self.parse_value(index, &mut NullStringBuffer(), &EmptyEnv(), true)?;
                     }
                 } else {
@@ -776,7 +944,7 @@ impl<'c> Parser<'c> {
                 // required error when unset
                 index += 1;
                 if let Some(value) = value {
-                    buf.push_str(value.to_string_lossy().as_ref());
+                    self.push_var_value(buf, key, &value, var_start_index + 1)?;
                     index = self.parse_value(index, &mut NullStringBuffer(), &EmptyEnv(), true)?;
                 } else {
                     parse_var_error!(self, index, buf, key, env, "may not be unset");
@@ -788,7 +956,7 @@ impl<'c> Parser<'c> {
                     if value.is_empty() {
                         index = self.parse_value(index, buf, env, true)?;
                     } else {
-                        buf.push_str(value.to_string_lossy().as_ref());
+                        self.push_var_value(buf, key, &value, var_start_index + 1)?;
                         index = self.parse_value(index, &mut NullStringBuffer(), &EmptyEnv(), true)?;
                     }
                 } else {
@@ -798,7 +966,7 @@ impl<'c> Parser<'c> {
                 // default when unset
                 index += 1;
                 if let Some(value) = value {
-                    buf.push_str(value.to_string_lossy().as_ref());
+                    self.push_var_value(buf, key, &value, var_start_index + 1)?;
                     index = self.parse_value(index, &mut NullStringBuffer(), &EmptyEnv(), true)?;
                 } else {
                     index = self.parse_value(index, buf, env, true)?;
@@ -824,7 +992,7 @@ impl<'c> Parser<'c> {
                     index = self.parse_value(index, &mut NullStringBuffer(), &EmptyEnv(), true)?;
                 }
             } else if let Some(value) = value {
-                buf.push_str(value.to_string_lossy().as_ref());
+                self.push_var_value(buf, key, &value, var_start_index + 1)?;
             }
 
             let tail = &self.linebuf[index..];
@@ -832,9 +1000,9 @@ impl<'c> Parser<'c> {
                 index += 1;
             } else {
                 let column = end_index + 1;
-                if self.debug {
+                if self.options.diagnostics_enabled() {
                     let line = self.linebuf.trim_end_matches('\n');
-                    eprintln!("{DEBUG_PREFIX}{}:{}:{column}: syntax error: expected '}}': {line}", self.path, self.lineno);
+                    self.options.report(Diagnostic::new(DiagnosticCode::SyntaxError, format!("syntax error: expected '}}': {line}")).at(self.lineno, column));
                 }
                 if self.strict {
                     return Err(Error::syntax_error(self.lineno, column));
@@ -843,43 +1011,68 @@ impl<'c> Parser<'c> {
                 buf.push_str(&self.linebuf[var_start_index..index]);
             }
         } else if let Some(value) = value {
-            // TODO: don't use lossy when strict?
-            buf.push_str(value.to_string_lossy().as_ref());
+            self.push_var_value(buf, key, &value, var_start_index + 1)?;
         }
 
         Ok(index)
     }
+
+    /// Append the value of a substituted variable. On Unix its bytes are taken
+    /// as is, elsewhere a value that isn't valid Unicode is an error in strict
+    /// mode and replaced lossily otherwise.
+    fn push_var_value(&self, buf: &mut dyn StringBuffer, key: &str, value: &OsStr, column: usize) -> Result<()> {
+        if buf.is_skipped() {
+            return Ok(());
+        }
+
+        if let Some(bytes) = os_str_as_bytes(value) {
+            buf.push_bytes(bytes);
+            return Ok(());
+        }
+
+        buf.push_str(&self.options.var_value_str(key, value, SourceLocation::new(self.lineno, column))?);
+
+        Ok(())
+    }
 }
 
 trait StringBuffer {
     fn push(&mut self, ch: char);
     fn push_str(&mut self, string: &str);
+    fn push_bytes(&mut self, bytes: &[u8]);
     fn len(&self) -> usize;
     fn truncate(&mut self, new_len: usize);
     fn is_skipped(&self) -> bool;
-    fn tail(&self, index: usize) -> &str;
-    fn slice(&self, start_index: usize, end_index: usize) -> &str;
+    fn tail(&self, index: usize) -> Cow<'_, str>;
+    fn slice(&self, start_index: usize, end_index: usize) -> Cow<'_, str>;
 }
 
-impl StringBuffer for String {
+/// Values are collected as bytes so that substituted variables that aren't
+/// valid UTF-8 survive on Unix.
+impl StringBuffer for Vec<u8> {
     #[inline]
     fn push(&mut self, ch: char) {
-        String::push(self, ch);
+        self.extend_from_slice(ch.encode_utf8(&mut [0; 4]).as_bytes());
     }
 
     #[inline]
     fn push_str(&mut self, string: &str) {
-        String::push_str(self, string);
+        self.extend_from_slice(string.as_bytes());
+    }
+
+    #[inline]
+    fn push_bytes(&mut self, bytes: &[u8]) {
+        self.extend_from_slice(bytes);
     }
 
     #[inline]
     fn len(&self) -> usize {
-        String::len(self)
+        Vec::len(self)
     }
 
     #[inline]
     fn truncate(&mut self, new_len: usize) {
-        String::truncate(self, new_len);
+        Vec::truncate(self, new_len);
     }
 
     #[inline]
@@ -888,13 +1081,13 @@ impl StringBuffer for String {
     }
 
     #[inline]
-    fn tail(&self, index: usize) -> &str {
-        &self[index..]
+    fn tail(&self, index: usize) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self[index..])
     }
 
     #[inline]
-    fn slice(&self, start_index: usize, end_index: usize) -> &str {
-        &self[start_index..end_index]
+    fn slice(&self, start_index: usize, end_index: usize) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self[start_index..end_index])
     }
 }
 
@@ -907,6 +1100,9 @@ impl StringBuffer for NullStringBuffer {
     #[inline]
     fn push_str(&mut self, _string: &str) {}
 
+    #[inline]
+    fn push_bytes(&mut self, _bytes: &[u8]) {}
+
     #[inline]
     fn len(&self) -> usize { 0 }
 
@@ -919,12 +1115,12 @@ impl StringBuffer for NullStringBuffer {
     }
 
     #[inline]
-    fn tail(&self, _index: usize) -> &str {
-        ""
+    fn tail(&self, _index: usize) -> Cow<'_, str> {
+        Cow::Borrowed("")
     }
 
     #[inline]
-    fn slice(&self, _start_index: usize, _end_index: usize) -> &str {
-        ""
+    fn slice(&self, _start_index: usize, _end_index: usize) -> Cow<'_, str> {
+        Cow::Borrowed("")
     }
 }
@@ -1,24 +1,24 @@
 // trying to emulate: https://github.com/theskumar/python-dotenv/blob/main/src/dotenv/parser.py
-use std::{borrow::Cow, io::BufRead, path::Path};
+use std::{io::BufRead, path::Path};
 
-use crate::{env::GetEnv, Env, Error, Options, Result, DEBUG_PREFIX};
+use crate::{env::GetEnv, error::SourceLocation, Diagnostic, DiagnosticCode, Env, Error, Options, Origin, Result};
 
 pub fn config_python_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, options: &Options<&Path>) -> Result<()> {
     let mut string = String::new();
-    options.encoding.read_to_string(reader, &mut string)?;
-    let mut reader = Reader::new(string, options);
+    options.read_to_string(reader, &mut string)?;
+    let mut reader = Reader::new(string);
 
     while reader.has_next() {
         let binding = match reader.parse_binding() {
             Ok(None) => continue,
             Ok(Some(binding)) => binding,
             Err(err) => {
-                if options.debug {
+                if options.diagnostics_enabled() {
                     if let Some(loc) = err.location() {
-                        eprintln!("{DEBUG_PREFIX}{}:{}:{}: invalid syntax", reader.path, loc.lineno(), loc.column());
+                        options.report(Diagnostic::new(DiagnosticCode::SyntaxError, "invalid syntax").at(loc.lineno(), loc.column()));
                     } else {
-                        let line_start = reader.string[..reader.position.index].rfind(|ch: char| ch == '\n' || ch == '\r').unwrap_or(0);
-                        eprintln!("{DEBUG_PREFIX}{}:{}:{}: invalid syntax", reader.path, reader.position.lineno, reader.position.index - line_start + 1);
+                        let line_start = reader.string[..reader.position.index].rfind(['\n', '\r']).unwrap_or(0);
+                        options.report(Diagnostic::new(DiagnosticCode::SyntaxError, "invalid syntax").at(reader.position.lineno, reader.position.index - line_start + 1));
                     }
                 }
                 if options.strict {
@@ -29,19 +29,14 @@ pub fn config_python_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, options
         };
 
         let Some(key) = &binding.key else {
-            if options.debug {
-                eprintln!("{DEBUG_PREFIX}{}:{}: invalid syntax parsing key", reader.path, reader.mark.lineno);
-            }
-            if options.strict {
-                return Err(Error::syntax_error(reader.mark.lineno, 1));
-            }
+            // a comment line, which python-dotenv skips without a warning
             continue;
         };
 
         if let Some(value) = &binding.value {
             // the original has interpolation as an option, but defaults to true
-            let value = interpolate(value, env.as_get_env());
-            options.set_var_cut_null(env, key, &value);
+            let (value, substituted) = interpolate(value, env.as_get_env(), options, binding.origin.location())?;
+            options.set_var_cut_null(env, key, &value, binding.origin.substituted(substituted));
         } else {
             let key = key.split('\0').next().unwrap();
             env.remove(key.as_ref());
@@ -52,19 +47,16 @@ pub fn config_python_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, options
 }
 
 // see: https://github.com/theskumar/python-dotenv/blob/main/src/dotenv/variables.py
-fn interpolate(mut src: &str, env: &dyn GetEnv) -> String {
+fn interpolate(mut src: &str, env: &dyn GetEnv, options: &Options<&Path>, location: SourceLocation) -> Result<(String, bool)> {
     let mut buf = String::new();
+    let mut substituted = false;
 
-    loop {
-        let Some(index) = src.find("${") else {
-            break;
-        };
-
+    while let Some(index) = src.find("${") {
         buf.push_str(&src[..index]);
         src = &src[index + 2..];
         let bak = src;
 
-        let index = src.find(|ch: char| ch == ':' || ch == '}').unwrap_or(src.len());
+        let index = src.find([':', '}']).unwrap_or(src.len());
         let key = &src[..index];
         src = &src[index..];
 
@@ -85,9 +77,10 @@ fn interpolate(mut src: &str, env: &dyn GetEnv) -> String {
         }
 
         src = &src[1..];
+        substituted = true;
 
         if let Some(value) = env.get(key.as_ref()) {
-            buf.push_str(value.to_string_lossy().as_ref());
+            buf.push_str(&options.var_value_str(key, &value, location)?);
         } else {
             buf.push_str(default);
         }
@@ -95,7 +88,7 @@ fn interpolate(mut src: &str, env: &dyn GetEnv) -> String {
 
     buf.push_str(src);
 
-    buf
+    Ok((buf, substituted))
 }
 
 struct Position {
@@ -129,23 +122,22 @@ impl Position {
 struct Binding {
     key: Option<String>,
     value: Option<String>,
+    origin: Origin,
 }
 
-struct Reader<'a> {
+struct Reader {
     string: String,
     position: Position,
     mark: Position,
-    path: Cow<'a, str>,
 }
 
-impl<'a> Reader<'a> {
+impl Reader {
     #[inline]
-    pub fn new(string: String, options: &Options<&'a Path>) -> Self {
+    pub fn new(string: String) -> Self {
         Self {
             string,
             position: Position::start(),
             mark: Position::start(),
-            path: options.path.to_string_lossy()
         }
     }
 
@@ -172,10 +164,10 @@ impl<'a> Reader<'a> {
         self.string[self.position.index..].chars().next()
     }
 
-    pub fn read_pattern(&mut self, pattern: fn(string: &str, index: usize) -> Option<Match>) -> Result<Match> {
+    pub fn read_pattern(&mut self, pattern: fn(string: &str, index: usize) -> Option<Match>) -> Result<Match<'_>> {
         let Some(m) = pattern(&self.string, self.position.index) else {
             let line_start = self.string[..self.position.index].
-                rfind(|ch| ch == '\n' || ch == '\r').
+                rfind(['\n', '\r']).
                 map(|pos| pos + 1).
                 unwrap_or(0);
             return Err(Error::syntax_error(self.position.lineno, 1 + self.position.index - line_start));
@@ -186,6 +178,12 @@ impl<'a> Reader<'a> {
         Ok(m)
     }
 
+    fn origin(&self) -> Origin {
+        let index = self.position.index;
+        let line_start = self.string[..index].rfind(['\n', '\r']).map(|index| index + 1).unwrap_or(0);
+        Origin::new(self.position.lineno, index - line_start + 1)
+    }
+
     fn parse_key(&mut self) -> Result<Option<String>> {
         let Some(ch) = self.peek() else {
             return Ok(None);
@@ -208,10 +206,7 @@ impl<'a> Reader<'a> {
         let res = self.read_pattern(match_unquoted_value)?;
         let mut value = res.value.unwrap_or("");
         let mut index = 0;
-        loop {
-            let Some(pos) = value[index..].find('#') else {
-                break;
-            };
+        while let Some(pos) = value[index..].find('#') {
             let hash_index = index + pos;
 
             if value[..hash_index].ends_with(|ch: char| ch.is_whitespace()) {
@@ -256,6 +251,7 @@ impl<'a> Reader<'a> {
 
             reader.read_pattern(match_export)?;
 
+            let mut origin = reader.origin();
             let key = reader.parse_key()?;
 
             reader.read_pattern(match_whitespace)?;
@@ -263,6 +259,7 @@ impl<'a> Reader<'a> {
             let value = match reader.peek() {
                 Some('=') => {
                     reader.read_pattern(match_equal_sign)?;
+                    origin = origin.quoted(matches!(reader.peek(), Some('\'' | '"')));
                     Some(reader.parse_value()?)
                 },
                 _ => None
@@ -274,6 +271,7 @@ impl<'a> Reader<'a> {
             Ok(Some(Binding {
                 key,
                 value,
+                origin,
             }))
         }
 
@@ -293,7 +291,7 @@ struct Match<'a> {
     end_index: usize,
 }
 
-fn match_multiline_whitespace(string: &str, index: usize) -> Option<Match> {
+fn match_multiline_whitespace(string: &str, index: usize) -> Option<Match<'_>> {
     let Some(slice) = string.get(index..) else {
         return Some(Match {
             value: None,
@@ -322,7 +320,7 @@ fn is_inline_whitespace(ch: char) -> bool {
     ch != '\r' && ch != '\n' && ch.is_whitespace()
 }
 
-fn match_whitespace(string: &str, index: usize) -> Option<Match> {
+fn match_whitespace(string: &str, index: usize) -> Option<Match<'_>> {
     let Some(slice) = string.get(index..) else {
         return Some(Match {
             value: None,
@@ -346,7 +344,7 @@ fn match_whitespace(string: &str, index: usize) -> Option<Match> {
     })
 }
 
-fn match_export(string: &str, index: usize) -> Option<Match> {
+fn match_export(string: &str, index: usize) -> Option<Match<'_>> {
     let slice = &string[index..];
 
     let Some(slice) = slice.strip_prefix("export") else {
@@ -382,7 +380,7 @@ fn match_export(string: &str, index: usize) -> Option<Match> {
     })
 }
 
-fn match_single_quoted_key(string: &str, index: usize) -> Option<Match> {
+fn match_single_quoted_key(string: &str, index: usize) -> Option<Match<'_>> {
     let slice = &string[index..];
 
     if !slice.starts_with('\'') {
@@ -403,7 +401,7 @@ fn match_single_quoted_key(string: &str, index: usize) -> Option<Match> {
     })
 }
 
-fn match_unquoted_key(string: &str, index: usize) -> Option<Match> {
+fn match_unquoted_key(string: &str, index: usize) -> Option<Match<'_>> {
     let slice = &string[index..];
 
     let pos = slice.find(|ch: char| ch == '=' || ch == '#' || ch.is_whitespace()).unwrap_or(slice.len());
@@ -419,7 +417,7 @@ fn match_unquoted_key(string: &str, index: usize) -> Option<Match> {
     })
 }
 
-fn match_equal_sign(string: &str, index: usize) -> Option<Match> {
+fn match_equal_sign(string: &str, index: usize) -> Option<Match<'_>> {
     let slice = &string[index..];
 
     if !slice.starts_with('=') {
@@ -437,16 +435,16 @@ fn match_equal_sign(string: &str, index: usize) -> Option<Match> {
 }
 
 #[inline]
-fn match_single_quoted_value(string: &str, index: usize) -> Option<Match> {
+fn match_single_quoted_value(string: &str, index: usize) -> Option<Match<'_>> {
     match_quoted_value(string, index, '\'')
 }
 
 #[inline]
-fn match_double_quoted_value(string: &str, index: usize) -> Option<Match> {
+fn match_double_quoted_value(string: &str, index: usize) -> Option<Match<'_>> {
     match_quoted_value(string, index, '"')
 }
 
-fn match_quoted_value(string: &str, index: usize, quote: char) -> Option<Match> {
+fn match_quoted_value(string: &str, index: usize, quote: char) -> Option<Match<'_>> {
     // emulating the regex r"'((?:\\'|[^'])*)'" including backtracking so it
     // also matches r"'\'"
     let slice = &string[index..];
@@ -473,10 +471,10 @@ fn match_quoted_value(string: &str, index: usize, quote: char) -> Option<Match>
     })
 }
 
-fn match_unquoted_value(string: &str, index: usize) -> Option<Match> {
+fn match_unquoted_value(string: &str, index: usize) -> Option<Match<'_>> {
     let slice = &string[index..];
 
-    let pos = slice.find(|ch: char| ch == '\n' || ch == '\r').unwrap_or(slice.len());
+    let pos = slice.find(['\n', '\r']).unwrap_or(slice.len());
 
     if pos == 0 {
         return None;
@@ -489,7 +487,7 @@ fn match_unquoted_value(string: &str, index: usize) -> Option<Match> {
     })
 }
 
-fn match_comment(string: &str, index: usize) -> Option<Match> {
+fn match_comment(string: &str, index: usize) -> Option<Match<'_>> {
     let Some(slice) = string.get(index..) else {
         return Some(Match {
             value: None,
@@ -515,7 +513,7 @@ fn match_comment(string: &str, index: usize) -> Option<Match> {
         });
     }
 
-    let end_index = if let Some(pos) = slice.find(|ch: char| ch == '\n' || ch == '\r') {
+    let end_index = if let Some(pos) = slice.find(['\n', '\r']) {
         pos
     } else {
         slice.len()
@@ -528,7 +526,7 @@ fn match_comment(string: &str, index: usize) -> Option<Match> {
     })
 }
 
-fn match_end_of_line(string: &str, index: usize) -> Option<Match> {
+fn match_end_of_line(string: &str, index: usize) -> Option<Match<'_>> {
     let Some(slice) = string.get(index..) else {
         return Some(Match {
             value: None,
@@ -548,7 +546,7 @@ fn match_end_of_line(string: &str, index: usize) -> Option<Match> {
     let slice = &slice[pos..];
     let end_index = index + pos + if slice.starts_with("\r\n") {
         2
-    } else if slice.starts_with(|ch: char| ch == '\n' || ch == '\r') {
+    } else if slice.starts_with(['\n', '\r']) {
         1
     } else {
         return None;
@@ -561,7 +559,7 @@ fn match_end_of_line(string: &str, index: usize) -> Option<Match> {
     })
 }
 
-fn match_rest_of_line(string: &str, index: usize) -> Option<Match> {
+fn match_rest_of_line(string: &str, index: usize) -> Option<Match<'_>> {
     let Some(slice) = string.get(index..) else {
         return Some(Match {
             value: None,
@@ -570,7 +568,7 @@ fn match_rest_of_line(string: &str, index: usize) -> Option<Match> {
         });
     };
 
-    let Some(pos) = slice.find(|ch: char| ch == '\n' || ch == '\r') else {
+    let Some(pos) = slice.find(['\n', '\r']) else {
         return Some(Match {
             value: None,
             start_index: index,
@@ -596,7 +594,7 @@ fn count_newlines(mut src: &str) -> usize {
     let mut count = 0;
 
     while !src.is_empty() {
-        let Some(index) = src.find(|ch| ch == '\n' || ch == '\r') else {
+        let Some(index) = src.find(['\n', '\r']) else {
             break;
         };
 
@@ -616,11 +614,7 @@ fn count_newlines(mut src: &str) -> usize {
 fn decode_single_quote_escapes(mut value: &str) -> String {
     let mut buf = String::with_capacity(value.len());
 
-    loop {
-        let Some(index) = value.find('\\') else {
-            break;
-        };
-
+    while let Some(index) = value.find('\\') {
         buf.push_str(&value[..index]);
 
         value = &value[index + 1..];
@@ -643,11 +637,7 @@ fn decode_single_quote_escapes(mut value: &str) -> String {
 fn decode_double_quote_escapes(mut value: &str) -> String {
     let mut buf = String::with_capacity(value.len());
 
-    loop {
-        let Some(index) = value.find('\\') else {
-            break;
-        };
-
+    while let Some(index) = value.find('\\') {
         buf.push_str(&value[..index]);
 
         value = &value[index + 1..];
@@ -1,13 +1,11 @@
 // trying to emulate: https://github.com/venthur/dotenv-cli/blob/master/dotenv_cli/core.py
 use std::{io::BufRead, path::Path};
 
-use crate::{Env, Error, Options, Result, DEBUG_PREFIX};
+use crate::{Diagnostic, DiagnosticCode, Env, Error, Options, Origin, Result};
 
 pub fn config_python_dotenv_cli(reader: &mut dyn BufRead, env: &mut dyn Env, options: &Options<&Path>) -> Result<()> {
-    let path_str = options.path.to_string_lossy();
-
     let mut lines = String::new();
-    options.encoding.read_to_string(reader, &mut lines)?;
+    options.read_to_string(reader, &mut lines)?;
 
     let mut lines = &lines[..];
     let mut value_buf = String::new();
@@ -16,7 +14,7 @@ pub fn config_python_dotenv_cli(reader: &mut dyn BufRead, env: &mut dyn Env, opt
         lineno += 1;
         let mut line;
         // split on "\n", "\r\n", and "\r"
-        if let Some(index) = lines.find(|p| p == '\r' || p == '\n') {
+        if let Some(index) = lines.find(['\r', '\n']) {
             line = &lines[..index];
             lines = &lines[index..];
             if lines.starts_with("\r\n") {
@@ -31,6 +29,7 @@ pub fn config_python_dotenv_cli(reader: &mut dyn BufRead, env: &mut dyn Env, opt
             }
         }
 
+        let raw_line = line;
         line = line.trim();
 
         // ignore comments
@@ -54,8 +53,12 @@ pub fn config_python_dotenv_cli(reader: &mut dyn BufRead, env: &mut dyn Env, opt
         key = key.trim();
         value = value.trim();
 
+        let column = key.as_ptr() as usize - raw_line.as_ptr() as usize + 1;
+        let mut origin = Origin::new(lineno, column);
+
         // remove quotes (not sure if this is standard behaviour)
         if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+            origin = origin.quoted(true);
             value = &value[1..value.len() - 1];
 
             // decode escape characters
@@ -69,8 +72,8 @@ pub fn config_python_dotenv_cli(reader: &mut dyn BufRead, env: &mut dyn Env, opt
                 value_buf.push_str(&value[..index]);
                 value = &value[index + 1..];
                 let Some(ch) = value.chars().next() else {
-                    if options.debug {
-                        eprintln!("{DEBUG_PREFIX}{path_str}:{lineno}: truncated escape sequence");
+                    if options.diagnostics_enabled() {
+                        options.report(Diagnostic::new(DiagnosticCode::IllegalEscape, "truncated escape sequence").at_line(lineno));
                     }
                     if options.strict {
                         return Err(Error::syntax_error(lineno, 1));
@@ -116,17 +119,17 @@ pub fn config_python_dotenv_cli(reader: &mut dyn BufRead, env: &mut dyn Env, opt
                     }
                     '0'|'1'|'2'|'3'|'4'|'5'|'6'|'7' => {
                         let mut end_index = 1;
-                        if value.len() > 1 && value[1..].starts_with(|ch| matches!(ch, '0'|'1'|'2'|'3'|'4'|'5'|'6'|'7')) {
+                        if value.len() > 1 && value[1..].starts_with(['0', '1', '2', '3', '4', '5', '6', '7']) {
                             end_index += 1;
-                            if value.len() > 2 && value[2..].starts_with(|ch| matches!(ch, '0'|'1'|'2'|'3'|'4'|'5'|'6'|'7')) {
+                            if value.len() > 2 && value[2..].starts_with(['0', '1', '2', '3', '4', '5', '6', '7']) {
                                 end_index += 1;
                             }
                         }
 
                         let arg = &value[..end_index];
                         let Ok(ch) = u8::from_str_radix(arg, 8) else {
-                            if options.debug {
-                                eprintln!("{DEBUG_PREFIX}{path_str}:{lineno}: invalid octal escape sequence: \\{}", arg);
+                            if options.diagnostics_enabled() {
+                                options.report(Diagnostic::new(DiagnosticCode::IllegalEscape, format!("invalid octal escape sequence: \\{}", arg)).at_line(lineno));
                             }
                             if options.strict {
                                 return Err(Error::syntax_error(lineno, 1));
@@ -139,8 +142,8 @@ pub fn config_python_dotenv_cli(reader: &mut dyn BufRead, env: &mut dyn Env, opt
                     }
                     'x' => {
                         if value.len() < 3 {
-                            if options.debug {
-                                eprintln!("{DEBUG_PREFIX}{path_str}:{lineno}: invalid hex escape sequence: \\{}", value);
+                            if options.diagnostics_enabled() {
+                                options.report(Diagnostic::new(DiagnosticCode::IllegalEscape, format!("invalid hex escape sequence: \\{}", value)).at_line(lineno));
                             }
                             if options.strict {
                                 return Err(Error::syntax_error(lineno, 1));
@@ -151,8 +154,8 @@ pub fn config_python_dotenv_cli(reader: &mut dyn BufRead, env: &mut dyn Env, opt
 
                         let arg = &value[1..3];
                         let Ok(ch) = u8::from_str_radix(arg, 16) else {
-                            if options.debug {
-                                eprintln!("{DEBUG_PREFIX}{path_str}:{lineno}: invalid hex escape sequence: \\x{}", arg);
+                            if options.diagnostics_enabled() {
+                                options.report(Diagnostic::new(DiagnosticCode::IllegalEscape, format!("invalid hex escape sequence: \\x{}", arg)).at_line(lineno));
                             }
                             if options.strict {
                                 return Err(Error::syntax_error(lineno, 1));
@@ -165,8 +168,8 @@ pub fn config_python_dotenv_cli(reader: &mut dyn BufRead, env: &mut dyn Env, opt
                     }
                     'u' => {
                         if value.len() < 5 {
-                            if options.debug {
-                                eprintln!("{DEBUG_PREFIX}{path_str}:{lineno}: invalid unicode escape sequence: \\{}", value);
+                            if options.diagnostics_enabled() {
+                                options.report(Diagnostic::new(DiagnosticCode::IllegalEscape, format!("invalid unicode escape sequence: \\{}", value)).at_line(lineno));
                             }
                             if options.strict {
                                 return Err(Error::syntax_error(lineno, 1));
@@ -177,8 +180,8 @@ pub fn config_python_dotenv_cli(reader: &mut dyn BufRead, env: &mut dyn Env, opt
 
                         let arg = &value[1..5];
                         let Ok(ch) = u16::from_str_radix(arg, 16) else {
-                            if options.debug {
-                                eprintln!("{DEBUG_PREFIX}{path_str}:{lineno}: invalid unicode escape sequence: \\u{}", arg);
+                            if options.diagnostics_enabled() {
+                                options.report(Diagnostic::new(DiagnosticCode::IllegalEscape, format!("invalid unicode escape sequence: \\u{}", arg)).at_line(lineno));
                             }
                             if options.strict {
                                 return Err(Error::syntax_error(lineno, 1));
@@ -187,8 +190,8 @@ pub fn config_python_dotenv_cli(reader: &mut dyn BufRead, env: &mut dyn Env, opt
                             continue;
                         };
                         let Some(ch) = char::from_u32(ch.into()) else {
-                            if options.debug {
-                                eprintln!("{DEBUG_PREFIX}{path_str}:{lineno}: invalid unicode escape sequence: \\u{}", arg);
+                            if options.diagnostics_enabled() {
+                                options.report(Diagnostic::new(DiagnosticCode::IllegalEscape, format!("invalid unicode escape sequence: \\u{}", arg)).at_line(lineno));
                             }
                             if options.strict {
                                 return Err(Error::syntax_error(lineno, 1));
@@ -201,8 +204,8 @@ pub fn config_python_dotenv_cli(reader: &mut dyn BufRead, env: &mut dyn Env, opt
                     }
                     'U' => {
                         if value.len() < 9 {
-                            if options.debug {
-                                eprintln!("{DEBUG_PREFIX}{path_str}:{lineno}: invalid unicode escape sequence: \\{}", value);
+                            if options.diagnostics_enabled() {
+                                options.report(Diagnostic::new(DiagnosticCode::IllegalEscape, format!("invalid unicode escape sequence: \\{}", value)).at_line(lineno));
                             }
                             if options.strict {
                                 return Err(Error::syntax_error(lineno, 1));
@@ -213,8 +216,8 @@ pub fn config_python_dotenv_cli(reader: &mut dyn BufRead, env: &mut dyn Env, opt
 
                         let arg = &value[1..9];
                         let Ok(ch) = u32::from_str_radix(arg, 16) else {
-                            if options.debug {
-                                eprintln!("{DEBUG_PREFIX}{path_str}:{lineno}: invalid unicode escape sequence: \\U{}", arg);
+                            if options.diagnostics_enabled() {
+                                options.report(Diagnostic::new(DiagnosticCode::IllegalEscape, format!("invalid unicode escape sequence: \\U{}", arg)).at_line(lineno));
                             }
                             if options.strict {
                                 return Err(Error::syntax_error(lineno, 1));
@@ -223,8 +226,8 @@ pub fn config_python_dotenv_cli(reader: &mut dyn BufRead, env: &mut dyn Env, opt
                             continue;
                         };
                         let Some(ch) = char::from_u32(ch) else {
-                            if options.debug {
-                                eprintln!("{DEBUG_PREFIX}{path_str}:{lineno}: invalid unicode escape sequence: \\U{}", arg);
+                            if options.diagnostics_enabled() {
+                                options.report(Diagnostic::new(DiagnosticCode::IllegalEscape, format!("invalid unicode escape sequence: \\U{}", arg)).at_line(lineno));
                             }
                             if options.strict {
                                 return Err(Error::syntax_error(lineno, 1));
@@ -236,8 +239,8 @@ pub fn config_python_dotenv_cli(reader: &mut dyn BufRead, env: &mut dyn Env, opt
                         value = &value[9..];
                     }
                     _ => {
-                        if options.debug {
-                            eprintln!("{DEBUG_PREFIX}{path_str}:{lineno}: invalid escape sequence: \\{}", ch);
+                        if options.diagnostics_enabled() {
+                            options.report(Diagnostic::new(DiagnosticCode::IllegalEscape, format!("invalid escape sequence: \\{}", ch)).at_line(lineno));
                         }
                         if options.strict {
                             return Err(Error::syntax_error(lineno, 1));
@@ -250,9 +253,10 @@ pub fn config_python_dotenv_cli(reader: &mut dyn BufRead, env: &mut dyn Env, opt
             value = &value_buf[..];
         } else if value.len() >= 2 && value.starts_with('\'') && value.ends_with('\'') {
             value = &value[1..value.len() - 1];
+            origin = origin.quoted(true);
         }
 
-        options.set_var_cut_null(env, key, value);
+        options.set_var_cut_null(env, key, value, origin);
     }
 
     Ok(())
@@ -1,7 +1,7 @@
 // trying to emulate: https://github.com/bkeepers/dotenv/blob/27c80ed122f9bbe403033282e922d74ca717d518/lib/dotenv/parser.rb
 use std::{ffi::OsStr, io::BufRead, path::Path};
 
-use crate::{env::GetEnv, Env, Error, Options, Result, DEBUG_PREFIX};
+use crate::{env::GetEnv, error::SourceLocation, Diagnostic, DiagnosticCode, Env, Error, Options, Origin, Result};
 
 pub fn config_ruby_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dyn GetEnv, options: &Options<&Path>) -> Result<()> {
     let legacy_linebreak = if let Some(value) = parent.get("DOTENV_LINEBREAK_MODE".as_ref()) {
@@ -10,9 +10,8 @@ pub fn config_ruby_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &
     } else {
         false
     };
-    let path_str = options.path.to_string_lossy();
     let mut buf = String::new();
-    options.encoding.read_to_string(reader, &mut buf)?;
+    options.read_to_string(reader, &mut buf)?;
 
     fix_newlines(&mut buf);
 
@@ -57,14 +56,12 @@ pub fn config_ruby_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &
         if key_start == key_end {
             let line_end = find_line_end(&parser.buf, parser.index);
             let column = parser.index - parser.line_start + 1;
-            if options.debug {
+            if options.diagnostics_enabled() {
                 let line = &parser.buf[parser.line_start..line_end];
                 if let Some(ch) = parser.buf[key_start..].chars().next() {
-                    eprintln!("{DEBUG_PREFIX}{path_str}:{}:{}: expected variable name, found {:?}: {}",
-                        parser.lineno, column, ch, line);
+                    options.report(Diagnostic::new(DiagnosticCode::SyntaxError, format!("expected variable name, found {:?}: {}", ch, line)).at(parser.lineno, column));
                 } else {
-                    eprintln!("{DEBUG_PREFIX}{path_str}:{}:{}: unexpected end of file: {}",
-                        parser.lineno, column, line);
+                    options.report(Diagnostic::new(DiagnosticCode::SyntaxError, format!("unexpected end of file: {}", line)).at(parser.lineno, column));
                 }
             }
             if options.strict {
@@ -82,10 +79,9 @@ pub fn config_ruby_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &
                 if env.get(key.as_ref()).is_none() {
                     let line_end = find_line_end(&parser.buf, parser.index);
                     let column = key_end - key_line_start + 1;
-                    if options.debug {
+                    if options.diagnostics_enabled() {
                         let line = &parser.buf[parser.line_start..line_end];
-                        eprintln!("{DEBUG_PREFIX}{path_str}:{}:{}: variable {key:?} is unset in line: {}",
-                            key_lineno, column, line);
+                        options.report(Diagnostic::new(DiagnosticCode::SyntaxError, format!("variable {key:?} is unset in line: {}", line)).at(key_lineno, column));
                     }
                     if options.strict {
                         return Err(Error::syntax_error(parser.lineno, column));
@@ -102,7 +98,7 @@ pub fn config_ruby_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &
 
                 parser.skip_ws_inline();
 
-                if parser.buf[parser.index..].starts_with(|ch| ch == '#' || ch == '\n') {
+                if parser.buf[parser.index..].starts_with(['#', '\n']) {
                     parser.index = find_line_end(&parser.buf, parser.index);
                     break;
                 }
@@ -120,14 +116,12 @@ pub fn config_ruby_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &
                 if key_start == key_end {
                     let line_end = find_line_end(&parser.buf, parser.index);
                     let column = parser.index - parser.line_start + 1;
-                    if options.debug {
+                    if options.diagnostics_enabled() {
                         let line = &parser.buf[parser.line_start..line_end];
                         if let Some(ch) = parser.buf[key_start..].chars().next() {
-                            eprintln!("{DEBUG_PREFIX}{path_str}:{}:{}: expected variable name, found {:?}: {}",
-                                parser.lineno, column, ch, line);
+                            options.report(Diagnostic::new(DiagnosticCode::SyntaxError, format!("expected variable name, found {:?}: {}", ch, line)).at(parser.lineno, column));
                         } else {
-                            eprintln!("{DEBUG_PREFIX}{path_str}:{}:{}: unexpected end of file: {}",
-                                parser.lineno, column, line);
+                            options.report(Diagnostic::new(DiagnosticCode::SyntaxError, format!("unexpected end of file: {}", line)).at(parser.lineno, column));
                         }
                     }
                     if options.strict {
@@ -138,19 +132,17 @@ pub fn config_ruby_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &
                 }
             }
             continue;
-        } else if !tail.starts_with(|ch| ch == '=' || ch == ':') {
+        } else if !tail.starts_with(['=', ':']) {
             let line_end = find_line_end(&parser.buf, parser.index);
             let column = parser.index - parser.line_start + 1;
             let ch = tail.chars().next();
 
-            if options.debug {
+            if options.diagnostics_enabled() {
                 let line = &parser.buf[parser.line_start..line_end];
                 if let Some(ch) = ch {
-                    eprintln!("{DEBUG_PREFIX}{path_str}:{}:{}: expected '=' or ':', found {:?}: {}",
-                        parser.lineno, column, ch, line);
+                    options.report(Diagnostic::new(DiagnosticCode::SyntaxError, format!("expected '=' or ':', found {:?}: {}", ch, line)).at(parser.lineno, column));
                 } else {
-                    eprintln!("{DEBUG_PREFIX}{path_str}:{}:{}: unexpected end of file: {}",
-                        parser.lineno, column, line);
+                    options.report(Diagnostic::new(DiagnosticCode::SyntaxError, format!("unexpected end of file: {}", line)).at(parser.lineno, column));
                 }
             }
 
@@ -168,10 +160,9 @@ pub fn config_ruby_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &
             let line_end = find_line_end(&parser.buf, parser.index);
             let column = parser.index - parser.line_start + 1;
 
-            if options.debug {
+            if options.diagnostics_enabled() {
                 let line = &parser.buf[parser.line_start..line_end];
-                eprintln!("{DEBUG_PREFIX}{path_str}:{}:{}: there may be no space between the variable name and ':': {}",
-                    parser.lineno, column, line);
+                options.report(Diagnostic::new(DiagnosticCode::SyntaxError, format!("there may be no space between the variable name and ':': {}", line)).at(parser.lineno, column));
             }
 
             if options.strict {
@@ -227,16 +218,18 @@ pub fn config_ruby_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &
 
         let value_slice = &parser.buf[value_start..value_end];
 
-        let value;
-        if value_slice.len() > 1 && value_slice.starts_with('\'') && value_slice.ends_with('\'') {
-            value = parser.buf[value_start + 1..value_end - 1].to_owned();
+        let origin = Origin::new(key_lineno, key_start - key_line_start + 1);
+        let (value, origin) = if value_slice.len() > 1 && value_slice.starts_with('\'') && value_slice.ends_with('\'') {
+            (parser.buf[value_start + 1..value_end - 1].to_owned(), origin.quoted(true))
         } else if value_slice.len() > 1 && value_slice.starts_with('"') && value_slice.ends_with('"') {
-            value = perform_substitutions(&unescape_double_quoted(&parser.buf[value_start + 1..value_end - 1], env, legacy_linebreak), env.as_get_env());
+            let (value, substituted) = perform_substitutions(&unescape_double_quoted(&parser.buf[value_start + 1..value_end - 1], env, legacy_linebreak), env.as_get_env(), options, origin.location())?;
+            (value, origin.quoted(true).substituted(substituted))
         } else {
-            value = perform_substitutions(&unescape_single_unquoted(value_slice.trim_end_matches(|ch| matches!(ch, '\t' | '\x0B' | '\x0C' | ' '))), env.as_get_env());
-        }
+            let (value, substituted) = perform_substitutions(&unescape_single_unquoted(value_slice.trim_end_matches(['\t', '\x0B', '\x0C', ' '])), env.as_get_env(), options, origin.location())?;
+            (value, origin.substituted(substituted))
+        };
 
-        options.set_var_cut_null(env, parser.buf[key_start..key_end].as_ref(), value.as_ref());
+        options.set_var_cut_null(env, parser.buf[key_start..key_end].as_ref(), value.as_ref(), origin);
 
         parser.skip_ws_inline();
         let Some(ch) = parser.buf[parser.index..].chars().next() else {
@@ -249,10 +242,9 @@ pub fn config_ruby_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &
             let line_end = find_line_end(&parser.buf, parser.index);
             let column = parser.index - parser.line_start + 1;
 
-            if options.debug {
+            if options.diagnostics_enabled() {
                 let line = &parser.buf[parser.line_start..line_end];
-                eprintln!("{DEBUG_PREFIX}{path_str}:{}:{}: expected line end, found {:?}: {}",
-                    parser.lineno, column, ch, line);
+                options.report(Diagnostic::new(DiagnosticCode::SyntaxError, format!("expected line end, found {:?}: {}", ch, line)).at(parser.lineno, column));
             }
 
             if options.strict {
@@ -265,14 +257,11 @@ pub fn config_ruby_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &
     Ok(())
 }
 
-fn perform_substitutions(mut src: &str, env: &dyn GetEnv) -> String {
+fn perform_substitutions(mut src: &str, env: &dyn GetEnv, options: &Options<&Path>, location: SourceLocation) -> Result<(String, bool)> {
     let mut buf = String::new();
+    let mut substituted = false;
 
-    loop {
-        let Some(index) = src.find(|ch| ch == '$' || ch == '\\') else {
-            break;
-        };
-
+    while let Some(index) = src.find(['$', '\\']) {
         buf.push_str(&src[..index]);
         src = &src[index..];
 
@@ -299,8 +288,9 @@ fn perform_substitutions(mut src: &str, env: &dyn GetEnv) -> String {
                 src = &src[var_end..];
             } else {
                 let key = &src[var_start..var_end];
+                substituted = true;
                 if let Some(value) = env.get(key.as_ref()) {
-                    buf.push_str(&value.to_string_lossy());
+                    buf.push_str(&options.var_value_str(key, &value, location)?);
                 }
                 // yes, the { is independent to the } in the original!
                 src = &src[var_end..];
@@ -313,7 +303,7 @@ fn perform_substitutions(mut src: &str, env: &dyn GetEnv) -> String {
 
     buf.push_str(src);
 
-    buf
+    Ok((buf, substituted))
 }
 
 fn unescape_single_unquoted(mut value: &str) -> String {
@@ -431,11 +421,7 @@ impl Parser {
 
         // Count newlines in the parsed string and set the line_start offset.
         let mut slice = &slice[..end_index];
-        loop {
-            let Some(index) = slice.find('\n') else {
-                break;
-            };
-
+        while let Some(index) = slice.find('\n') {
             self.lineno += 1;
             self.line_start += index + 1;
             slice = &slice[index + 1..];
@@ -484,7 +470,7 @@ fn find_value_end(src: &str, index: usize) -> usize {
     let Some(slice) = src.get(index..) else {
         return src.len();
     };
-    let Some(pos) = slice.find(|ch| ch == '\n' || ch == '#') else {
+    let Some(pos) = slice.find(['\n', '#']) else {
         return src.len();
     };
     pos + index
@@ -503,10 +489,7 @@ fn find_line_end(src: &str, index: usize) -> usize {
 
 fn fix_newlines(buf: &mut String) {
     let mut index = 0;
-    loop {
-        let Some(pos) = buf[index..].find('\r') else {
-            break;
-        };
+    while let Some(pos) = buf[index..].find('\r') {
         let cr_index = pos + index;
 
         if buf[cr_index..].starts_with("\r\n") {
@@ -0,0 +1,272 @@
+// trying to emulate: https://github.com/systemd/systemd/blob/v256/src/basic/env-file.c
+// plus the checks done by strv_env_clean() for EnvironmentFile=
+use std::{io::BufRead, path::Path};
+
+use crate::{parse::LineTracker, Diagnostic, DiagnosticCode, Env, Error, Options, Origin, Result};
+
+const NEWLINE: &[char] = &['\n', '\r'];
+const WHITESPACE: &[char] = &[' ', '\t', '\n', '\r'];
+const COMMENTS: &[char] = &['#', ';'];
+const SHELL_NEED_ESCAPE: &[char] = &['"', '\\', '`', '$'];
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum State {
+    PreKey,
+    Key,
+    PreValue,
+    Value,
+    ValueEscape,
+    SingleQuoteValue,
+    DoubleQuoteValue,
+    DoubleQuoteValueEscape,
+    Comment,
+    CommentEscape,
+}
+
+pub fn config_systemd(reader: &mut dyn BufRead, env: &mut dyn Env, options: &Options<&Path>) -> Result<()> {
+    let mut src = String::new();
+    options.read_to_string(reader, &mut src)?;
+
+    // it's a C string
+    if let Some(index) = src.find('\0') {
+        if options.diagnostics_enabled() {
+            let origin = LineTracker::new().origin_at(&src, index);
+            let location = origin.location();
+            options.report(Diagnostic::new(DiagnosticCode::IllegalNullByte,
+                "file contains a null byte, ignoring the rest").
+                at(location.lineno(), location.column()));
+        }
+        src.truncate(index);
+    }
+
+    let mut parser = Parser {
+        src: &src,
+        options,
+        tracker: LineTracker::new(),
+        key: String::new(),
+        value: String::new(),
+        key_start: 0,
+        last_key_whitespace: None,
+        last_value_whitespace: None,
+        quoted: false,
+    };
+
+    let mut state = State::PreKey;
+
+    for (index, c) in src.char_indices() {
+        match state {
+            State::PreKey => {
+                if COMMENTS.contains(&c) {
+                    state = State::Comment;
+                } else if !WHITESPACE.contains(&c) {
+                    state = State::Key;
+                    parser.key_start = index;
+                    parser.last_key_whitespace = None;
+                    parser.key.push(c);
+                }
+            }
+            State::Key => {
+                if NEWLINE.contains(&c) {
+                    state = State::PreKey;
+                    parser.missing_equal()?;
+                } else if c == '=' {
+                    state = State::PreValue;
+                    parser.last_value_whitespace = None;
+                } else {
+                    if !WHITESPACE.contains(&c) {
+                        parser.last_key_whitespace = None;
+                    } else if parser.last_key_whitespace.is_none() {
+                        parser.last_key_whitespace = Some(parser.key.len());
+                    }
+                    parser.key.push(c);
+                }
+            }
+            State::PreValue => {
+                if NEWLINE.contains(&c) {
+                    state = State::PreKey;
+                    parser.push(env, false)?;
+                } else if c == '\'' {
+                    state = State::SingleQuoteValue;
+                    parser.quoted = true;
+                } else if c == '"' {
+                    state = State::DoubleQuoteValue;
+                    parser.quoted = true;
+                } else if c == '\\' {
+                    state = State::ValueEscape;
+                } else if !WHITESPACE.contains(&c) {
+                    state = State::Value;
+                    parser.value.push(c);
+                }
+            }
+            State::Value => {
+                if NEWLINE.contains(&c) {
+                    state = State::PreKey;
+                    parser.push(env, true)?;
+                } else if c == '\\' {
+                    state = State::ValueEscape;
+                    parser.last_value_whitespace = None;
+                } else {
+                    if !WHITESPACE.contains(&c) {
+                        parser.last_value_whitespace = None;
+                    } else if parser.last_value_whitespace.is_none() {
+                        parser.last_value_whitespace = Some(parser.value.len());
+                    }
+                    parser.value.push(c);
+                }
+            }
+            State::ValueEscape => {
+                state = State::Value;
+                // Escaped newlines we eat up entirely
+                if !NEWLINE.contains(&c) {
+                    parser.value.push(c);
+                }
+            }
+            State::SingleQuoteValue => {
+                if c == '\'' {
+                    state = State::PreValue;
+                } else {
+                    parser.value.push(c);
+                }
+            }
+            State::DoubleQuoteValue => {
+                if c == '"' {
+                    state = State::PreValue;
+                } else if c == '\\' {
+                    state = State::DoubleQuoteValueEscape;
+                } else {
+                    parser.value.push(c);
+                }
+            }
+            State::DoubleQuoteValueEscape => {
+                state = State::DoubleQuoteValue;
+                if SHELL_NEED_ESCAPE.contains(&c) {
+                    // If this is a char that needs escaping, just unescape it.
+                    parser.value.push(c);
+                } else if c != '\n' {
+                    // If other char than what needs escaping, keep the "\" in
+                    // place, like the real shell does.
+                    parser.value.push('\\');
+                    parser.value.push(c);
+                }
+                // Escaped newlines (aka "continuation lines") are eaten up entirely
+            }
+            State::Comment => {
+                if c == '\\' {
+                    state = State::CommentEscape;
+                } else if NEWLINE.contains(&c) {
+                    state = State::PreKey;
+                }
+            }
+            State::CommentEscape => {
+                // Since v254 a comment isn't continued in the next line.
+                if NEWLINE.contains(&c) {
+                    state = State::PreKey;
+                } else {
+                    state = State::Comment;
+                }
+            }
+        }
+    }
+
+    match state {
+        State::PreValue |
+        State::Value |
+        State::ValueEscape => {
+            parser.push(env, state == State::Value)?;
+        }
+        State::SingleQuoteValue |
+        State::DoubleQuoteValue |
+        State::DoubleQuoteValueEscape => {
+            parser.unterminated_string()?;
+            parser.push(env, false)?;
+        }
+        State::Key => {
+            parser.missing_equal()?;
+        }
+        State::PreKey |
+        State::Comment |
+        State::CommentEscape => {}
+    }
+
+    Ok(())
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    options: &'a Options<&'a Path>,
+    tracker: LineTracker,
+    key: String,
+    value: String,
+    key_start: usize,
+    last_key_whitespace: Option<usize>,
+    last_value_whitespace: Option<usize>,
+    quoted: bool,
+}
+
+impl<'a> Parser<'a> {
+    #[inline]
+    fn origin(&mut self) -> Origin {
+        self.tracker.origin_at(self.src, self.key_start)
+    }
+
+    fn error(&mut self, code: DiagnosticCode, message: impl Into<String>) -> Result<()> {
+        let location = self.origin().location();
+        if self.options.diagnostics_enabled() {
+            self.options.report(Diagnostic::new(code, message).
+                at(location.lineno(), location.column()));
+        }
+        if self.options.strict {
+            return Err(Error::syntax_error(location.lineno(), location.column()));
+        }
+        Ok(())
+    }
+
+    fn missing_equal(&mut self) -> Result<()> {
+        let message = format!("line without '=' is ignored: {:?}", self.key);
+        self.key.clear();
+        self.error(DiagnosticCode::SyntaxError, message)
+    }
+
+    #[inline]
+    fn unterminated_string(&mut self) -> Result<()> {
+        self.error(DiagnosticCode::UnterminatedString, "unterminated string literal")
+    }
+
+    fn push(&mut self, env: &mut dyn Env, chomp: bool) -> Result<()> {
+        // Chomp off trailing whitespace from value
+        if chomp {
+            if let Some(index) = self.last_value_whitespace {
+                self.value.truncate(index);
+            }
+        }
+
+        // strip trailing whitespace from key
+        if let Some(index) = self.last_key_whitespace {
+            self.key.truncate(index);
+        }
+
+        let key = std::mem::take(&mut self.key);
+        let value = std::mem::take(&mut self.value);
+        let quoted = std::mem::replace(&mut self.quoted, false);
+
+        if !is_valid_name(&key) {
+            return self.error(DiagnosticCode::SyntaxError,
+                format!("Ignoring invalid environment assignment '{key}={value}'"));
+        }
+
+        let origin = self.origin().quoted(quoted);
+        self.options.set_var(env, key.as_ref(), value.as_ref(), origin);
+
+        Ok(())
+    }
+}
+
+/// Emulates `env_name_is_valid()`.
+fn is_valid_name(name: &str) -> bool {
+    let Some(first) = name.chars().next() else {
+        return false;
+    };
+
+    !first.is_ascii_digit() &&
+    name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
@@ -0,0 +1,857 @@
+//! Lossless concrete syntax tree of a [`Dialect::Punktum`](crate::Dialect::Punktum) file.
+//!
+//! A [`Document`] keeps every byte of the parsed source (comments, blank
+//! lines, whitespace, quoting) so that it can be inspected, edited and then
+//! written back. An untouched document serializes to exactly the text it was
+//! parsed from.
+//!
+//! ```
+//! use punktum::document::Document;
+//!
+//! let mut doc = Document::parse("# the database\nDB_HOST = localhost # dev only\n").unwrap();
+//! doc.set("DB_HOST", "db.example.com");
+//! assert_eq!(doc.to_string(), "# the database\nDB_HOST = db.example.com # dev only\n");
+//! ```
+//!
+//! The document only checks the structure of the file (where entries, quoted
+//! strings and `${...}` substitutions begin and end), it does not evaluate
+//! values. Use [`Document::to_string()`] and the normal `config_*` functions
+//! to get the values.
+
+use std::io::BufRead;
+
+use crate::{error::SourceLocation, Encoding, Error, Result};
+
+/// Byte range into the source a node was parsed from.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    #[inline]
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum QuoteKind {
+    /// Unquoted text, including any `$VAR`/`${VAR}` substitutions and
+    /// whitespace between quoted parts.
+    Unquoted,
+    Single,
+    Double,
+}
+
+/// A run of a value that uses one quoting style, e.g. `"foo"` or `'bar'`.
+/// The raw text includes the quotes.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ValuePart {
+    quote: QuoteKind,
+    raw: String,
+    span: Option<Span>,
+}
+
+impl ValuePart {
+    #[inline]
+    pub fn quote(&self) -> QuoteKind {
+        self.quote
+    }
+
+    #[inline]
+    pub fn raw(&self) -> &str {
+        &self.raw
+    }
+
+    /// `None` if the part was created by an edit.
+    #[inline]
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+}
+
+/// `KEY=VALUE`, or just `KEY` which imports the variable from the parent
+/// environment.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Entry {
+    /// `"export"` plus the following whitespace, if present.
+    export: Option<String>,
+    key: String,
+    key_span: Option<Span>,
+    /// `=` including the surrounding whitespace, `None` for imports.
+    assign: Option<String>,
+    value: Vec<ValuePart>,
+}
+
+impl Entry {
+    /// New entry that will be written as `KEY=VALUE` with minimal quoting.
+    pub fn new(key: impl Into<String>, value: &str) -> Self {
+        let mut entry = Self {
+            export: None,
+            key: key.into(),
+            key_span: None,
+            assign: Some("=".to_owned()),
+            value: Vec::new(),
+        };
+        entry.set_value(value);
+        entry
+    }
+
+    #[inline]
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    #[inline]
+    pub fn key_span(&self) -> Option<Span> {
+        self.key_span
+    }
+
+    #[inline]
+    pub fn is_export(&self) -> bool {
+        self.export.is_some()
+    }
+
+    /// `true` for `KEY` lines without `=`, which import the variable from the
+    /// parent environment.
+    #[inline]
+    pub fn is_import(&self) -> bool {
+        self.assign.is_none()
+    }
+
+    #[inline]
+    pub fn value_parts(&self) -> &[ValuePart] {
+        &self.value
+    }
+
+    /// The value exactly as it is written in the file, quotes included.
+    pub fn raw_value(&self) -> String {
+        let mut raw = String::new();
+        for part in &self.value {
+            raw.push_str(&part.raw);
+        }
+        raw
+    }
+
+    /// Replace the value with `value`, quoted so that it reads back as
+    /// exactly that string. Turns an import into an assignment.
+    pub fn set_value(&mut self, value: &str) {
+        if self.assign.is_none() {
+            self.assign = Some("=".to_owned());
+        }
+
+        self.value.clear();
+
+        if value.is_empty() {
+            return;
+        }
+
+        if value.chars().all(is_safe_unquoted) {
+            self.value.push(ValuePart { quote: QuoteKind::Unquoted, raw: value.to_owned(), span: None });
+            return;
+        }
+
+        let mut value = value;
+        loop {
+            let index = value.find('\'').unwrap_or(value.len());
+            if index > 0 {
+                self.value.push(ValuePart { quote: QuoteKind::Single, raw: format!("'{}'", &value[..index]), span: None });
+            }
+            if index == value.len() {
+                break;
+            }
+            self.value.push(ValuePart { quote: QuoteKind::Double, raw: "\"'\"".to_owned(), span: None });
+            value = &value[index + 1..];
+        }
+    }
+
+    /// Replace the value with already quoted/escaped Punktum source text.
+    /// The text is checked to be a single well-formed value.
+    pub fn set_raw_value(&mut self, raw: &str) -> Result<()> {
+        let mut scanner = Scanner::new(raw);
+        let parts = scanner.scan_value()?;
+        if scanner.index < raw.len() {
+            return Err(scanner.syntax_error(scanner.index));
+        }
+        self.value = parts.into_iter().map(|mut part| { part.span = None; part }).collect();
+        if self.assign.is_none() {
+            self.assign = Some("=".to_owned());
+        }
+        Ok(())
+    }
+
+    fn write(&self, out: &mut String) {
+        if let Some(export) = &self.export {
+            out.push_str(export);
+        }
+        out.push_str(&self.key);
+        if let Some(assign) = &self.assign {
+            out.push_str(assign);
+        }
+        for part in &self.value {
+            out.push_str(&part.raw);
+        }
+    }
+}
+
+/// `include PATH` or `include? PATH`, which loads another file.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Include {
+    /// `include`, the optional `?` and the whitespace up to the path.
+    keyword: String,
+    path: Vec<ValuePart>,
+}
+
+impl Include {
+    /// `true` for `include? PATH`, which skips missing files.
+    #[inline]
+    pub fn is_optional(&self) -> bool {
+        self.keyword.contains('?')
+    }
+
+    #[inline]
+    pub fn path_parts(&self) -> &[ValuePart] {
+        &self.path
+    }
+
+    /// The path exactly as it is written in the file, quotes included.
+    pub fn raw_path(&self) -> String {
+        let mut raw = String::new();
+        for part in &self.path {
+            raw.push_str(&part.raw);
+        }
+        raw
+    }
+
+    fn write(&self, out: &mut String) {
+        out.push_str(&self.keyword);
+        for part in &self.path {
+            out.push_str(&part.raw);
+        }
+    }
+}
+
+/// What a line declares, besides its comment.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Statement {
+    Entry(Entry),
+    Include(Include),
+}
+
+impl Statement {
+    fn write(&self, out: &mut String) {
+        match self {
+            Statement::Entry(entry) => entry.write(out),
+            Statement::Include(include) => include.write(out),
+        }
+    }
+}
+
+#[inline]
+fn is_safe_unquoted(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || matches!(ch, '_' | '-' | '.' | '/' | ':' | ',' | '@' | '+' | '%')
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Comment {
+    /// Including the leading `#`.
+    text: String,
+    span: Option<Span>,
+}
+
+impl Comment {
+    /// `text` must not contain a newline. A leading `#` is added if missing.
+    pub fn new(text: &str) -> Self {
+        let text = text.trim_end_matches(['\n', '\r']);
+        let text = if text.starts_with('#') { text.to_owned() } else { format!("#{text}") };
+        Self { text, span: None }
+    }
+
+    #[inline]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    #[inline]
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+}
+
+/// One logical line. An entry with a multi-line value spans several physical
+/// lines.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Line {
+    indent: String,
+    statement: Option<Statement>,
+    trailing: String,
+    comment: Option<Comment>,
+    /// `"\n"`, `"\r\n"`, or `""` for the last line of a file without a
+    /// final newline.
+    newline: String,
+    span: Option<Span>,
+}
+
+impl Line {
+    #[inline]
+    pub fn blank() -> Self {
+        Self {
+            indent: String::new(),
+            statement: None,
+            trailing: String::new(),
+            comment: None,
+            newline: "\n".to_owned(),
+            span: None,
+        }
+    }
+
+    #[inline]
+    pub fn with_entry(entry: Entry) -> Self {
+        Self { statement: Some(Statement::Entry(entry)), ..Self::blank() }
+    }
+
+    #[inline]
+    pub fn with_comment(comment: Comment) -> Self {
+        Self { comment: Some(comment), ..Self::blank() }
+    }
+
+    #[inline]
+    pub fn indent(&self) -> &str {
+        &self.indent
+    }
+
+    #[inline]
+    pub fn statement(&self) -> Option<&Statement> {
+        self.statement.as_ref()
+    }
+
+    #[inline]
+    pub fn entry(&self) -> Option<&Entry> {
+        match &self.statement {
+            Some(Statement::Entry(entry)) => Some(entry),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn entry_mut(&mut self) -> Option<&mut Entry> {
+        match &mut self.statement {
+            Some(Statement::Entry(entry)) => Some(entry),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn include(&self) -> Option<&Include> {
+        match &self.statement {
+            Some(Statement::Include(include)) => Some(include),
+            _ => None,
+        }
+    }
+
+    /// Whitespace between the statement and the comment or end of line.
+    #[inline]
+    pub fn trailing(&self) -> &str {
+        &self.trailing
+    }
+
+    #[inline]
+    pub fn comment(&self) -> Option<&Comment> {
+        self.comment.as_ref()
+    }
+
+    #[inline]
+    pub fn set_comment(&mut self, comment: Option<Comment>) {
+        if comment.is_some() && self.statement.is_some() && self.trailing.is_empty() {
+            self.trailing.push(' ');
+        }
+        self.comment = comment;
+    }
+
+    #[inline]
+    pub fn newline(&self) -> &str {
+        &self.newline
+    }
+
+    /// Neither a statement nor a comment.
+    #[inline]
+    pub fn is_blank(&self) -> bool {
+        self.statement.is_none() && self.comment.is_none()
+    }
+
+    /// `None` if the line was created by an edit.
+    #[inline]
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    fn write(&self, out: &mut String) {
+        out.push_str(&self.indent);
+        if let Some(statement) = &self.statement {
+            statement.write(out);
+        }
+        out.push_str(&self.trailing);
+        if let Some(comment) = &self.comment {
+            out.push_str(&comment.text);
+        }
+        out.push_str(&self.newline);
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Document {
+    lines: Vec<Line>,
+}
+
+impl Document {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn parse(src: &str) -> Result<Self> {
+        let mut scanner = Scanner::new(src);
+        let mut lines = Vec::new();
+
+        while scanner.index < src.len() {
+            lines.push(scanner.scan_line()?);
+        }
+
+        Ok(Self { lines })
+    }
+
+    /// Decode and parse everything `reader` yields.
+    pub fn read(reader: &mut dyn BufRead, encoding: Encoding) -> Result<Self> {
+        let mut src = String::new();
+        encoding.read_to_string(reader, &mut src)?;
+        Self::parse(&src)
+    }
+
+    #[inline]
+    pub fn lines(&self) -> &[Line] {
+        &self.lines
+    }
+
+    #[inline]
+    pub fn lines_mut(&mut self) -> &mut Vec<Line> {
+        &mut self.lines
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &Entry> {
+        self.lines.iter().filter_map(Line::entry)
+    }
+
+    /// The last entry defining `key`.
+    pub fn get(&self, key: &str) -> Option<&Entry> {
+        self.lines.iter().rev().filter_map(Line::entry).find(|entry| entry.key == key)
+    }
+
+    /// The last entry defining `key`.
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Entry> {
+        self.lines.iter_mut().rev().filter_map(Line::entry_mut).find(|entry| entry.key == key)
+    }
+
+    /// Set the value of the last entry defining `key`, or append a new entry
+    /// at the end of the document.
+    pub fn set(&mut self, key: &str, value: &str) {
+        if let Some(entry) = self.get_mut(key) {
+            entry.set_value(value);
+        } else {
+            self.push(Line::with_entry(Entry::new(key, value)));
+        }
+    }
+
+    /// Remove all lines defining `key`, including their trailing comments.
+    /// Returns `true` if any line was removed.
+    pub fn remove(&mut self, key: &str) -> bool {
+        let len = self.lines.len();
+        self.lines.retain(|line| line.entry().is_none_or(|entry| entry.key != key));
+        self.lines.len() != len
+    }
+
+    /// Append a line, terminating the previous last line if it has no
+    /// newline.
+    pub fn push(&mut self, line: Line) {
+        if let Some(last) = self.lines.last_mut() {
+            if last.newline.is_empty() {
+                last.newline.push('\n');
+            }
+        }
+        self.lines.push(line);
+    }
+
+    pub fn write(&self, mut writer: impl std::io::Write) -> std::io::Result<()> {
+        writer.write_all(self.to_string().as_bytes())
+    }
+}
+
+impl std::fmt::Display for Document {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut out = String::new();
+        for line in &self.lines {
+            out.clear();
+            line.write(&mut out);
+            f.write_str(&out)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for Document {
+    type Err = Error;
+
+    #[inline]
+    fn from_str(src: &str) -> Result<Self> {
+        Self::parse(src)
+    }
+}
+
+#[inline]
+fn is_word(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || ch == '_'
+}
+
+struct Scanner<'a> {
+    src: &'a str,
+    index: usize,
+}
+
+impl<'a> Scanner<'a> {
+    #[inline]
+    fn new(src: &'a str) -> Self {
+        Self { src, index: 0 }
+    }
+
+    #[inline]
+    fn peek(&self) -> Option<char> {
+        self.src[self.index..].chars().next()
+    }
+
+    #[inline]
+    fn at_newline(&self) -> bool {
+        let tail = &self.src[self.index..];
+        tail.starts_with('\n') || tail.starts_with("\r\n")
+    }
+
+    #[inline]
+    fn at_line_end(&self) -> bool {
+        self.index >= self.src.len() || self.at_newline()
+    }
+
+    fn location(&self, index: usize) -> SourceLocation {
+        let before = &self.src[..index];
+        let lineno = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map(|pos| pos + 1).unwrap_or(0);
+        SourceLocation::new(lineno, index - line_start + 1)
+    }
+
+    #[inline]
+    fn syntax_error(&self, index: usize) -> Error {
+        let location = self.location(index);
+        Error::syntax_error(location.lineno(), location.column())
+    }
+
+    /// Whitespace up to but not including the end of the line.
+    fn skip_ws(&mut self) -> &'a str {
+        let start = self.index;
+        while let Some(ch) = self.peek() {
+            if !ch.is_ascii_whitespace() || self.at_newline() {
+                break;
+            }
+            self.index += 1;
+        }
+        &self.src[start..self.index]
+    }
+
+    fn skip_word(&mut self) -> &'a str {
+        let start = self.index;
+        while let Some(ch) = self.peek() {
+            if !is_word(ch) {
+                break;
+            }
+            self.index += 1;
+        }
+        &self.src[start..self.index]
+    }
+
+    fn scan_newline(&mut self) -> &'a str {
+        let start = self.index;
+        if self.src[self.index..].starts_with("\r\n") {
+            self.index += 2;
+        } else if self.src[self.index..].starts_with('\n') {
+            self.index += 1;
+        }
+        &self.src[start..self.index]
+    }
+
+    fn scan_comment(&mut self) -> Comment {
+        let start = self.index;
+        while !self.at_line_end() {
+            self.index += self.peek().map(char::len_utf8).unwrap_or(1);
+        }
+        Comment {
+            text: self.src[start..self.index].to_owned(),
+            span: Some(Span::new(start, self.index)),
+        }
+    }
+
+    fn scan_line(&mut self) -> Result<Line> {
+        let start = self.index;
+        let indent = self.skip_ws().to_owned();
+        let mut statement = None;
+        let mut trailing = String::new();
+
+        if let Some(ch) = self.peek() {
+            if is_word(ch) {
+                statement = Some(match self.scan_include()? {
+                    Some(include) => Statement::Include(include),
+                    None => Statement::Entry(self.scan_entry()?),
+                });
+                trailing.push_str(self.skip_ws());
+            } else if ch != '#' && !self.at_newline() {
+                return Err(self.syntax_error(self.index));
+            }
+        }
+
+        let comment = if self.peek() == Some('#') {
+            Some(self.scan_comment())
+        } else {
+            None
+        };
+
+        if !self.at_line_end() {
+            return Err(self.syntax_error(self.index));
+        }
+
+        let newline = self.scan_newline().to_owned();
+
+        Ok(Line {
+            indent,
+            statement,
+            trailing,
+            comment,
+            newline,
+            span: Some(Span::new(start, self.index)),
+        })
+    }
+
+    /// `include PATH` or `include? PATH`. `None` if the line is an entry,
+    /// e.g. `include=PATH` or an import of `include`.
+    fn scan_include(&mut self) -> Result<Option<Include>> {
+        let start = self.index;
+        if self.skip_word() != "include" {
+            self.index = start;
+            return Ok(None);
+        }
+
+        let ws_start = self.index;
+        self.skip_ws();
+
+        if self.peek() == Some('?') {
+            self.index += 1;
+            self.skip_ws();
+        } else if self.index == ws_start || self.at_line_end() || matches!(self.peek(), Some('=' | '#')) {
+            self.index = start;
+            return Ok(None);
+        }
+
+        let keyword = self.src[start..self.index].to_owned();
+        let path = self.scan_value()?;
+
+        Ok(Some(Include { keyword, path }))
+    }
+
+    fn scan_entry(&mut self) -> Result<Entry> {
+        let mut key_start = self.index;
+        let mut key = self.skip_word();
+        let mut export = None;
+
+        let ws_start = self.index;
+        self.skip_ws();
+
+        if key == "export" && self.index > ws_start && self.peek().is_some_and(is_word) {
+            export = Some(self.src[key_start..self.index].to_owned());
+            key_start = self.index;
+            key = self.skip_word();
+        } else {
+            self.index = ws_start;
+        }
+
+        let key_span = Some(Span::new(key_start, self.index));
+        let assign_start = self.index;
+        self.skip_ws();
+
+        if self.peek() != Some('=') {
+            if self.at_line_end() || self.peek() == Some('#') {
+                // import, the whitespace belongs to the line
+                self.index = assign_start;
+                return Ok(Entry {
+                    export,
+                    key: key.to_owned(),
+                    key_span,
+                    assign: None,
+                    value: Vec::new(),
+                });
+            }
+            return Err(self.syntax_error(self.index));
+        }
+
+        self.index += 1;
+        self.skip_ws();
+        let assign = Some(self.src[assign_start..self.index].to_owned());
+        let value = self.scan_value()?;
+
+        Ok(Entry {
+            export,
+            key: key.to_owned(),
+            key_span,
+            assign,
+            value,
+        })
+    }
+
+    fn scan_value(&mut self) -> Result<Vec<ValuePart>> {
+        let mut parts = Vec::new();
+        let mut unquoted_start = self.index;
+
+        while let Some(ch) = self.peek() {
+            if self.at_newline() || ch == '#' {
+                break;
+            }
+
+            if ch == '"' || ch == '\'' {
+                if self.index > unquoted_start {
+                    parts.push(ValuePart {
+                        quote: QuoteKind::Unquoted,
+                        raw: self.src[unquoted_start..self.index].to_owned(),
+                        span: Some(Span::new(unquoted_start, self.index)),
+                    });
+                }
+                let start = self.index;
+                self.scan_quoted(ch)?;
+                parts.push(ValuePart {
+                    quote: if ch == '"' { QuoteKind::Double } else { QuoteKind::Single },
+                    raw: self.src[start..self.index].to_owned(),
+                    span: Some(Span::new(start, self.index)),
+                });
+                unquoted_start = self.index;
+            } else if ch.is_ascii_whitespace() {
+                let ws_start = self.index;
+                self.skip_ws();
+                if self.at_line_end() || self.peek() == Some('#') {
+                    // trailing whitespace is not part of the value
+                    self.index = ws_start;
+                    break;
+                }
+            } else if ch == '$' {
+                self.scan_var()?;
+            } else {
+                self.index += ch.len_utf8();
+            }
+        }
+
+        if self.index > unquoted_start {
+            parts.push(ValuePart {
+                quote: QuoteKind::Unquoted,
+                raw: self.src[unquoted_start..self.index].to_owned(),
+                span: Some(Span::new(unquoted_start, self.index)),
+            });
+        }
+
+        Ok(parts)
+    }
+
+    fn scan_quoted(&mut self, quote: char) -> Result<()> {
+        let start = self.index;
+        self.index += 1;
+
+        loop {
+            let Some(ch) = self.peek() else {
+                return Err(self.syntax_error(start));
+            };
+
+            if ch == quote {
+                self.index += 1;
+                return Ok(());
+            }
+
+            if quote == '"' && ch == '\\' {
+                self.index += 1;
+                let Some(ch) = self.peek() else {
+                    return Err(self.syntax_error(self.index));
+                };
+                self.index += ch.len_utf8();
+            } else if quote == '"' && ch == '$' {
+                self.scan_var()?;
+            } else {
+                self.index += ch.len_utf8();
+            }
+        }
+    }
+
+    fn scan_var(&mut self) -> Result<()> {
+        let start = self.index;
+        self.index += 1;
+
+        let brace = self.peek() == Some('{');
+        if brace {
+            self.index += 1;
+        }
+
+        if self.skip_word().is_empty() {
+            return Err(self.syntax_error(start));
+        }
+
+        if !brace {
+            return Ok(());
+        }
+
+        let tail = &self.src[self.index..];
+        let op_len = if tail.starts_with(":?") || tail.starts_with(":-") || tail.starts_with(":+") {
+            2
+        } else if tail.starts_with('?') || tail.starts_with('-') || tail.starts_with('+') {
+            1
+        } else {
+            0
+        };
+
+        if op_len > 0 {
+            self.index += op_len;
+            self.scan_nested()?;
+        }
+
+        if self.peek() != Some('}') {
+            return Err(self.syntax_error(self.index));
+        }
+        self.index += 1;
+
+        Ok(())
+    }
+
+    fn scan_nested(&mut self) -> Result<()> {
+        loop {
+            let Some(ch) = self.peek() else {
+                return Ok(());
+            };
+
+            match ch {
+                '}' => return Ok(()),
+                '"' | '\'' => self.scan_quoted(ch)?,
+                '$' => self.scan_var()?,
+                _ => self.index += ch.len_utf8(),
+            }
+        }
+    }
+}
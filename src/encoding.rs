@@ -1,58 +1,200 @@
-use std::{ffi::OsStr, io::{BufRead, ErrorKind}};
+use std::{ffi::OsStr, io::{BufRead, Read}};
+
+use crate::codec::DecodingReader;
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum Encoding {
     ASCII,
     /// aka ISO-8859-1
     Latin1,
+    /// aka ISO-8859-2
+    Latin2,
+    /// aka ISO-8859-15, Latin-1 with the euro sign
+    Latin9,
+    /// aka CP1252, what Windows calls ANSI in western locales
+    Windows1252,
+    KOI8R,
     UTF8,
     UTF16BE,
     UTF16LE,
     UTF32BE,
     UTF32LE,
+    /// Detect UTF-8, UTF-16 and UTF-32 by their BOM, which is then skipped,
+    /// or UTF-16 and UTF-32 without a BOM by the null bytes of ASCII
+    /// characters. Anything else is UTF-8, or [`crate::Options::fallback_encoding`]
+    /// if it isn't valid UTF-8.
+    Auto,
 }
 
 impl Encoding {
-    pub(crate) fn read_line(&self, reader: &mut dyn BufRead, line: &mut String) -> std::io::Result<usize> {
+    pub(crate) fn read_to_string(&self, reader: &mut dyn BufRead, buf: &mut String) -> std::io::Result<usize> {
         match self {
-            Encoding::UTF8    => reader.read_line(line),
-            Encoding::ASCII   => read_line_ascii(reader, line),
-            Encoding::Latin1  => read_line_latin1(reader, line),
-            Encoding::UTF16LE => read_line_utf16le(reader, line),
-            Encoding::UTF16BE => read_line_utf16be(reader, line),
-            Encoding::UTF32LE => read_line_utf32le(reader, line),
-            Encoding::UTF32BE => read_line_utf32be(reader, line),
+            Encoding::UTF8 => reader.read_to_string(buf),
+            _ => DecodingReader::new(reader, *self).read_to_string(buf),
         }
     }
 
-    pub(crate) fn read_to_string(&self, reader: &mut dyn BufRead, buf: &mut String) -> std::io::Result<usize> {
+    /// Reads one character from `reader`, consuming only its bytes. Returns
+    /// `None` at the end of the input. [`Encoding::Auto`] is read as UTF-8.
+    pub(crate) fn read_char(&self, reader: &mut dyn BufRead) -> std::io::Result<Option<Decoded>> {
         match self {
-            Encoding::UTF8 => reader.read_to_string(buf),
-            Encoding::ASCII => {
-                let mut bytes = Vec::new();
-                let byte_count = reader.read_to_end(&mut bytes)?;
-                if bytes.iter().cloned().any(|byte| byte > 127) {
-                    return Err(std::io::Error::from(std::io::ErrorKind::InvalidData));
+            Encoding::UTF8 | Encoding::Auto => read_char_utf8(reader),
+            Encoding::ASCII       => read_char_single_byte(reader, |byte| byte < 0x80, |byte| byte as char),
+            Encoding::Latin1      => read_char_single_byte(reader, |_| true, |byte| byte as char),
+            Encoding::Latin2      => read_char_table(reader, &LATIN2),
+            Encoding::Latin9      => read_char_table(reader, &LATIN9),
+            Encoding::Windows1252 => read_char_table(reader, &WINDOWS_1252),
+            Encoding::KOI8R       => read_char_table(reader, &KOI8_R),
+            Encoding::UTF16LE     => read_char_utf16(reader, u16::from_le_bytes),
+            Encoding::UTF16BE     => read_char_utf16(reader, u16::from_be_bytes),
+            Encoding::UTF32LE     => read_char_utf32(reader, u32::from_le_bytes),
+            Encoding::UTF32BE     => read_char_utf32(reader, u32::from_be_bytes),
+        }
+    }
+
+    /// Appends `ch` in this encoding to `out`. Returns `false` if `ch` can't be
+    /// represented in it. [`Encoding::Auto`] is written as UTF-8.
+    pub(crate) fn encode_char(&self, ch: char, out: &mut Vec<u8>) -> bool {
+        let high_half = match self {
+            Encoding::UTF8 | Encoding::Auto => {
+                out.extend_from_slice(ch.encode_utf8(&mut [0u8; 4]).as_bytes());
+                return true;
+            }
+            Encoding::ASCII | Encoding::Latin1 => {
+                let max = if *self == Encoding::ASCII { 0x7F } else { 0xFF };
+                if ch as u32 > max {
+                    return false;
+                }
+                out.push(ch as u8);
+                return true;
+            }
+            Encoding::UTF16LE | Encoding::UTF16BE => {
+                for unit in ch.encode_utf16(&mut [0u16; 2]) {
+                    if *self == Encoding::UTF16LE {
+                        out.extend_from_slice(&unit.to_le_bytes());
+                    } else {
+                        out.extend_from_slice(&unit.to_be_bytes());
+                    }
                 }
+                return true;
+            }
+            Encoding::UTF32LE => {
+                out.extend_from_slice(&(ch as u32).to_le_bytes());
+                return true;
+            }
+            Encoding::UTF32BE => {
+                out.extend_from_slice(&(ch as u32).to_be_bytes());
+                return true;
+            }
+            Encoding::Latin2      => &LATIN2,
+            Encoding::Latin9      => &LATIN9,
+            Encoding::Windows1252 => &WINDOWS_1252,
+            Encoding::KOI8R       => &KOI8_R,
+        };
+
+        if ch.is_ascii() {
+            out.push(ch as u8);
+            return true;
+        }
+
+        match high_half.iter().position(|other| *other == ch) {
+            Some(index) => {
+                out.push(0x80 + index as u8);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Detects the encoding by the BOM at the start of `bytes` and returns it
+    /// together with the length of the BOM. Without a BOM UTF-16 and UTF-32
+    /// are detected by where the null bytes are, since the keys are ASCII.
+    pub fn detect(bytes: &[u8]) -> Option<(Encoding, usize)> {
+        const BOMS: &[(&[u8], Encoding)] = &[
+            (b"\xEF\xBB\xBF",     Encoding::UTF8),
+            (b"\xFF\xFE\0\0",     Encoding::UTF32LE),
+            (b"\0\0\xFE\xFF",     Encoding::UTF32BE),
+            (b"\xFF\xFE",         Encoding::UTF16LE),
+            (b"\xFE\xFF",         Encoding::UTF16BE),
+        ];
+
+        for (bom, encoding) in BOMS {
+            if bytes.starts_with(bom) {
+                return Some((*encoding, bom.len()));
+            }
+        }
+
+        let sample = &bytes[..bytes.len().min(512) & !3];
+        let mut zeros = [0usize; 4];
+        for (index, byte) in sample.iter().enumerate() {
+            if *byte == 0 {
+                zeros[index & 3] += 1;
+            }
+        }
+
+        let quads = sample.len() / 4;
+        if quads == 0 || zeros == [0; 4] {
+            return None;
+        }
 
-                buf.push_str(&unsafe { String::from_utf8_unchecked(bytes) });
+        // the highest byte of a code point is always null in UTF-32, and the
+        // second highest is for anything outside of the supplementary planes
+        if zeros[3] == quads && zeros[2] * 3 >= quads * 2 && zeros[0] < quads {
+            return Some((Encoding::UTF32LE, 0));
+        }
 
-                Ok(byte_count)
-            },
-            Encoding::Latin1 => {
-                let mut bytes = Vec::new();
-                let byte_count = reader.read_to_end(&mut bytes)?;
+        if zeros[0] == quads && zeros[1] * 3 >= quads * 2 && zeros[3] < quads {
+            return Some((Encoding::UTF32BE, 0));
+        }
 
-                buf.reserve(byte_count);
-                buf.extend(bytes.iter().cloned().map(|byte| byte as char));
+        // ASCII and Latin-1 have a null high byte in UTF-16
+        let pairs = quads * 2;
+        let even = zeros[0] + zeros[2];
+        let odd  = zeros[1] + zeros[3];
 
-                Ok(byte_count)
-            },
-            Encoding::UTF16BE => read_utf16(reader, buf, u16::from_be_bytes),
-            Encoding::UTF16LE => read_utf16(reader, buf, u16::from_le_bytes),
-            Encoding::UTF32BE => read_utf32(reader, buf, u32::from_be_bytes),
-            Encoding::UTF32LE => read_utf32(reader, buf, u32::from_le_bytes),
+        if odd * 3 >= pairs * 2 && even * 4 <= odd {
+            return Some((Encoding::UTF16LE, 0));
         }
+
+        if even * 3 >= pairs * 2 && odd * 4 <= even {
+            return Some((Encoding::UTF16BE, 0));
+        }
+
+        None
+    }
+}
+
+/// Resolves [`Encoding::Auto`] for `reader`, of which `head` was already
+/// read. Returns the encoding and the bytes to decode before the rest of
+/// `reader`, with the BOM removed.
+///
+/// To decide between UTF-8 and `fallback` the whole input is read into
+/// memory, otherwise only what is buffered anyway.
+pub(crate) fn resolve_auto(mut head: Vec<u8>, reader: &mut dyn BufRead, fallback: Option<Encoding>) -> std::io::Result<(Encoding, Vec<u8>)> {
+    if head.len() < 4 {
+        let buf = reader.fill_buf()?;
+        let len = buf.len().min(4 - head.len());
+        head.extend_from_slice(&buf[..len]);
+        reader.consume(len);
+    }
+
+    let mut sample = head.clone();
+    sample.extend_from_slice(reader.fill_buf()?);
+
+    if let Some((encoding, bom_len)) = Encoding::detect(&sample) {
+        head.drain(..bom_len);
+        return Ok((encoding, head));
+    }
+
+    let Some(fallback) = fallback.filter(|fallback| *fallback != Encoding::Auto) else {
+        return Ok((Encoding::UTF8, head));
+    };
+
+    reader.read_to_end(&mut head)?;
+    if std::str::from_utf8(&head).is_ok() {
+        Ok((Encoding::UTF8, head))
+    } else {
+        Ok((fallback, head))
     }
 }
 
@@ -88,6 +230,27 @@ impl TryFrom<&OsStr> for Encoding {
                   value.eq_ignore_ascii_case("windows-28591") ||
                   value.eq_ignore_ascii_case("cp819") {
             Ok(Encoding::Latin1)
+        } else if value.eq_ignore_ascii_case("latin2") ||
+                  value.eq_ignore_ascii_case("iso-8859-2") ||
+                  value.eq_ignore_ascii_case("iso8859-2") ||
+                  value.eq_ignore_ascii_case("iso8859_2") ||
+                  value.eq_ignore_ascii_case("windows-28592") {
+            Ok(Encoding::Latin2)
+        } else if value.eq_ignore_ascii_case("latin9") ||
+                  value.eq_ignore_ascii_case("iso-8859-15") ||
+                  value.eq_ignore_ascii_case("iso8859-15") ||
+                  value.eq_ignore_ascii_case("iso8859_15") ||
+                  value.eq_ignore_ascii_case("windows-28605") {
+            Ok(Encoding::Latin9)
+        } else if value.eq_ignore_ascii_case("windows-1252") ||
+                  value.eq_ignore_ascii_case("windows1252") ||
+                  value.eq_ignore_ascii_case("cp1252") {
+            Ok(Encoding::Windows1252)
+        } else if value.eq_ignore_ascii_case("koi8-r") ||
+                  value.eq_ignore_ascii_case("koi8r") ||
+                  value.eq_ignore_ascii_case("koi8_r") ||
+                  value.eq_ignore_ascii_case("windows-20866") {
+            Ok(Encoding::KOI8R)
         } else if value.eq_ignore_ascii_case("utf-16le") ||
                   value.eq_ignore_ascii_case("utf16le") ||
                   value.eq_ignore_ascii_case("windows-1200") {
@@ -104,6 +267,8 @@ impl TryFrom<&OsStr> for Encoding {
                   value.eq_ignore_ascii_case("utf32be") ||
                   value.eq_ignore_ascii_case("windows-12001") {
             Ok(Encoding::UTF32BE)
+        } else if value.eq_ignore_ascii_case("auto") {
+            Ok(Encoding::Auto)
         } else {
             Err(IllegalEncoding())
         }
@@ -126,218 +291,216 @@ impl Default for Encoding {
     }
 }
 
-fn read_line_ascii(reader: &mut dyn BufRead, line: &mut String) -> std::io::Result<usize> {
-    let mut byte_count = 0;
-
-    loop {
-        let mut buf = [0u8; 1];
-        if let Err(error) = reader.read_exact(&mut buf) {
-            if error.kind() == ErrorKind::UnexpectedEof {
-                break;
-            }
-            return Err(error);
-        }
-        byte_count += 1;
-
-        let ch = buf[0];
-        if ch > 127 {
-            return Err(std::io::Error::from(std::io::ErrorKind::InvalidData));
-        }
+/// A character read by [`Encoding::read_char()`].
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(crate) enum Decoded {
+    /// The character and the number of bytes it was encoded with.
+    Char(char, usize),
+    /// This many bytes aren't valid in the encoding and were skipped.
+    Invalid(usize),
+}
 
-        let ch = ch as char;
+/// Reads `N` bytes. `Ok(Err(len))` if the input ends after `len` bytes.
+fn read_unit<const N: usize>(reader: &mut dyn BufRead) -> std::io::Result<std::result::Result<[u8; N], usize>> {
+    let mut unit = [0u8; N];
 
-        line.push(ch);
-        if ch == '\n' {
-            break;
-        }
+    for (len, byte) in unit.iter_mut().enumerate() {
+        let Some(&next) = reader.fill_buf()?.first() else {
+            return Ok(Err(len));
+        };
+        *byte = next;
+        reader.consume(1);
     }
 
-    Ok(byte_count)
+    Ok(Ok(unit))
 }
 
-fn read_line_latin1(reader: &mut dyn BufRead, line: &mut String) -> std::io::Result<usize> {
-    let mut byte_count = 0;
-
-    loop {
-        let mut buf = [0u8; 1];
-        if let Err(error) = reader.read_exact(&mut buf) {
-            if error.kind() == ErrorKind::UnexpectedEof {
-                break;
+fn read_char_utf8(reader: &mut dyn BufRead) -> std::io::Result<Option<Decoded>> {
+    let lead = match read_unit::<1>(reader)? {
+        Ok([lead]) => lead,
+        Err(_) => return Ok(None),
+    };
+
+    let width = match lead {
+        0x00..=0x7F => return Ok(Some(Decoded::Char(lead as char, 1))),
+        0xC2..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF4 => 4,
+        _ => return Ok(Some(Decoded::Invalid(1))),
+    };
+
+    let mut bytes = [lead, 0, 0, 0];
+    let mut len = 1;
+    while len < width {
+        // only continuation bytes are consumed, anything else starts the next character
+        match reader.fill_buf()?.first() {
+            Some(&byte) if byte & 0xC0 == 0x80 => {
+                bytes[len] = byte;
+                len += 1;
+                reader.consume(1);
             }
-            return Err(error);
-        }
-        byte_count += 1;
-
-        let ch = buf[0] as char;
-
-        line.push(ch);
-        if ch == '\n' {
-            break;
+            _ => return Ok(Some(Decoded::Invalid(len))),
         }
     }
 
-    Ok(byte_count)
+    Ok(Some(match std::str::from_utf8(&bytes[..len]) {
+        Ok(src) => Decoded::Char(src.chars().next().unwrap_or('\u{FFFD}'), len),
+        Err(_) => Decoded::Invalid(len),
+    }))
 }
 
-#[inline]
-fn read_line_utf16be(reader: &mut dyn BufRead, line: &mut String) -> std::io::Result<usize> {
-    read_line_utf16(reader, line, u16::from_be_bytes)
-}
+fn read_char_utf16(reader: &mut dyn BufRead, decode: fn([u8; 2]) -> u16) -> std::io::Result<Option<Decoded>> {
+    let hi = match read_unit::<2>(reader)? {
+        Err(0) => return Ok(None),
+        Err(len) => return Ok(Some(Decoded::Invalid(len))),
+        Ok(unit) => decode(unit),
+    };
 
-#[inline]
-fn read_line_utf16le(reader: &mut dyn BufRead, line: &mut String) -> std::io::Result<usize> {
-    read_line_utf16(reader, line, u16::from_le_bytes)
-}
-
-fn read_line_utf16(reader: &mut dyn BufRead, line: &mut String, decode: fn([u8; 2]) -> u16) -> std::io::Result<usize> {
-    let mut byte_count = 0;
-
-    loop {
-        let mut buf = [0u8; 2];
+    if hi >= 0xDC00 && hi <= 0xDFFF {
+        return Ok(Some(Decoded::Invalid(2)));
+    }
 
-        if let Err(error) = reader.read_exact(&mut buf) {
-            if error.kind() == ErrorKind::UnexpectedEof {
-                break;
-            }
-            return Err(error);
-        }
-        byte_count += buf.len();
-        let hi = decode(buf);
+    if hi < 0xD800 || hi > 0xDBFF {
+        // not a surrogate, so always a valid code point
+        return Ok(Some(Decoded::Char(char::from_u32(hi as u32).unwrap_or('\u{FFFD}'), 2)));
+    }
 
-        if hi == ('\n' as u16) {
-            line.push('\n');
-            break;
+    let buf = reader.fill_buf()?;
+    let lo = if buf.len() >= 2 {
+        let lo = decode([buf[0], buf[1]]);
+        if lo < 0xDC00 || lo > 0xDFFF {
+            // leave it to be read as a character of its own
+            return Ok(Some(Decoded::Invalid(2)));
         }
-
-        if hi >= 0xDC00 /* && hi <= 0xDFFF */ {
-            return Err(std::io::Error::from(std::io::ErrorKind::InvalidData));
+        reader.consume(2);
+        lo
+    } else {
+        // The unit spans the end of the buffer, so it can't be peeked at
+        // without losing the alignment.
+        match read_unit::<2>(reader)? {
+            Err(len) => return Ok(Some(Decoded::Invalid(2 + len))),
+            Ok(unit) => decode(unit),
         }
+    };
 
-        if hi >= 0xD800 && hi <= 0xDBFF {
-            if let Err(error) = reader.read_exact(&mut buf) {
-                if error.kind() == ErrorKind::UnexpectedEof {
-                    return Err(std::io::Error::from(std::io::ErrorKind::InvalidData));
-                }
-                return Err(error);
-            }
-            byte_count += buf.len();
-            let lo = decode(buf);
-
-            if lo < 0xDC00 || lo > 0xDFFF {
-                return Err(std::io::Error::from(std::io::ErrorKind::InvalidData));
-            }
-
-            let ch = (((hi & 0x3ff) as u32) << 10 | (lo & 0x3ff) as u32) + 0x1_0000;
-            line.push(unsafe { char::from_u32_unchecked(ch) });
-        } else {
-            line.push(unsafe { char::from_u32_unchecked(hi as u32) });
-        }
+    if lo < 0xDC00 || lo > 0xDFFF {
+        return Ok(Some(Decoded::Invalid(4)));
     }
 
-    Ok(byte_count)
+    let code_point = (((hi & 0x3ff) as u32) << 10 | (lo & 0x3ff) as u32) + 0x1_0000;
+    Ok(Some(match char::from_u32(code_point) {
+        Some(ch) => Decoded::Char(ch, 4),
+        None => Decoded::Invalid(4),
+    }))
 }
 
-#[inline]
-fn read_line_utf32be(reader: &mut dyn BufRead, line: &mut String) -> std::io::Result<usize> {
-    read_line_utf32(reader, line, u32::from_be_bytes)
+fn read_char_utf32(reader: &mut dyn BufRead, decode: fn([u8; 4]) -> u32) -> std::io::Result<Option<Decoded>> {
+    Ok(match read_unit::<4>(reader)? {
+        Err(0) => None,
+        Err(len) => Some(Decoded::Invalid(len)),
+        Ok(unit) => Some(match char::from_u32(decode(unit)) {
+            Some(ch) => Decoded::Char(ch, 4),
+            None => Decoded::Invalid(4),
+        }),
+    })
 }
 
 #[inline]
-fn read_line_utf32le(reader: &mut dyn BufRead, line: &mut String) -> std::io::Result<usize> {
-    read_line_utf32(reader, line, u32::from_le_bytes)
-}
-
-fn read_line_utf32(reader: &mut dyn BufRead, line: &mut String, decode: fn([u8; 4]) -> u32) -> std::io::Result<usize> {
-    let mut byte_count = 0;
-
-    loop {
-        let mut buf = [0u8; 4];
-
-        if let Err(error) = reader.read_exact(&mut buf) {
-            if error.kind() == ErrorKind::UnexpectedEof {
-                break;
-            }
-            return Err(error);
-        }
-        byte_count += buf.len();
-        let ch = decode(buf);
-
-        let Some(ch) = char::from_u32(ch) else {
-            return Err(std::io::Error::from(std::io::ErrorKind::InvalidData));
-        };
-        line.push(ch);
-
-        if ch == '\n' {
-            break;
-        }
-    }
-
-    Ok(byte_count)
+fn read_char_single_byte(reader: &mut dyn BufRead, valid: fn(u8) -> bool, decode: fn(u8) -> char) -> std::io::Result<Option<Decoded>> {
+    Ok(match read_unit::<1>(reader)? {
+        Err(_) => None,
+        Ok([byte]) if valid(byte) => Some(Decoded::Char(decode(byte), 1)),
+        Ok(_) => Some(Decoded::Invalid(1)),
+    })
 }
 
-fn read_utf16(reader: &mut dyn BufRead, out: &mut String, decode: fn([u8; 2]) -> u16) -> std::io::Result<usize> {
-    let mut byte_count = 0;
-
-    loop {
-        let mut buf = [0u8; 2];
-
-        if let Err(error) = reader.read_exact(&mut buf) {
-            if error.kind() == ErrorKind::UnexpectedEof {
-                break;
-            }
-            return Err(error);
-        }
-        byte_count += buf.len();
-        let hi = decode(buf);
-
-        if hi >= 0xDC00 /* && hi <= 0xDFFF */ {
-            return Err(std::io::Error::from(std::io::ErrorKind::InvalidData));
-        }
-
-        if hi >= 0xD800 && hi <= 0xDBFF {
-            if let Err(error) = reader.read_exact(&mut buf) {
-                if error.kind() == ErrorKind::UnexpectedEof {
-                    return Err(std::io::Error::from(std::io::ErrorKind::InvalidData));
-                }
-                return Err(error);
-            }
-            byte_count += buf.len();
-            let lo = decode(buf);
-
-            if lo < 0xDC00 || lo > 0xDFFF {
-                return Err(std::io::Error::from(std::io::ErrorKind::InvalidData));
-            }
-
-            let ch = (((hi & 0x3ff) as u32) << 10 | (lo & 0x3ff) as u32) + 0x1_0000;
-            out.push(unsafe { char::from_u32_unchecked(ch) });
-        } else {
-            out.push(unsafe { char::from_u32_unchecked(hi as u32) });
-        }
-    }
-
-    Ok(byte_count)
+fn read_char_table(reader: &mut dyn BufRead, high_half: &HighHalf) -> std::io::Result<Option<Decoded>> {
+    Ok(match read_unit::<1>(reader)? {
+        Err(_) => None,
+        Ok([byte]) if byte < 0x80 => Some(Decoded::Char(byte as char, 1)),
+        Ok([byte]) => match high_half[(byte - 0x80) as usize] {
+            '\0' => Some(Decoded::Invalid(1)),
+            ch => Some(Decoded::Char(ch, 1)),
+        },
+    })
 }
 
-fn read_utf32(reader: &mut dyn BufRead, line: &mut String, decode: fn([u8; 4]) -> u32) -> std::io::Result<usize> {
-    let mut byte_count = 0;
-
-    loop {
-        let mut buf = [0u8; 4];
-
-        if let Err(error) = reader.read_exact(&mut buf) {
-            if error.kind() == ErrorKind::UnexpectedEof {
-                break;
-            }
-            return Err(error);
-        }
-        byte_count += buf.len();
-        let ch = decode(buf);
-
-        let Some(ch) = char::from_u32(ch) else {
-            return Err(std::io::Error::from(std::io::ErrorKind::InvalidData));
-        };
-        line.push(ch);
-    }
-
-    Ok(byte_count)
-}
+/// The characters of the bytes 0x80 to 0xFF of a single-byte encoding that is
+/// ASCII compatible. Bytes that aren't defined by the encoding are `'\0'`.
+type HighHalf = [char; 128];
+
+static WINDOWS_1252: HighHalf = [
+    '\u{20AC}', '\0', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\0', '\u{017D}', '\0',
+    '\0', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\0', '\u{017E}', '\u{0178}',
+    '\u{00A0}', '\u{00A1}', '\u{00A2}', '\u{00A3}', '\u{00A4}', '\u{00A5}', '\u{00A6}', '\u{00A7}',
+    '\u{00A8}', '\u{00A9}', '\u{00AA}', '\u{00AB}', '\u{00AC}', '\u{00AD}', '\u{00AE}', '\u{00AF}',
+    '\u{00B0}', '\u{00B1}', '\u{00B2}', '\u{00B3}', '\u{00B4}', '\u{00B5}', '\u{00B6}', '\u{00B7}',
+    '\u{00B8}', '\u{00B9}', '\u{00BA}', '\u{00BB}', '\u{00BC}', '\u{00BD}', '\u{00BE}', '\u{00BF}',
+    '\u{00C0}', '\u{00C1}', '\u{00C2}', '\u{00C3}', '\u{00C4}', '\u{00C5}', '\u{00C6}', '\u{00C7}',
+    '\u{00C8}', '\u{00C9}', '\u{00CA}', '\u{00CB}', '\u{00CC}', '\u{00CD}', '\u{00CE}', '\u{00CF}',
+    '\u{00D0}', '\u{00D1}', '\u{00D2}', '\u{00D3}', '\u{00D4}', '\u{00D5}', '\u{00D6}', '\u{00D7}',
+    '\u{00D8}', '\u{00D9}', '\u{00DA}', '\u{00DB}', '\u{00DC}', '\u{00DD}', '\u{00DE}', '\u{00DF}',
+    '\u{00E0}', '\u{00E1}', '\u{00E2}', '\u{00E3}', '\u{00E4}', '\u{00E5}', '\u{00E6}', '\u{00E7}',
+    '\u{00E8}', '\u{00E9}', '\u{00EA}', '\u{00EB}', '\u{00EC}', '\u{00ED}', '\u{00EE}', '\u{00EF}',
+    '\u{00F0}', '\u{00F1}', '\u{00F2}', '\u{00F3}', '\u{00F4}', '\u{00F5}', '\u{00F6}', '\u{00F7}',
+    '\u{00F8}', '\u{00F9}', '\u{00FA}', '\u{00FB}', '\u{00FC}', '\u{00FD}', '\u{00FE}', '\u{00FF}',
+];
+
+static LATIN2: HighHalf = [
+    '\u{0080}', '\u{0081}', '\u{0082}', '\u{0083}', '\u{0084}', '\u{0085}', '\u{0086}', '\u{0087}',
+    '\u{0088}', '\u{0089}', '\u{008A}', '\u{008B}', '\u{008C}', '\u{008D}', '\u{008E}', '\u{008F}',
+    '\u{0090}', '\u{0091}', '\u{0092}', '\u{0093}', '\u{0094}', '\u{0095}', '\u{0096}', '\u{0097}',
+    '\u{0098}', '\u{0099}', '\u{009A}', '\u{009B}', '\u{009C}', '\u{009D}', '\u{009E}', '\u{009F}',
+    '\u{00A0}', '\u{0104}', '\u{02D8}', '\u{0141}', '\u{00A4}', '\u{013D}', '\u{015A}', '\u{00A7}',
+    '\u{00A8}', '\u{0160}', '\u{015E}', '\u{0164}', '\u{0179}', '\u{00AD}', '\u{017D}', '\u{017B}',
+    '\u{00B0}', '\u{0105}', '\u{02DB}', '\u{0142}', '\u{00B4}', '\u{013E}', '\u{015B}', '\u{02C7}',
+    '\u{00B8}', '\u{0161}', '\u{015F}', '\u{0165}', '\u{017A}', '\u{02DD}', '\u{017E}', '\u{017C}',
+    '\u{0154}', '\u{00C1}', '\u{00C2}', '\u{0102}', '\u{00C4}', '\u{0139}', '\u{0106}', '\u{00C7}',
+    '\u{010C}', '\u{00C9}', '\u{0118}', '\u{00CB}', '\u{011A}', '\u{00CD}', '\u{00CE}', '\u{010E}',
+    '\u{0110}', '\u{0143}', '\u{0147}', '\u{00D3}', '\u{00D4}', '\u{0150}', '\u{00D6}', '\u{00D7}',
+    '\u{0158}', '\u{016E}', '\u{00DA}', '\u{0170}', '\u{00DC}', '\u{00DD}', '\u{0162}', '\u{00DF}',
+    '\u{0155}', '\u{00E1}', '\u{00E2}', '\u{0103}', '\u{00E4}', '\u{013A}', '\u{0107}', '\u{00E7}',
+    '\u{010D}', '\u{00E9}', '\u{0119}', '\u{00EB}', '\u{011B}', '\u{00ED}', '\u{00EE}', '\u{010F}',
+    '\u{0111}', '\u{0144}', '\u{0148}', '\u{00F3}', '\u{00F4}', '\u{0151}', '\u{00F6}', '\u{00F7}',
+    '\u{0159}', '\u{016F}', '\u{00FA}', '\u{0171}', '\u{00FC}', '\u{00FD}', '\u{0163}', '\u{02D9}',
+];
+
+static LATIN9: HighHalf = [
+    '\u{0080}', '\u{0081}', '\u{0082}', '\u{0083}', '\u{0084}', '\u{0085}', '\u{0086}', '\u{0087}',
+    '\u{0088}', '\u{0089}', '\u{008A}', '\u{008B}', '\u{008C}', '\u{008D}', '\u{008E}', '\u{008F}',
+    '\u{0090}', '\u{0091}', '\u{0092}', '\u{0093}', '\u{0094}', '\u{0095}', '\u{0096}', '\u{0097}',
+    '\u{0098}', '\u{0099}', '\u{009A}', '\u{009B}', '\u{009C}', '\u{009D}', '\u{009E}', '\u{009F}',
+    '\u{00A0}', '\u{00A1}', '\u{00A2}', '\u{00A3}', '\u{20AC}', '\u{00A5}', '\u{0160}', '\u{00A7}',
+    '\u{0161}', '\u{00A9}', '\u{00AA}', '\u{00AB}', '\u{00AC}', '\u{00AD}', '\u{00AE}', '\u{00AF}',
+    '\u{00B0}', '\u{00B1}', '\u{00B2}', '\u{00B3}', '\u{017D}', '\u{00B5}', '\u{00B6}', '\u{00B7}',
+    '\u{017E}', '\u{00B9}', '\u{00BA}', '\u{00BB}', '\u{0152}', '\u{0153}', '\u{0178}', '\u{00BF}',
+    '\u{00C0}', '\u{00C1}', '\u{00C2}', '\u{00C3}', '\u{00C4}', '\u{00C5}', '\u{00C6}', '\u{00C7}',
+    '\u{00C8}', '\u{00C9}', '\u{00CA}', '\u{00CB}', '\u{00CC}', '\u{00CD}', '\u{00CE}', '\u{00CF}',
+    '\u{00D0}', '\u{00D1}', '\u{00D2}', '\u{00D3}', '\u{00D4}', '\u{00D5}', '\u{00D6}', '\u{00D7}',
+    '\u{00D8}', '\u{00D9}', '\u{00DA}', '\u{00DB}', '\u{00DC}', '\u{00DD}', '\u{00DE}', '\u{00DF}',
+    '\u{00E0}', '\u{00E1}', '\u{00E2}', '\u{00E3}', '\u{00E4}', '\u{00E5}', '\u{00E6}', '\u{00E7}',
+    '\u{00E8}', '\u{00E9}', '\u{00EA}', '\u{00EB}', '\u{00EC}', '\u{00ED}', '\u{00EE}', '\u{00EF}',
+    '\u{00F0}', '\u{00F1}', '\u{00F2}', '\u{00F3}', '\u{00F4}', '\u{00F5}', '\u{00F6}', '\u{00F7}',
+    '\u{00F8}', '\u{00F9}', '\u{00FA}', '\u{00FB}', '\u{00FC}', '\u{00FD}', '\u{00FE}', '\u{00FF}',
+];
+
+static KOI8_R: HighHalf = [
+    '\u{2500}', '\u{2502}', '\u{250C}', '\u{2510}', '\u{2514}', '\u{2518}', '\u{251C}', '\u{2524}',
+    '\u{252C}', '\u{2534}', '\u{253C}', '\u{2580}', '\u{2584}', '\u{2588}', '\u{258C}', '\u{2590}',
+    '\u{2591}', '\u{2592}', '\u{2593}', '\u{2320}', '\u{25A0}', '\u{2219}', '\u{221A}', '\u{2248}',
+    '\u{2264}', '\u{2265}', '\u{00A0}', '\u{2321}', '\u{00B0}', '\u{00B2}', '\u{00B7}', '\u{00F7}',
+    '\u{2550}', '\u{2551}', '\u{2552}', '\u{0451}', '\u{2553}', '\u{2554}', '\u{2555}', '\u{2556}',
+    '\u{2557}', '\u{2558}', '\u{2559}', '\u{255A}', '\u{255B}', '\u{255C}', '\u{255D}', '\u{255E}',
+    '\u{255F}', '\u{2560}', '\u{2561}', '\u{0401}', '\u{2562}', '\u{2563}', '\u{2564}', '\u{2565}',
+    '\u{2566}', '\u{2567}', '\u{2568}', '\u{2569}', '\u{256A}', '\u{256B}', '\u{256C}', '\u{00A9}',
+    '\u{044E}', '\u{0430}', '\u{0431}', '\u{0446}', '\u{0434}', '\u{0435}', '\u{0444}', '\u{0433}',
+    '\u{0445}', '\u{0438}', '\u{0439}', '\u{043A}', '\u{043B}', '\u{043C}', '\u{043D}', '\u{043E}',
+    '\u{043F}', '\u{044F}', '\u{0440}', '\u{0441}', '\u{0442}', '\u{0443}', '\u{0436}', '\u{0432}',
+    '\u{044C}', '\u{044B}', '\u{0437}', '\u{0448}', '\u{044D}', '\u{0449}', '\u{0447}', '\u{044A}',
+    '\u{042E}', '\u{0410}', '\u{0411}', '\u{0426}', '\u{0414}', '\u{0415}', '\u{0424}', '\u{0413}',
+    '\u{0425}', '\u{0418}', '\u{0419}', '\u{041A}', '\u{041B}', '\u{041C}', '\u{041D}', '\u{041E}',
+    '\u{041F}', '\u{042F}', '\u{0420}', '\u{0421}', '\u{0422}', '\u{0423}', '\u{0416}', '\u{0412}',
+    '\u{042C}', '\u{042B}', '\u{0417}', '\u{0428}', '\u{042D}', '\u{0429}', '\u{0427}', '\u{042A}',
+];
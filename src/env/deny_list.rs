@@ -1,6 +1,7 @@
 use std::{borrow::Cow, collections::HashSet, ffi::OsStr};
 
 use super::{Env, GetEnv};
+use crate::parse::Origin;
 
 pub struct DenyListEnv<'a, E> {
     env: E,
@@ -67,6 +68,13 @@ where E: Env {
         }
     }
 
+    #[inline]
+    fn set_from(&mut self, key: &OsStr, value: &OsStr, origin: &Origin) {
+        if !self.deny_list.contains(key) {
+            self.env.set_from(key, value, origin);
+        }
+    }
+
     #[inline]
     fn remove(&mut self, key: &OsStr) {
         if !self.deny_list.contains(key) {
@@ -3,9 +3,9 @@ pub mod empty;
 pub mod allow_list;
 pub mod deny_list;
 
-use std::{borrow::Cow, collections::HashMap, ffi::{OsStr, OsString}, hash::BuildHasher};
+use std::{borrow::Cow, collections::HashMap, ffi::{OsStr, OsString}, hash::BuildHasher, path::PathBuf};
 
-use crate::{options::{DEFAULT_PATH, IllegalOption, OptionType}, Dialect, Encoding, Error, ErrorKind, Result};
+use crate::{options::{DEFAULT_PATH, IllegalOption, OptionType}, parse::Origin, search::Search, Dialect, Encoding, Error, KeyMapping, ErrorKind, Result};
 
 pub use system::{SystemEnv, SYSTEM_ENV};
 pub use empty::EmptyEnv;
@@ -22,6 +22,36 @@ pub fn parse_bool(value: &OsStr) -> Option<bool> {
     }
 }
 
+/// The bytes of `value`. On Unix these are the raw bytes of the variable,
+/// elsewhere the value needs to be valid Unicode and its UTF-8 is returned.
+#[inline]
+pub fn os_str_as_bytes(value: &OsStr) -> Option<&[u8]> {
+    #[cfg(unix)]
+    {
+        Some(std::os::unix::ffi::OsStrExt::as_bytes(value))
+    }
+
+    #[cfg(not(unix))]
+    {
+        value.to_str().map(str::as_bytes)
+    }
+}
+
+/// The inverse of [`os_str_as_bytes()`]. Off Unix `bytes` need to be valid
+/// UTF-8.
+#[inline]
+pub fn os_str_from_bytes(bytes: &[u8]) -> Option<&OsStr> {
+    #[cfg(unix)]
+    {
+        Some(std::os::unix::ffi::OsStrExt::from_bytes(bytes))
+    }
+
+    #[cfg(not(unix))]
+    {
+        std::str::from_utf8(bytes).ok().map(OsStr::new)
+    }
+}
+
 pub trait GetEnv {
     fn get<'a>(&'a self, key: &OsStr) -> Option<Cow<'a, OsStr>>;
 
@@ -34,6 +64,20 @@ pub trait GetEnv {
             })
     }
 
+    #[inline]
+    fn get_mode(&self) -> Option<OsString> {
+        self.get("DOTENV_CONFIG_MODE".as_ref())
+            .filter(|mode| !mode.is_empty())
+            .map(Cow::into_owned)
+    }
+
+    #[inline]
+    fn get_include_root(&self) -> Option<PathBuf> {
+        self.get("DOTENV_CONFIG_INCLUDE_ROOT".as_ref())
+            .filter(|path| !path.is_empty())
+            .map(|path| PathBuf::from(path.into_owned()))
+    }
+
     #[inline]
     fn get_override_env(&self) -> Result<bool> {
         self.get_bool("DOTENV_CONFIG_OVERRIDE".as_ref(), false)
@@ -69,6 +113,24 @@ pub trait GetEnv {
         Ok(encoding)
     }
 
+    fn get_fallback_encoding(&self) -> Result<Option<Encoding>> {
+        let encoding_key = OsStr::new("DOTENV_CONFIG_FALLBACK_ENCODING");
+        let Some(encoding) = self.get(encoding_key).filter(|encoding| !encoding.is_empty()) else {
+            return Ok(None);
+        };
+
+        let Ok(encoding) = Encoding::try_from(encoding.as_ref()) else {
+            return Err(Error::with_cause(
+                ErrorKind::OptionsParseError,
+                IllegalOption::new(
+                    encoding_key.to_owned(),
+                    encoding.into(),
+                    OptionType::Encoding)));
+        };
+
+        Ok(Some(encoding))
+    }
+
     fn get_dialect(&self) -> Result<Dialect> {
         let dialect_key = OsStr::new("DOTENV_CONFIG_DIALECT");
         let dialect = self.get(dialect_key);
@@ -89,6 +151,44 @@ pub trait GetEnv {
         Ok(dialect)
     }
 
+    fn get_key_mapping(&self) -> Result<KeyMapping> {
+        let key_mapping_key = OsStr::new("DOTENV_CONFIG_KEY_MAPPING");
+        let key_mapping = self.get(key_mapping_key);
+        let key_mapping = if let Some(key_mapping) = key_mapping {
+            let Ok(key_mapping) = KeyMapping::try_from(key_mapping.as_ref()) else {
+                return Err(Error::with_cause(
+                    ErrorKind::OptionsParseError,
+                    IllegalOption::new(
+                        key_mapping_key.to_owned(),
+                        key_mapping.into(),
+                        OptionType::KeyMapping)));
+            };
+            key_mapping
+        } else {
+            KeyMapping::default()
+        };
+
+        Ok(key_mapping)
+    }
+
+    fn get_search(&self) -> Result<Search> {
+        let search_key = OsStr::new("DOTENV_CONFIG_SEARCH");
+        let Some(search) = self.get(search_key) else {
+            return Ok(Search::default());
+        };
+
+        let Ok(search) = Search::try_from(search.as_ref()) else {
+            return Err(Error::with_cause(
+                ErrorKind::OptionsParseError,
+                IllegalOption::new(
+                    search_key.to_owned(),
+                    search.into(),
+                    OptionType::Search)));
+        };
+
+        Ok(search)
+    }
+
     fn get_bool(&self, key: &OsStr, default_value: bool) -> Result<bool> {
         if let Some(value) = self.get(key) {
             let value: &OsStr = &value;
@@ -114,6 +214,14 @@ pub trait GetEnv {
 
 pub trait Env: GetEnv {
     fn set(&mut self, key: &OsStr, value: &OsStr);
+
+    /// Called by the parsers instead of [`Env::set()`], so an environment can
+    /// record where a value came from. Defaults to just setting it.
+    #[inline]
+    fn set_from(&mut self, key: &OsStr, value: &OsStr, _origin: &Origin) {
+        self.set(key, value);
+    }
+
     fn remove(&mut self, key: &OsStr);
     fn as_get_env(&self) -> &dyn GetEnv;
 }
@@ -172,6 +280,11 @@ impl<T: Env> Env for &mut T where Self: GetEnv {
         (**self).set(key, value);
     }
 
+    #[inline]
+    fn set_from(&mut self, key: &OsStr, value: &OsStr, origin: &Origin) {
+        (**self).set_from(key, value, origin);
+    }
+
     #[inline]
     fn remove(&mut self, key: &OsStr) {
         (**self).remove(key);
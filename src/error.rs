@@ -1,3 +1,5 @@
+use std::path::{Path, PathBuf};
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum ErrorKind {
     OptionsParseError,
@@ -7,6 +9,9 @@ pub enum ErrorKind {
     ExecError,
     IllegalArgument,
     NotEnoughArguments,
+    Unrepresentable,
+    AmbiguousDialect,
+    IncludeError,
 }
 
 impl std::fmt::Display for ErrorKind {
@@ -39,11 +44,37 @@ impl SourceLocation {
     }
 }
 
+/// A step of [`Error::include_chain()`]: `path` was included by the
+/// `include` statement at `location` of the including file.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Include {
+    path: PathBuf,
+    location: SourceLocation,
+}
+
+impl Include {
+    #[inline]
+    pub fn new(path: impl Into<PathBuf>, location: SourceLocation) -> Self {
+        Self { path: path.into(), location }
+    }
+
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    #[inline]
+    pub fn location(&self) -> SourceLocation {
+        self.location
+    }
+}
+
 #[derive(Debug)]
 pub struct Error {
     kind: ErrorKind,
     cause: Option<Box<dyn std::error::Error>>,
     location: Option<SourceLocation>,
+    include_chain: Vec<Include>,
 }
 
 impl Error {
@@ -54,6 +85,7 @@ impl Error {
             cause: Some(cause.into()),
             kind,
             location: Some(location),
+            include_chain: Vec::new(),
         }
     }
 
@@ -64,6 +96,7 @@ impl Error {
             cause: Some(cause.into()),
             kind,
             location: None,
+            include_chain: Vec::new(),
         }
     }
 
@@ -73,6 +106,7 @@ impl Error {
             cause: None,
             kind,
             location: Some(location),
+            include_chain: Vec::new(),
         }
     }
 
@@ -82,6 +116,7 @@ impl Error {
             cause: None,
             kind: ErrorKind::SyntaxError,
             location: Some(SourceLocation::new(lineno, column)),
+            include_chain: Vec::new(),
         }
     }
 
@@ -91,6 +126,7 @@ impl Error {
             cause: None,
             kind: ErrorKind::SubstitutionError,
             location: Some(SourceLocation::new(lineno, column)),
+            include_chain: Vec::new(),
         }
     }
 
@@ -103,12 +139,27 @@ impl Error {
     pub fn location(&self) -> &Option<SourceLocation> {
         &self.location
     }
+
+    /// If the error happened in an included file, the files from the one with
+    /// the error out to the one that was loaded, each with the location of
+    /// the `include` statement that included it. The location of the error is
+    /// in the first file.
+    #[inline]
+    pub fn include_chain(&self) -> &[Include] {
+        &self.include_chain
+    }
+
+    #[inline]
+    pub(crate) fn included(mut self, include: Include) -> Self {
+        self.include_chain.push(include);
+        self
+    }
 }
 
 impl From<ErrorKind> for Error {
     #[inline]
     fn from(kind: ErrorKind) -> Self {
-        Self { kind, cause: None, location: None }
+        Self { kind, cause: None, location: None, include_chain: Vec::new() }
     }
 }
 
@@ -121,6 +172,11 @@ impl std::fmt::Display for Error {
             write!(f, " on line {} at column {}", location.lineno, location.column)?;
         }
 
+        for include in &self.include_chain {
+            write!(f, " in {}, included on line {} at column {}",
+                include.path.display(), include.location.lineno, include.location.column)?;
+        }
+
         if let Some(cause) = &self.cause {
             write!(f, ": {cause}")?
         }
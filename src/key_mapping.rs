@@ -0,0 +1,120 @@
+use std::{ffi::OsStr, sync::Arc};
+
+/// Turns a key of a file format that doesn't use environment variable names
+/// into one. Implemented for closures.
+pub trait KeyMapper: Send + Sync {
+    fn map(&self, key: &str) -> String;
+}
+
+impl<F> KeyMapper for F
+where F: Fn(&str) -> String + Send + Sync {
+    #[inline]
+    fn map(&self, key: &str) -> String {
+        self(key)
+    }
+}
+
+/// How dialects whose keys aren't environment variable names (like
+/// [`crate::Dialect::JavaProperties`]) map them to variable names.
+///
+/// Two instances are equal if they are the same variant and, for hooks, refer
+/// to the same hook.
+#[derive(Clone, Default)]
+pub enum KeyMapping {
+    /// Uppercase the key and replace everything that isn't a letter, a digit
+    /// or `_` with `_`: `spring.datasource.url` → `SPRING_DATASOURCE_URL`,
+    /// `log-level` → `LOG_LEVEL`.
+    #[default]
+    UpperSnake,
+
+    /// The inverse of Spring Boot's relaxed binding: `.` and `[` become `_`,
+    /// `-` and `]` are removed and the rest is uppercased:
+    /// `my.main-project.list[0]` → `MY_MAINPROJECT_LIST_0`.
+    Spring,
+
+    /// Use the key as is.
+    Keep,
+
+    /// Pass the key to a caller-supplied hook.
+    Hook(Arc<dyn KeyMapper>),
+}
+
+impl KeyMapping {
+    #[inline]
+    pub fn hook(hook: impl KeyMapper + 'static) -> Self {
+        KeyMapping::Hook(Arc::new(hook))
+    }
+
+    pub fn map(&self, key: &str) -> String {
+        match self {
+            KeyMapping::UpperSnake => key.chars().
+                flat_map(|ch| {
+                    let ch = if ch.is_alphanumeric() { ch } else { '_' };
+                    ch.to_uppercase()
+                }).
+                collect(),
+            KeyMapping::Spring => key.chars().
+                filter(|&ch| ch != '-' && ch != ']').
+                flat_map(|ch| {
+                    let ch = if ch == '.' || ch == '[' { '_' } else { ch };
+                    ch.to_uppercase()
+                }).
+                collect(),
+            KeyMapping::Keep => key.to_owned(),
+            KeyMapping::Hook(hook) => hook.map(key),
+        }
+    }
+}
+
+impl PartialEq for KeyMapping {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (KeyMapping::UpperSnake, KeyMapping::UpperSnake) => true,
+            (KeyMapping::Spring,     KeyMapping::Spring)     => true,
+            (KeyMapping::Keep,       KeyMapping::Keep)       => true,
+            (KeyMapping::Hook(lhs), KeyMapping::Hook(rhs)) => Arc::ptr_eq(lhs, rhs),
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Debug for KeyMapping {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeyMapping::UpperSnake => f.write_str("UpperSnake"),
+            KeyMapping::Spring     => f.write_str("Spring"),
+            KeyMapping::Keep       => f.write_str("Keep"),
+            KeyMapping::Hook(_)    => f.write_str("Hook(..)"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct IllegalKeyMapping();
+
+impl std::fmt::Display for IllegalKeyMapping {
+    #[inline]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        "IllegalKeyMapping".fmt(f)
+    }
+}
+
+impl std::error::Error for IllegalKeyMapping {}
+
+impl TryFrom<&OsStr> for KeyMapping {
+    type Error = IllegalKeyMapping;
+
+    fn try_from(value: &OsStr) -> std::result::Result<Self, Self::Error> {
+        if value.is_empty() ||
+           value.eq_ignore_ascii_case("UpperSnake") ||
+           value.eq_ignore_ascii_case("upper-snake") {
+            Ok(KeyMapping::UpperSnake)
+        } else if value.eq_ignore_ascii_case("Spring") {
+            Ok(KeyMapping::Spring)
+        } else if value.eq_ignore_ascii_case("Keep") {
+            Ok(KeyMapping::Keep)
+        } else {
+            Err(IllegalKeyMapping())
+        }
+    }
+}
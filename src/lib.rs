@@ -12,16 +12,6 @@ use std::io::BufReader;
 use std::path::Path;
 
 pub mod error;
-use dialects::binary::config_binary;
-use dialects::composego::config_composego;
-use dialects::go_dotenv::config_go_dotenv;
-use dialects::java_dotenv::config_java_dotenv;
-use dialects::javascript_dotenv::config_javascript_dotenv;
-use dialects::nodejs::config_nodejs;
-use dialects::punktum::config_punktum;
-use dialects::python_dotenv::config_python_dotenv;
-use dialects::python_dotenv_cli::config_python_dotenv_cli;
-use dialects::ruby_dotenv::config_ruby_dotenv;
 use env::SYSTEM_ENV;
 pub use error::Error;
 pub use error::ErrorKind;
@@ -40,13 +30,41 @@ use env::{GetEnv, SystemEnv};
 pub mod encoding;
 pub use encoding::Encoding;
 
+pub mod codec;
+pub use codec::{DecodingReader, EncodingWriter};
+
 pub mod dialect;
-pub use dialect::Dialect;
+pub use dialect::{register_dialect, Dialect, DialectParser};
 
 pub mod dialects;
 
+pub mod command;
+pub use command::{CommandHook, CommandPolicy};
+
+pub mod key_mapping;
+pub use key_mapping::{KeyMapper, KeyMapping};
+
+pub mod diagnostic;
+pub use diagnostic::{Diagnostic, DiagnosticCode, DiagnosticSink, Severity};
+
+pub mod document;
+
+pub mod parse;
+pub use parse::{parse, parse_with_parent, Entries, Entry, Origin};
+
 pub mod line_splitter;
 
+pub mod write;
+pub use write::Writer;
+
+pub mod modeline;
+pub use modeline::Modeline;
+
+pub mod cascade;
+
+pub mod search;
+pub use search::Search;
+
 pub(crate) const DEBUG_PREFIX: &str = concat!("[", env!("CARGO_PKG_NAME"), "@", env!("CARGO_PKG_VERSION"), "][DEBUG] ");
 
 #[inline]
@@ -92,11 +110,18 @@ where P: AsRef<Path> {
         return config_with_reader(&mut std::io::stdin().lock(), env, parent, options);
     }
 
+    if options.search == Search::Upward && path.is_relative() {
+        return config_found_upward(env, parent, options);
+    }
+
+    if let Some(mode) = &options.mode {
+        return cascade::config_cascade(env, parent, options, mode);
+    }
+
     let file = match File::open(path) {
         Err(err) => {
-            if options.debug {
-                let path_str = path.to_string_lossy();
-                eprintln!("{DEBUG_PREFIX}{path_str}: {err}");
+            if options.diagnostics_enabled() {
+                options.report(Diagnostic::new(DiagnosticCode::IOError, err.to_string()));
             }
             if options.strict {
                 return Err(Error::with_cause(ErrorKind::IOError, err));
@@ -110,30 +135,107 @@ where P: AsRef<Path> {
     config_with_reader(&mut reader, env, parent, options)
 }
 
+/// Load the file that [`Search::Upward`] finds for `options.path`. If there is
+/// none the path is used as is, so it is reported like any missing file.
+fn config_found_upward<P>(env: &mut impl Env, parent: &impl GetEnv, options: &Options<P>) -> Result<()>
+where P: AsRef<Path> {
+    let path = options.path.as_ref();
+    let found = std::env::current_dir().ok().and_then(|dir|
+        search::find_upward(&dir, path, options.mode.as_deref()));
+
+    if options.diagnostics_enabled() {
+        if let Some(found) = &found {
+            options.report(Diagnostic::new(DiagnosticCode::FileLookup,
+                format!("using {}", found.display())));
+        } else {
+            options.report(Diagnostic::new(DiagnosticCode::FileLookup,
+                "not found in the current directory or any of its parents"));
+        }
+    }
+
+    let options = Options {
+        override_env:      options.override_env,
+        strict:            options.strict,
+        debug:             options.debug,
+        encoding:          options.encoding,
+        fallback_encoding: options.fallback_encoding,
+        dialect:           options.dialect,
+        path:              found.as_deref().unwrap_or(path),
+        mode:              options.mode.clone(),
+        search:            Search::Off,
+        include_root:      options.include_root.clone(),
+        diagnostics:       options.diagnostics.clone(),
+        command_policy:    options.command_policy.clone(),
+        key_mapping:       options.key_mapping.clone(),
+    };
+
+    config_with_options(env, parent, &options)
+}
+
 #[inline]
 pub fn config_with_reader<P>(reader: &mut dyn BufRead, env: &mut impl Env, parent: &impl GetEnv, options: &Options<P>) -> Result<()>
 where P: AsRef<Path> {
     let options = Options {
-        override_env: options.override_env,
-        strict:       options.strict,
-        debug:        options.debug,
-        encoding:     options.encoding,
-        dialect:      options.dialect,
-        path:         options.path.as_ref(),
+        override_env:      options.override_env,
+        strict:            options.strict,
+        debug:             options.debug,
+        encoding:          options.encoding,
+        fallback_encoding: options.fallback_encoding,
+        dialect:           options.dialect,
+        path:              options.path.as_ref(),
+        mode:              options.mode.clone(),
+        search:            options.search,
+        include_root:      options.include_root.clone(),
+        diagnostics:       options.diagnostics.clone(),
+        command_policy:    options.command_policy.clone(),
+        key_mapping:       options.key_mapping.clone(),
     };
 
-    match options.dialect {
-        Dialect::Punktum          => config_punktum(          reader, env, parent, &options),
-        Dialect::JavaScriptDotenv => config_javascript_dotenv(reader, env, &options),
-        Dialect::NodeJS           => config_nodejs(           reader, env, &options),
-        Dialect::PythonDotenv     => config_python_dotenv(    reader, env, &options),
-        Dialect::PythonDotenvCLI  => config_python_dotenv_cli(reader, env, &options),
-        Dialect::ComposeGo        => config_composego(        reader, env, parent, &options),
-        Dialect::GoDotenv         => config_go_dotenv(        reader, env, &options),
-        Dialect::RubyDotenv       => config_ruby_dotenv(      reader, env, parent, &options),
-        Dialect::JavaDotenv       => config_java_dotenv(      reader, env, &options),
-        Dialect::Binary           => config_binary(           reader, env, &options),
+    config_with_dyn_reader(reader, env, parent, options)
+}
+
+/// [`config_with_reader()`] for when there are only trait objects, like in a
+/// [`DialectParser`] that loads another file.
+pub(crate) fn config_with_dyn_reader(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dyn GetEnv, mut options: Options<&Path>) -> Result<()> {
+    let head = read_head(reader, &mut options)?;
+
+    let mut reader = std::io::Read::chain(head.as_slice(), reader);
+
+    // Parsers only get to see UTF-8. Binary ignores the encoding.
+    if options.encoding == Encoding::UTF8 || options.dialect == Dialect::Binary {
+        return options.dialect.parser().parse(&mut reader, env, parent, &options);
+    }
+
+    let mut reader = DecodingReader::new(reader, options.encoding).lossy(!options.strict);
+    options.encoding = Encoding::UTF8;
+
+    let result = options.dialect.parser().parse(&mut reader, env, parent, &options);
+
+    for error in reader.take_errors() {
+        options.report_decode_error(&error);
     }
+
+    result
+}
+
+/// Apply the modeline of `reader` to `options` and resolve [`Encoding::Auto`].
+/// Returns the bytes that were read to do so, since they still have to be
+/// parsed.
+pub(crate) fn read_head<P>(reader: &mut dyn BufRead, options: &mut Options<P>) -> Result<Vec<u8>>
+where P: AsRef<Path> {
+    let mut head = modeline::read_modeline(reader, options)?;
+
+    if options.encoding == Encoding::Auto {
+        let (encoding, rest) = encoding::resolve_auto(head, reader, options.fallback_encoding)?;
+        if options.diagnostics_enabled() {
+            options.report(Diagnostic::new(DiagnosticCode::EncodingDetection,
+                format!("detected encoding {encoding:?}")));
+        }
+        options.encoding = encoding;
+        head = rest;
+    }
+
+    Ok(head)
 }
 
 pub trait EnvWrite {
@@ -142,8 +244,8 @@ pub trait EnvWrite {
 
 impl<K, V> EnvWrite for HashMap<K, V>
 where
-    K: AsRef<str>,
-    V: AsRef<str>
+    K: AsRef<OsStr>,
+    V: AsRef<OsStr>
 {
     #[inline]
     fn write_env(&self, writer: impl std::io::Write) -> std::io::Result<()> {
@@ -151,39 +253,69 @@ where
     }
 }
 
-pub fn write_var(mut writer: impl std::io::Write, key: impl AsRef<str>, value: impl AsRef<str>)  -> std::io::Result<()> {
-    let key = key.as_ref();
-    let mut value = value.as_ref();
-    write!(writer, "{key}='")?;
+/// On Unix keys and values are written as their raw bytes, elsewhere a
+/// key or value that isn't valid Unicode is an error of kind
+/// [`std::io::ErrorKind::InvalidData`].
+fn os_str_as_bytes(value: &OsStr) -> std::io::Result<&[u8]> {
+    env::os_str_as_bytes(value).ok_or_else(|| std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("{value:?} is not valid Unicode")))
+}
+
+pub fn write_var(mut writer: impl std::io::Write, key: impl AsRef<OsStr>, value: impl AsRef<OsStr>)  -> std::io::Result<()> {
+    let key = os_str_as_bytes(key.as_ref())?;
+    let mut value = os_str_as_bytes(value.as_ref())?;
+    writer.write_all(key)?;
+    writer.write_all(b"='")?;
 
-    while let Some(index) = value.find('\'') {
-        write!(writer, "{}'\"'\"'", &value[..index])?;
+    while let Some(index) = value.iter().position(|&byte| byte == b'\'') {
+        writer.write_all(&value[..index])?;
+        writer.write_all(b"'\"'\"'")?;
         value = &value[index + 1..];
     }
 
-    writeln!(writer, "{value}'")?;
+    writer.write_all(value)?;
+    writer.write_all(b"'\n")?;
 
     Ok(())
 }
 
-pub fn write_iter(mut writer: impl std::io::Write, iter: impl Iterator<Item=(impl AsRef<str>, impl AsRef<str>)>) -> std::io::Result<()> {
+pub fn write_iter(mut writer: impl std::io::Write, iter: impl Iterator<Item=(impl AsRef<OsStr>, impl AsRef<OsStr>)>) -> std::io::Result<()> {
     for (key, value) in iter {
         write_var(&mut writer, key, value)?;
     }
     Ok(())
 }
 
-pub fn write_var_binary(mut writer: impl std::io::Write, key: impl AsRef<str>, value: impl AsRef<str>)  -> std::io::Result<()> {
-    let key = key.as_ref();
-    let value = value.as_ref();
-    write!(writer, "{key}={value}\0")?;
+pub fn write_var_binary(mut writer: impl std::io::Write, key: impl AsRef<OsStr>, value: impl AsRef<OsStr>)  -> std::io::Result<()> {
+    let key = os_str_as_bytes(key.as_ref())?;
+    let value = os_str_as_bytes(value.as_ref())?;
+    writer.write_all(key)?;
+    writer.write_all(b"=")?;
+    writer.write_all(value)?;
+    writer.write_all(b"\0")?;
 
     Ok(())
 }
 
-pub fn write_iter_binary(mut writer: impl std::io::Write, iter: impl Iterator<Item=(impl AsRef<str>, impl AsRef<str>)>) -> std::io::Result<()> {
+pub fn write_iter_binary(mut writer: impl std::io::Write, iter: impl Iterator<Item=(impl AsRef<OsStr>, impl AsRef<OsStr>)>) -> std::io::Result<()> {
     for (key, value) in iter {
         write_var_binary(&mut writer, key, value)?;
     }
     Ok(())
 }
+
+/// Write `key` and `value` in the syntax of [`Dialect::Systemd`], see
+/// [`Writer`].
+#[inline]
+pub fn write_var_systemd(writer: impl std::io::Write, key: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> Result<()> {
+    Writer::new(writer, Dialect::Systemd).write_var_os(key, value)
+}
+
+pub fn write_iter_systemd(writer: impl std::io::Write, iter: impl Iterator<Item=(impl AsRef<OsStr>, impl AsRef<OsStr>)>) -> Result<()> {
+    let mut writer = Writer::new(writer, Dialect::Systemd);
+    for (key, value) in iter {
+        writer.write_var_os(key, value)?;
+    }
+    Ok(())
+}
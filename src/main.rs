@@ -1,17 +1,29 @@
-use std::{collections::HashMap, env::ArgsOs, ffi::{OsStr, OsString}, io::Write, path::Path, process::Command};
+use std::{borrow::Cow, collections::HashMap, env::ArgsOs, ffi::{OsStr, OsString}, io::Write, path::Path, process::Command};
 
 #[cfg(target_family = "unix")]
 use std::os::unix::process::CommandExt;
 
-use punktum::{env::{parse_bool, AllowListEnv, DenyListEnv}, options::{Builder, IllegalOption, OptionType}, Dialect, Encoding, Env, Error, ErrorKind};
+use punktum::{env::{parse_bool, AllowListEnv, DenyListEnv, EmptyEnv, GetEnv, SYSTEM_ENV}, options::{Builder, IllegalOption, OptionType}, Dialect, Encoding, EncodingWriter, Env, Error, ErrorKind, KeyMapping, Origin, Writer};
 
 const USAGE: &str = concat!("\
 usage: ", env!("CARGO_BIN_NAME"), " [--file=PATH...] [--replace] [--] command [args...]
-       ", env!("CARGO_BIN_NAME"), " [--file=PATH...] [--replace] --print-env [--sorted] [--export] [--binary]
+       ", env!("CARGO_BIN_NAME"), " [--file=PATH...] [--replace] --print-env [--sorted] [--export] [--binary] [--systemd]
+       ", env!("CARGO_BIN_NAME"), " convert --from=DIALECT --to=DIALECT [--file=PATH...] [--inherit] [--sorted]
        ", env!("CARGO_BIN_NAME"), " [--help] [--version]
 
 Punktum executes a given command with environment variables loaded from a .env file.
 
+A file can set its own dialect and encoding with a comment in its first line,
+which takes precedence over all options:
+
+    # punktum: dialect=ruby-dotenv encoding=latin1
+
+The convert mode parses the given files with the --from dialect and prints the
+variables in the syntax of the --to dialect. Variables that can't be written in
+the target dialect are reported and left out. Values that were produced by
+variable substitution are written as they were substituted, which is reported
+as a warning, since the meaning of the file changes.
+
 Positional arguments:
   command                   Program to execute.
 
@@ -22,17 +34,35 @@ Optional arguments:
                             This option can be passed multiple times.
                             All files are loaded in order.
                             Pass \"-\" to read from stdin.
+      --file-dialect=DIALECT
+                            Dialect of the preceding --file only.
+      --file-encoding=ENCODING
+                            Encoding of the preceding --file only.
   -r, --replace             Completely replace the environment with the one loaded
                             from the .env file.
   -p, --print-env           Instead of running a command print the built environment
                             in a syntax compatible to Punktum and bash.
       --sorted              Sort printed environment variables for reproducible output.
       --export              Add \"export \" prefix to every printed environment variable.
+      --systemd             Print the environment in the syntax of systemd's
+                            EnvironmentFile= (i.e. the Systemd dialect).
+      --output-encoding=ENCODING
+                            Encoding of the printed environment. Without it
+                            values are printed as is, which on Unix means their
+                            raw bytes even if they aren't valid UTF-8.
       --strict=bool         Overwrite DOTENV_CONFIG_STRICT
       --debug=bool          Overwrite DOTENV_CONFIG_DEBUG
       --override=bool       Overwrite DOTENV_CONFIG_OVERRIDE
       --encoding=ENCODING   Overwrite DOTENV_CONFIG_ENCODING
+      --fallback-encoding=ENCODING
+                            Overwrite DOTENV_CONFIG_FALLBACK_ENCODING
       --dialect=DIALECT     Overwrite DOTENV_CONFIG_DIALECT
+      --mode=MODE           Overwrite DOTENV_CONFIG_MODE
+      --from=DIALECT        convert only: Dialect of the input files.
+      --to=DIALECT          convert only: Dialect of the output.
+      --inherit             convert only: Substitute variables that aren't defined
+                            in the files from the process environment. Without
+                            this option they are unset.
 
 Environemnt variables:
   DOTENV_CONFIG_PATH=FILE
@@ -64,11 +94,21 @@ Environemnt variables:
     Supported values:
     - ASCII
     - ISO-8859-1  (alias: Latin1)
+    - ISO-8859-2  (alias: Latin2)
+    - ISO-8859-15 (alias: Latin9)
+    - Windows-1252 (alias: CP1252)
+    - KOI8-R
     - UTF-8       (default)
     - UTF-16BE
     - UTF-16LE
     - UTF-32BE
     - UTF-32LE
+    - Auto        (detect UTF-8/16/32 by BOM, which is skipped)
+
+  DOTENV_CONFIG_FALLBACK_ENCODING=ENCODING
+    Encoding used by Auto if the file has no BOM and isn't valid UTF-8.
+    This can be overwritten with --fallback-encoding.
+    [default: none]
 
   DOTENV_CONFIG_DIALECT=DIALECT
     Dialect for the parser to use.
@@ -78,14 +118,56 @@ Environemnt variables:
     Supported values:
     - Punktum (default)
     - NodeJS
+    - NodeJS20
+    - NodeJS24
     - JavaScriptDotenv
+    - DotenvExpand
+    - Dotenvx
     - PythonDotenv
     - PythonDotenvCLI
     - ComposeGo
+    - ComposeRaw
+    - DockerCLI
     - GoDotenv
     - RubyDotenv
+    - PhpDotenv
     - JavaDotenv
+    - JavaProperties
+    - Dotenvy
+    - Systemd
+    - PosixShell
     - Binary
+    - Auto        (guess from the file's content)
+
+  DOTENV_CONFIG_MODE=MODE
+    Instead of just the file load the cascade of files for this mode
+    (e.g. development), from lowest to highest precedence:
+      .env, .env.local, .env.MODE, .env.MODE.local
+    Missing files are skipped. In the mode \"test\" .env.local isn't loaded.
+    With --file the names are derived from each given file.
+    This can be overwritten with --mode, pass an empty value to disable it.
+    [default: none]
+
+  DOTENV_CONFIG_SEARCH=off|upward
+    With upward a relative file that isn't in the current directory is
+    searched in its parent directories, up to the root of the repository
+    (a directory with .git, .hg, .jj or .svn) or of the filesystem.
+    Use --debug=true to see which file was used.
+    [default: off]
+
+  DOTENV_CONFIG_INCLUDE_ROOT=DIR
+    Files included with include in the Punktum dialect have to be inside of
+    this directory.
+    [default: none]
+
+  DOTENV_CONFIG_KEY_MAPPING=MAPPING
+    How the JavaProperties dialect turns property keys into variable names.
+    [default: UpperSnake]
+
+    Supported values:
+    - UpperSnake  (default, alias: upper-snake)  a.b-c[0] -> A_B_C_0_
+    - Spring      a.b-c[0] -> A_BC_0
+    - Keep
 
   DOTENV_LINEBREAK_MODE=legacy
     RubyDotenv dialect-only. If this environment variable is set to \"legacy\"
@@ -96,7 +178,30 @@ Environemnt variables:
 GitHub: https://github.com/panzi/punktum
 ");
 
-fn config_with_lists<P>(env: &mut impl Env, allow_list: &Option<Vec<OsString>>, deny_list: &Option<Vec<OsString>>, files: &[impl AsRef<OsStr>], builder: &Builder<P>) -> punktum::Result<()>
+/// A `--file` argument and the settings given for only that file.
+#[derive(Debug)]
+struct FileArg {
+    path: OsString,
+    dialect: Option<Dialect>,
+    encoding: Option<Encoding>,
+}
+
+impl FileArg {
+    #[inline]
+    fn new(path: impl Into<OsString>) -> Self {
+        Self { path: path.into(), dialect: None, encoding: None }
+    }
+}
+
+fn last_file<'a>(option: &str, files: &'a mut [FileArg]) -> punktum::Result<&'a mut FileArg> {
+    let Some(file) = files.last_mut() else {
+        eprintln!("Error: {option} has to follow a --file option");
+        return Err(punktum::ErrorKind::IllegalArgument.into());
+    };
+    Ok(file)
+}
+
+fn config_with_lists<P>(env: &mut impl Env, allow_list: &Option<Vec<OsString>>, deny_list: &Option<Vec<OsString>>, files: &[FileArg], builder: &Builder<P>) -> punktum::Result<()>
 where P: AsRef<Path> {
     if let Some(allow_list) = allow_list {
         let mut env = AllowListEnv::from_slice(env, allow_list);
@@ -105,7 +210,7 @@ where P: AsRef<Path> {
         return next(env, deny_list, files, builder);
     }
 
-    fn next<P>(env: &mut impl Env, deny_list: &Option<Vec<OsString>>, files: &[impl AsRef<OsStr>], builder: &Builder<P>) -> punktum::Result<()>
+    fn next<P>(env: &mut impl Env, deny_list: &Option<Vec<OsString>>, files: &[FileArg], builder: &Builder<P>) -> punktum::Result<()>
     where P: AsRef<Path> {
         if let Some(deny_list) = deny_list {
             let mut env = DenyListEnv::from_slice(env, deny_list);
@@ -114,13 +219,20 @@ where P: AsRef<Path> {
             return next(env, files, builder);
         }
 
-        fn next<P>(env: &mut impl Env, files: &[impl AsRef<OsStr>], builder: &Builder<P>) -> punktum::Result<()>
+        fn next<P>(env: &mut impl Env, files: &[FileArg], builder: &Builder<P>) -> punktum::Result<()>
         where P: AsRef<Path> {
             if files.is_empty() {
                 builder.options().config_env(env)?;
             } else {
                 for file in files {
-                    builder.path(file.as_ref()).config_env(env)?;
+                    let mut builder = builder.path(&file.path);
+                    if let Some(dialect) = file.dialect {
+                        builder = builder.dialect(dialect);
+                    }
+                    if let Some(encoding) = file.encoding {
+                        builder = builder.encoding(encoding);
+                    }
+                    builder.config_env(env)?;
                 }
             }
             Ok(())
@@ -195,6 +307,217 @@ fn require_arg(option: &OsStr, args: &mut ArgsOs) -> punktum::Result<OsString> {
     Ok(value)
 }
 
+/// File and origin of a definition. `None` for values not set by a parser.
+type Source = Option<(OsString, Origin)>;
+
+/// Environment of the convert mode. Remembers in which order variables were
+/// defined and where.
+#[derive(Debug, Default)]
+struct ConvertEnv {
+    path: OsString,
+    vars: HashMap<OsString, (OsString, Source)>,
+    keys: Vec<OsString>,
+}
+
+impl ConvertEnv {
+    fn insert(&mut self, key: &OsStr, value: &OsStr, source: Source) {
+        if self.vars.insert(key.to_owned(), (value.to_owned(), source)).is_none() {
+            self.keys.push(key.to_owned());
+        }
+    }
+
+    fn into_vars(mut self) -> Vec<(OsString, OsString, Source)> {
+        self.keys.into_iter().map(|key| {
+            let (value, source) = self.vars.remove(&key).unwrap();
+            (key, value, source)
+        }).collect()
+    }
+}
+
+impl GetEnv for ConvertEnv {
+    #[inline]
+    fn get<'a>(&'a self, key: &OsStr) -> Option<Cow<'a, OsStr>> {
+        self.vars.get(key).map(|(value, _)| Cow::from(value.as_os_str()))
+    }
+}
+
+impl Env for ConvertEnv {
+    #[inline]
+    fn set(&mut self, key: &OsStr, value: &OsStr) {
+        self.insert(key, value, None);
+    }
+
+    #[inline]
+    fn set_from(&mut self, key: &OsStr, value: &OsStr, origin: &Origin) {
+        let source = Some((self.path.clone(), *origin));
+        self.insert(key, value, source);
+    }
+
+    #[inline]
+    fn remove(&mut self, key: &OsStr) {
+        if self.vars.remove(key).is_some() {
+            self.keys.retain(|other| other != key);
+        }
+    }
+
+    #[inline]
+    fn as_get_env(&self) -> &dyn GetEnv {
+        self
+    }
+}
+
+fn convert_files<P>(env: &mut ConvertEnv, parent: &impl GetEnv, files: &[OsString], builder: &Builder<P>) -> punktum::Result<()>
+where P: AsRef<Path> {
+    if files.is_empty() {
+        env.path = builder.options().path.as_ref().as_os_str().to_owned();
+        builder.options().config_with_parent(env, parent)?;
+    } else {
+        for file in files {
+            env.path = file.clone();
+            builder.path(file).config_with_parent(&mut *env, parent)?;
+        }
+    }
+    Ok(())
+}
+
+fn convert(mut args: ArgsOs) -> punktum::Result<()> {
+    let mut files = vec![];
+    let mut from: Option<Dialect> = None;
+    let mut to: Option<Dialect> = None;
+    let mut inherit = false;
+    let mut sorted = false;
+    let mut debug: Option<bool> = None;
+    let mut strict: Option<bool> = None;
+    let mut encoding: Option<Encoding> = None;
+
+    while let Some(arg) = args.next() {
+        if arg == "--inherit" {
+            inherit = true;
+        } else if arg == "--sorted" {
+            sorted = true;
+        } else if arg == "-f" || arg == "--file" {
+            files.push(require_arg(&arg, &mut args)?);
+        } else if arg == "--from" {
+            let value = require_arg(&arg, &mut args)?;
+            from = Some(parse_dialect_option(&arg, &value)?);
+        } else if arg == "--to" {
+            let value = require_arg(&arg, &mut args)?;
+            to = Some(parse_dialect_option(&arg, &value)?);
+        } else if arg == "--strict" {
+            let value = require_arg(&arg, &mut args)?;
+            strict = Some(parse_bool_option(&arg, &value)?);
+        } else if arg == "--debug" {
+            let value = require_arg(&arg, &mut args)?;
+            debug = Some(parse_bool_option(&arg, &value)?);
+        } else if arg == "--encoding" {
+            let value = require_arg(&arg, &mut args)?;
+            encoding = Some(parse_encoding_option(&arg, &value)?);
+        } else if arg == "-h" || arg == "--help" {
+            print!("{USAGE}");
+            return Ok(());
+        } else {
+            let str_arg = arg.to_str().unwrap_or("");
+
+            if let Some(file) = str_arg.strip_prefix("--file=") {
+                files.push(OsStr::new(file).into());
+            } else if let Some(value) = str_arg.strip_prefix("--from=") {
+                from = Some(parse_dialect_option("--from", value)?);
+            } else if let Some(value) = str_arg.strip_prefix("--to=") {
+                to = Some(parse_dialect_option("--to", value)?);
+            } else if let Some(value) = str_arg.strip_prefix("--strict=") {
+                strict = Some(parse_bool_option("--strict", value)?);
+            } else if let Some(value) = str_arg.strip_prefix("--debug=") {
+                debug = Some(parse_bool_option("--debug", value)?);
+            } else if let Some(value) = str_arg.strip_prefix("--encoding=") {
+                encoding = Some(parse_encoding_option("--encoding", value)?);
+            } else {
+                eprintln!("Error: illegal argument: {arg:?}");
+                return Err(punktum::ErrorKind::IllegalArgument.into());
+            }
+        }
+    }
+
+    let (Some(from), Some(to)) = (from, to) else {
+        eprintln!("Error: convert requires --from and --to");
+        return Err(punktum::ErrorKind::NotEnoughArguments.into());
+    };
+
+    // convert works on the given files only
+    let mut builder = punktum::build_from_env()?.dialect(from).mode(None);
+
+    if let Some(debug) = debug {
+        builder = builder.debug(debug);
+    }
+
+    if let Some(strict) = strict {
+        builder = builder.strict(strict);
+    }
+
+    if let Some(encoding) = encoding {
+        builder = builder.encoding(encoding);
+    }
+
+    let mut env = ConvertEnv::default();
+    if inherit {
+        convert_files(&mut env, &SYSTEM_ENV, &files, &builder)?;
+    } else {
+        convert_files(&mut env, &EmptyEnv(), &files, &builder)?;
+    }
+
+    let mut vars = env.into_vars();
+    if sorted {
+        vars.sort_by(|(a, _, _), (b, _, _)| a.cmp(b));
+    }
+
+    let mut writer = Writer::new(std::io::stdout().lock(), to);
+    let mut failed = 0;
+
+    for (key, value, source) in vars {
+        let at = if let Some((path, origin)) = &source {
+            format!("{}:{}: ", path.to_string_lossy(), origin.location().lineno())
+        } else {
+            String::new()
+        };
+
+        let (Some(key), Some(value)) = (key.to_str(), value.to_str()) else {
+            eprintln!("Error: {at}{key:?} is not valid Unicode and can't be converted");
+            failed += 1;
+            continue;
+        };
+
+        match writer.write_var(key, value) {
+            Ok(()) => {}
+            Err(err) if err.kind() == ErrorKind::Unrepresentable => {
+                eprintln!("Error: {at}{err}");
+                failed += 1;
+                continue;
+            }
+            Err(err) => return Err(err),
+        }
+
+        if source.as_ref().is_some_and(|(_, origin)| origin.is_substituted()) {
+            eprintln!("Warning: {at}{key:?}: value was substituted by the {from} dialect and is written as the literal {value:?}");
+        }
+
+        if to == Dialect::JavaProperties {
+            let name = KeyMapping::default().map(key);
+            if name != key {
+                eprintln!("Warning: {at}{key:?} is read back as {name:?} unless DOTENV_CONFIG_KEY_MAPPING=Keep");
+            }
+        }
+    }
+
+    writer.flush()?;
+
+    if failed > 0 {
+        return Err(Error::with_cause(
+            ErrorKind::Unrepresentable,
+            format!("{failed} variable(s) couldn't be converted to the {to} dialect")));
+    }
+
+    Ok(())
+}
+
 fn exec() -> punktum::Result<()> {
     let mut args = std::env::args_os();
     let mut replace = false;
@@ -204,15 +527,24 @@ fn exec() -> punktum::Result<()> {
     let mut sorted: bool = false;
     let mut export: bool = false;
     let mut binary: bool = false;
+    let mut systemd: bool = false;
     let mut debug: Option<bool> = None;
     let mut strict: Option<bool> = None;
     let mut override_env: Option<bool> = None;
     let mut encoding: Option<Encoding> = None;
+    let mut fallback_encoding: Option<Encoding> = None;
+    let mut output_encoding: Option<Encoding> = None;
     let mut dialect: Option<Dialect> = None;
+    let mut mode: Option<OsString> = None;
     let mut allow_list: Option<Vec<OsString>> = None;
     let mut deny_list: Option<Vec<OsString>> = None;
 
     args.next();
+    if std::env::args_os().nth(1).is_some_and(|arg| arg == "convert") {
+        args.next();
+        return convert(args);
+    }
+
     while let Some(arg) = args.next() {
         if arg == "--" {
             program = args.next();
@@ -227,8 +559,16 @@ fn exec() -> punktum::Result<()> {
             export = true;
         } else if arg == "--binary" {
             binary = true;
+        } else if arg == "--systemd" {
+            systemd = true;
         } else if arg == "-f" || arg == "--file" {
-            files.push(require_arg(&arg, &mut args)?);
+            files.push(FileArg::new(require_arg(&arg, &mut args)?));
+        } else if arg == "--file-dialect" {
+            let value = require_arg(&arg, &mut args)?;
+            last_file("--file-dialect", &mut files)?.dialect = Some(parse_dialect_option(&arg, &value)?);
+        } else if arg == "--file-encoding" {
+            let value = require_arg(&arg, &mut args)?;
+            last_file("--file-encoding", &mut files)?.encoding = Some(parse_encoding_option(&arg, &value)?);
         } else if arg == "--override" {
             let value = require_arg(&arg, &mut args)?;
             override_env = Some(parse_bool_option(&arg, &value)?);
@@ -241,6 +581,14 @@ fn exec() -> punktum::Result<()> {
         } else if arg == "--encoding" {
             let value = require_arg(&arg, &mut args)?;
             encoding = Some(parse_encoding_option(&arg, &value)?);
+        } else if arg == "--fallback-encoding" {
+            let value = require_arg(&arg, &mut args)?;
+            fallback_encoding = Some(parse_encoding_option(&arg, &value)?);
+        } else if arg == "--output-encoding" {
+            let value = require_arg(&arg, &mut args)?;
+            output_encoding = Some(parse_encoding_option(&arg, &value)?);
+        } else if arg == "--mode" {
+            mode = Some(require_arg(&arg, &mut args)?);
         } else if arg == "--dialect" {
             let value = require_arg(&arg, &mut args)?;
             dialect = Some(parse_dialect_option(&arg, &value)?);
@@ -263,7 +611,11 @@ fn exec() -> punktum::Result<()> {
             };
 
             if let Some(file) = str_arg.strip_prefix("--file=") {
-                files.push(OsStr::new(file).into());
+                files.push(FileArg::new(file));
+            } else if let Some(value) = str_arg.strip_prefix("--file-dialect=") {
+                last_file("--file-dialect", &mut files)?.dialect = Some(parse_dialect_option("--file-dialect", value)?);
+            } else if let Some(value) = str_arg.strip_prefix("--file-encoding=") {
+                last_file("--file-encoding", &mut files)?.encoding = Some(parse_encoding_option("--file-encoding", value)?);
             } else if let Some(value) = str_arg.strip_prefix("--override=") {
                 override_env = Some(parse_bool_option("--override", value)?);
             } else if let Some(value) = str_arg.strip_prefix("--strict=") {
@@ -272,6 +624,12 @@ fn exec() -> punktum::Result<()> {
                 debug = Some(parse_bool_option("--debug", value)?);
             } else if let Some(value) = str_arg.strip_prefix("--encoding=") {
                 encoding = Some(parse_encoding_option("--encoding", value)?);
+            } else if let Some(value) = str_arg.strip_prefix("--fallback-encoding=") {
+                fallback_encoding = Some(parse_encoding_option("--fallback-encoding", value)?);
+            } else if let Some(value) = str_arg.strip_prefix("--output-encoding=") {
+                output_encoding = Some(parse_encoding_option("--output-encoding", value)?);
+            } else if let Some(value) = str_arg.strip_prefix("--mode=") {
+                mode = Some(value.into());
             } else if let Some(value) = str_arg.strip_prefix("--dialect=") {
                 dialect = Some(parse_dialect_option("--dialect", value)?);
             } else if let Some(value) = str_arg.strip_prefix("--allow=") {
@@ -312,10 +670,18 @@ fn exec() -> punktum::Result<()> {
         builder = builder.encoding(encoding);
     }
 
+    if let Some(fallback_encoding) = fallback_encoding {
+        builder = builder.fallback_encoding(Some(fallback_encoding));
+    }
+
     if let Some(dialect) = dialect {
         builder = builder.dialect(dialect);
     }
 
+    if let Some(mode) = mode {
+        builder = builder.mode(Some(mode).filter(|mode| !mode.is_empty()));
+    }
+
     config_with_lists(&mut env, &allow_list, &deny_list, &files, &builder)?;
 
     if print_env {
@@ -324,25 +690,35 @@ fn exec() -> punktum::Result<()> {
             return Err(punktum::ErrorKind::IllegalArgument.into());
         }
 
+        if systemd && (binary || export) {
+            eprintln!("Error: Option --systemd is mutually exclusive with --binary and --export!");
+            return Err(punktum::ErrorKind::IllegalArgument.into());
+        }
+
         if program.is_some() {
             eprintln!("Error: When --print-env is specified no command is expected!");
             return Err(punktum::ErrorKind::IllegalArgument.into());
         }
 
-        let mut out = std::io::stdout().lock();
+        // without --output-encoding values are written as is, which on Unix
+        // includes values that aren't valid UTF-8
+        let mut out: Box<dyn Write> = if let Some(output_encoding) = output_encoding {
+            Box::new(EncodingWriter::new(std::io::stdout().lock(), output_encoding))
+        } else {
+            Box::new(std::io::stdout().lock())
+        };
 
         if sorted {
-            let mut sorted_env = vec![];
-
-            for (key, value) in &env {
-                let key = key.to_string_lossy();
-                let value = value.to_string_lossy();
-                sorted_env.push((key, value));
-            }
+            let mut sorted_env: Vec<_> = env.iter().collect();
             sorted_env.sort();
 
             if binary {
                 punktum::write_iter_binary(&mut out, sorted_env.into_iter())?;
+            } else if systemd {
+                let mut writer = Writer::new(&mut out, Dialect::Systemd);
+                for (key, value) in sorted_env {
+                    writer.write_var_os(key, value)?;
+                }
             } else {
                 for (key, value) in sorted_env {
                     if export {
@@ -353,14 +729,15 @@ fn exec() -> punktum::Result<()> {
             }
         } else if binary {
             for (key, value) in env {
-                let key = key.to_string_lossy();
-                let value = value.to_string_lossy();
                 punktum::write_var_binary(&mut out, key, value)?;
             }
+        } else if systemd {
+            let mut writer = Writer::new(&mut out, Dialect::Systemd);
+            for (key, value) in env {
+                writer.write_var_os(key, value)?;
+            }
         } else {
             for (key, value) in env {
-                let key = key.to_string_lossy();
-                let value = value.to_string_lossy();
                 if export {
                     write!(out.by_ref(), "export ")?;
                 }
@@ -380,11 +757,21 @@ fn exec() -> punktum::Result<()> {
         return Err(punktum::ErrorKind::IllegalArgument.into());
     }
 
+    if output_encoding.is_some() {
+        eprintln!("Error: Option --output-encoding is only to be used in combination with --print-env");
+        return Err(punktum::ErrorKind::IllegalArgument.into());
+    }
+
     if binary {
         eprintln!("Error: Option --binary is only to be used in combination with --print-env");
         return Err(punktum::ErrorKind::IllegalArgument.into());
     }
 
+    if systemd {
+        eprintln!("Error: Option --systemd is only to be used in combination with --print-env");
+        return Err(punktum::ErrorKind::IllegalArgument.into());
+    }
+
     let Some(program) = program else {
         return Err(punktum::ErrorKind::NotEnoughArguments.into())
     };
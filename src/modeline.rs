@@ -0,0 +1,112 @@
+use std::{ffi::OsStr, io::BufRead, path::Path};
+
+use crate::{diagnostic::Severity, options::{IllegalOption, OptionType}, Diagnostic, DiagnosticCode, Dialect, Encoding, Error, ErrorKind, Options, Result};
+
+/// Per-file settings from a comment in the first line of a file:
+///
+/// ```plain
+/// # punktum: dialect=ruby-dotenv encoding=latin1
+/// ```
+///
+/// They override [`Options::dialect`] and [`Options::encoding`] for that file.
+/// The line stays a part of the file, so it has to be a comment in the chosen
+/// dialect.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct Modeline {
+    pub dialect: Option<Dialect>,
+    pub encoding: Option<Encoding>,
+}
+
+impl Modeline {
+    /// Returns `None` if `line` isn't a modeline. A comment that starts with
+    /// `punktum:` but isn't followed by only `name=value` settings, like
+    /// `# punktum: config for dev`, is just a comment.
+    pub fn parse(line: &str) -> Option<Result<Self>> {
+        let settings = line.trim().strip_prefix('#')?.trim_start().strip_prefix("punktum:")?;
+        let settings = settings.split_ascii_whitespace().
+            map(|setting| setting.split_once('=')).
+            collect::<Option<Vec<_>>>()?;
+        let mut modeline = Modeline::default();
+
+        for (name, value) in settings {
+            let value = OsStr::new(value);
+            match name {
+                "dialect" => match Dialect::try_from(value) {
+                    Ok(dialect) => modeline.dialect = Some(dialect),
+                    Err(_) => return Some(Err(illegal_option(name, value, OptionType::Dialect))),
+                },
+                "encoding" => match Encoding::try_from(value) {
+                    Ok(encoding) => modeline.encoding = Some(encoding),
+                    Err(_) => return Some(Err(illegal_option(name, value, OptionType::Encoding))),
+                },
+                _ => return Some(Err(Error::with_cause(
+                    ErrorKind::OptionsParseError,
+                    format!("unknown modeline setting: {name:?}")))),
+            }
+        }
+
+        Some(Ok(modeline))
+    }
+
+    /// Apply the settings to `options`.
+    #[inline]
+    pub fn apply<P>(&self, options: &mut Options<P>) {
+        if let Some(dialect) = self.dialect {
+            options.dialect = dialect;
+        }
+
+        if let Some(encoding) = self.encoding {
+            options.encoding = encoding;
+        }
+    }
+}
+
+#[inline]
+fn illegal_option(name: &str, value: &OsStr, option_type: OptionType) -> Error {
+    Error::with_cause(
+        ErrorKind::OptionsParseError,
+        IllegalOption::new(name.into(), value.into(), option_type))
+}
+
+/// Reads the first line of `reader` and applies it to `options` if it is a
+/// modeline. Returns the bytes that were read, since they still have to be
+/// parsed.
+pub(crate) fn read_modeline<P>(reader: &mut dyn BufRead, options: &mut Options<P>) -> Result<Vec<u8>>
+where P: AsRef<Path> {
+    let mut first = Vec::new();
+    reader.read_until(b'\n', &mut first)?;
+
+    // The modeline is ASCII, which is enough to find it in all supported
+    // encodings if the null bytes of UTF-16 and UTF-32 and the BOM are
+    // ignored.
+    let line: String = String::from_utf8_lossy(&first).chars().
+        filter(|ch| !matches!(ch, '\0' | '\u{FEFF}' | '\u{FFFD}')).
+        collect();
+
+    let Some(modeline) = Modeline::parse(&line) else {
+        return Ok(first);
+    };
+
+    match modeline {
+        Ok(modeline) => {
+            if options.diagnostics_enabled() {
+                options.report(Diagnostic::new(DiagnosticCode::Modeline,
+                    format!("modeline applied: {}", line.trim())).
+                    at_line(1));
+            }
+            modeline.apply(options);
+        }
+        Err(error) => {
+            if options.diagnostics_enabled() {
+                options.report(Diagnostic::new(DiagnosticCode::Modeline, error.to_string()).
+                    with_severity(Severity::Error).
+                    at_line(1));
+            }
+            if options.strict {
+                return Err(error);
+            }
+        }
+    }
+
+    Ok(first)
+}
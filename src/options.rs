@@ -1,6 +1,6 @@
-use std::{borrow::Cow, collections::HashMap, ffi::{OsStr, OsString}, io::BufRead, path::Path};
+use std::{borrow::Cow, collections::HashMap, ffi::{OsStr, OsString}, io::{BufRead, Read}, path::{Path, PathBuf}};
 
-use crate::{encoding::Encoding, env::{GetEnv, SystemEnv, SYSTEM_ENV}, Dialect, Env, Result, DEBUG_PREFIX};
+use crate::{command::CommandPolicy, key_mapping::KeyMapping, diagnostic::{Diagnostic, DiagnosticCode, DiagnosticSink, Diagnostics, Severity}, encoding::Encoding, search::Search, env::{GetEnv, SystemEnv, SYSTEM_ENV}, parse::{parse_with_parent, Entries, Origin}, codec::DecodeError, error::SourceLocation, DecodingReader, Dialect, Env, Error, ErrorKind, Result, DEBUG_PREFIX};
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Options<P=&'static str> {
@@ -15,9 +15,35 @@ pub struct Options<P=&'static str> {
 
     pub encoding: Encoding,
 
+    /// What [`Encoding::Auto`] uses if a file without a BOM isn't valid
+    /// UTF-8.
+    pub fallback_encoding: Option<Encoding>,
+
     pub dialect: Dialect,
 
     pub path: P,
+
+    /// Load the cascade of files for this mode (e.g. `development`) based on
+    /// `path` instead of just `path`, see [`crate::cascade`].
+    pub mode: Option<OsString>,
+
+    /// Where to look for a relative `path`.
+    pub search: Search,
+
+    /// Directory that files included with `include` in the Punktum dialect
+    /// have to be in. Any directory if `None`.
+    pub include_root: Option<PathBuf>,
+
+    /// Receives problems found while reading the file, in addition to them
+    /// being logged if `debug` is set.
+    pub diagnostics: Diagnostics,
+
+    /// What to do with `$(command)` in dialects that support it.
+    pub command_policy: CommandPolicy,
+
+    /// How dialects like [`Dialect::JavaProperties`] turn their keys into
+    /// variable names.
+    pub key_mapping: KeyMapping,
 }
 
 pub const DEFAULT_PATH: &str = ".env";
@@ -33,8 +59,15 @@ impl Default for Options {
             strict: DEFAULT_STRICT,
             debug: DEFAULT_DEBUG,
             encoding: Encoding::default(),
+            fallback_encoding: None,
             dialect: Dialect::default(),
             path: DEFAULT_PATH,
+            mode: None,
+            search: Search::default(),
+            include_root: None,
+            diagnostics: Diagnostics::none(),
+            command_policy: CommandPolicy::default(),
+            key_mapping: KeyMapping::default(),
         }
     }
 }
@@ -45,10 +78,15 @@ impl<'a> Options<Cow<'a, OsStr>> {
         let strict = env.get_strict()?;
         let debug = env.get_debug()?;
         let encoding = env.get_encoding()?;
+        let fallback_encoding = env.get_fallback_encoding()?;
         let dialect = env.get_dialect()?;
+        let key_mapping = env.get_key_mapping()?;
         let path = env.get_config_path();
+        let mode = env.get_mode();
+        let search = env.get_search()?;
+        let include_root = env.get_include_root();
 
-        Ok(Self { override_env, strict, debug, encoding, dialect, path })
+        Ok(Self { override_env, strict, debug, encoding, fallback_encoding, dialect, path, mode, search, include_root, diagnostics: Diagnostics::none(), command_policy: CommandPolicy::default(), key_mapping })
     }
 
     #[inline]
@@ -65,8 +103,15 @@ impl<P> Options<P> {
             strict: DEFAULT_STRICT,
             debug: DEFAULT_DEBUG,
             encoding: Encoding::default(),
+            fallback_encoding: None,
             dialect: Dialect::default(),
             path,
+            mode: None,
+            search: Search::default(),
+            include_root: None,
+            diagnostics: Diagnostics::none(),
+            command_policy: CommandPolicy::default(),
+            key_mapping: KeyMapping::default(),
         }
     }
 }
@@ -126,24 +171,126 @@ where P: AsRef<Path> {
         Ok(env)
     }
 
+    /// See [`crate::parse()`].
+    #[inline]
+    pub fn parse<'a>(&self, reader: impl BufRead + 'a) -> Entries<'a> {
+        parse_with_parent(reader, &SYSTEM_ENV, self)
+    }
+
+    #[inline]
+    pub fn parse_with_parent<'a>(&self, reader: impl BufRead + 'a, parent: &'a impl GetEnv) -> Entries<'a> {
+        parse_with_parent(reader, parent, self)
+    }
+
+    /// Whether anyone is interested in diagnostics, so building the message
+    /// is worth it.
+    #[inline]
+    pub fn diagnostics_enabled(&self) -> bool {
+        self.debug || self.diagnostics.is_some()
+    }
+
+    /// Log `diagnostic` if `debug` is set and pass it to the diagnostics sink.
+    /// Errors the parser recovers from in non-strict mode are reported as
+    /// warnings.
+    pub fn report(&self, diagnostic: Diagnostic) {
+        let mut diagnostic = diagnostic.with_path(self.path.as_ref());
+        if !self.strict && diagnostic.severity() == Severity::Error {
+            diagnostic = diagnostic.with_severity(Severity::Warning);
+        }
+
+        if self.debug {
+            eprintln!("{DEBUG_PREFIX}{diagnostic}");
+        }
+
+        self.diagnostics.report(diagnostic);
+    }
+
+    /// Set `key` in `env` unless it is already defined and `override_env`
+    /// isn't set. For use in [`crate::DialectParser`] implementations.
     #[inline]
-    pub(crate) fn set_var(&self, env: &mut dyn Env, key: &OsStr, value: &OsStr) {
+    pub fn set_var(&self, env: &mut dyn Env, key: &OsStr, value: &OsStr, origin: Origin) {
         if self.override_env {
-            env.set(key, value);
+            env.set_from(key, value, &origin);
         } else if env.get(key).is_some() {
-            if self.debug {
-                eprintln!("{DEBUG_PREFIX}{key:?} is already defined and was NOT overwritten");
+            if self.diagnostics_enabled() {
+                let location = origin.location();
+                self.report(Diagnostic::new(DiagnosticCode::NotOverwritten,
+                    format!("{key:?} is already defined and was NOT overwritten")).
+                    at(location.lineno(), location.column()));
             }
         } else {
-            env.set(key, value);
+            env.set_from(key, value, &origin);
+        }
+    }
+
+    /// Read all of `reader` in `encoding` into `buf`. For use in
+    /// [`crate::DialectParser`] implementations that parse the whole file at
+    /// once.
+    ///
+    /// In strict mode the first line that can't be decoded is an error. In
+    /// non-strict mode such lines are decoded lossily and reported, so one
+    /// broken line doesn't lose the whole file.
+    pub fn read_to_string(&self, reader: &mut dyn BufRead, buf: &mut String) -> Result<()> {
+        let mut reader = DecodingReader::new(reader, self.encoding).lossy(!self.strict);
+
+        if let Err(err) = reader.read_to_string(buf) {
+            let location = DecodeError::from_io(&err).map(|error| SourceLocation::new(error.lineno(), error.column()));
+            if self.diagnostics_enabled() {
+                let mut diagnostic = Diagnostic::new(DiagnosticCode::from(&err), err.to_string());
+                if let Some(location) = location {
+                    diagnostic = diagnostic.at(location.lineno(), location.column());
+                }
+                self.report(diagnostic);
+            }
+            return Err(match location {
+                Some(location) => Error::new(ErrorKind::IOError, err, location),
+                None => Error::with_cause(ErrorKind::IOError, err),
+            });
         }
+
+        for error in reader.take_errors() {
+            self.report_decode_error(&error);
+        }
+
+        Ok(())
+    }
+
+    /// The value of the variable `key` that is substituted at `location`, for
+    /// [`crate::DialectParser`] implementations that build their values as
+    /// strings.
+    ///
+    /// A value that isn't valid Unicode is an error in strict mode. In
+    /// non-strict mode it is reported and replaced lossily.
+    pub fn var_value_str<'v>(&self, key: &str, value: &'v OsStr, location: SourceLocation) -> Result<Cow<'v, str>> {
+        if let Some(value) = value.to_str() {
+            return Ok(Cow::Borrowed(value));
+        }
+
+        if self.diagnostics_enabled() {
+            self.report(Diagnostic::new(DiagnosticCode::EncodingError, format!("variable ${key} is not valid Unicode")).
+                at(location.lineno(), location.column()));
+        }
+
+        if self.strict {
+            return Err(Error::substitution_error(location.lineno(), location.column()));
+        }
+
+        Ok(value.to_string_lossy())
     }
 
     #[inline]
-    pub(crate) fn set_var_cut_null(&self, env: &mut dyn Env, key: &str, value: &str) {
+    pub(crate) fn report_decode_error(&self, error: &DecodeError) {
+        if self.diagnostics_enabled() {
+            self.report(Diagnostic::new(DiagnosticCode::EncodingError, error.to_string()).
+                at(error.lineno(), error.column()));
+        }
+    }
+
+    #[inline]
+    pub(crate) fn set_var_cut_null(&self, env: &mut dyn Env, key: &str, value: &str, origin: Origin) {
         let key = key.split('\0').next().unwrap();
         let value = value.split('\0').next().unwrap();
-        self.set_var(env, key.as_ref(), value.as_ref());
+        self.set_var(env, key.as_ref(), value.as_ref(), origin);
     }
 }
 
@@ -153,6 +300,8 @@ pub enum OptionType {
     Encoding,
     Dialect,
     CommaList,
+    KeyMapping,
+    Search,
 }
 
 impl std::fmt::Display for OptionType {
@@ -264,12 +413,56 @@ impl<P> Builder<P> {
         self
     }
 
+    #[inline]
+    pub fn fallback_encoding(mut self, value: Option<Encoding>) -> Self {
+        self.options.fallback_encoding = value;
+        self
+    }
+
     #[inline]
     pub fn dialect(mut self, value: Dialect) -> Self {
         self.options.dialect = value;
         self
     }
 
+    #[inline]
+    pub fn mode(mut self, value: Option<OsString>) -> Self {
+        self.options.mode = value;
+        self
+    }
+
+    #[inline]
+    pub fn search(mut self, value: Search) -> Self {
+        self.options.search = value;
+        self
+    }
+
+    #[inline]
+    pub fn include_root(mut self, value: Option<PathBuf>) -> Self {
+        self.options.include_root = value;
+        self
+    }
+
+    /// Pass all diagnostics to `sink`. To collect them use e.g. an
+    /// `Arc<Mutex<Vec<Diagnostic>>>` and keep a clone of it.
+    #[inline]
+    pub fn diagnostics(mut self, sink: impl DiagnosticSink + 'static) -> Self {
+        self.options.diagnostics = Diagnostics::new(sink);
+        self
+    }
+
+    #[inline]
+    pub fn command_policy(mut self, value: CommandPolicy) -> Self {
+        self.options.command_policy = value;
+        self
+    }
+
+    #[inline]
+    pub fn key_mapping(mut self, value: KeyMapping) -> Self {
+        self.options.key_mapping = value;
+        self
+    }
+
     #[inline]
     pub fn options(&self) -> &Options<P> {
         &self.options
@@ -296,8 +489,15 @@ where P: AsRef<Path> {
                 debug: self.options.debug,
                 strict: self.options.strict,
                 encoding: self.options.encoding,
+                fallback_encoding: self.options.fallback_encoding,
                 dialect: self.options.dialect,
                 path: value,
+                mode: self.options.mode.clone(),
+                search: self.options.search,
+                include_root: self.options.include_root.clone(),
+                diagnostics: self.options.diagnostics.clone(),
+                command_policy: self.options.command_policy.clone(),
+                key_mapping: self.options.key_mapping.clone(),
             }
         }
     }
@@ -351,6 +551,16 @@ where P: AsRef<Path> {
     pub fn config_new(&self) -> Result<HashMap<OsString, OsString>> {
         self.options.config_new()
     }
+
+    #[inline]
+    pub fn parse<'a>(&self, reader: impl BufRead + 'a) -> Entries<'a> {
+        self.options.parse(reader)
+    }
+
+    #[inline]
+    pub fn parse_with_parent<'a>(&self, reader: impl BufRead + 'a, parent: &'a impl GetEnv) -> Entries<'a> {
+        self.options.parse_with_parent(reader, parent)
+    }
 }
 
 impl<P> From<Options<P>> for Builder<P>
@@ -0,0 +1,368 @@
+use std::{borrow::Cow, collections::{HashMap, VecDeque}, ffi::{OsStr, OsString}, io::{BufRead, Cursor, Read}, path::{Path, PathBuf}};
+
+use crate::{dialects::{binary, punktum}, env::{GetEnv, SYSTEM_ENV}, error::SourceLocation, DecodingReader, Dialect, Encoding, Env, Options, Result};
+
+/// Where a value came from in the source file.
+///
+/// Passed by the dialect parsers to [`Env::set_from()`].
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Origin {
+    location: SourceLocation,
+    quoted: bool,
+    substituted: bool,
+}
+
+impl Origin {
+    /// Line and column (1-based, counting bytes) of the key.
+    #[inline]
+    pub fn new(lineno: usize, column: usize) -> Self {
+        Self {
+            location: SourceLocation::new(lineno, column),
+            quoted: false,
+            substituted: false,
+        }
+    }
+
+    #[inline]
+    pub fn quoted(mut self, quoted: bool) -> Self {
+        self.quoted = quoted;
+        self
+    }
+
+    #[inline]
+    pub fn substituted(mut self, substituted: bool) -> Self {
+        self.substituted = substituted;
+        self
+    }
+
+    #[inline]
+    pub fn location(&self) -> SourceLocation {
+        self.location
+    }
+
+    /// Whether (any part of) the value was written in quotes.
+    #[inline]
+    pub fn is_quoted(&self) -> bool {
+        self.quoted
+    }
+
+    /// Whether the value contains variable substitutions or was imported from
+    /// the parent environment.
+    #[inline]
+    pub fn is_substituted(&self) -> bool {
+        self.substituted
+    }
+}
+
+/// Turns byte offsets into [`Origin`]s for parsers that work on the whole
+/// file at once. Counts lines incrementally, so offsets should be increasing.
+#[derive(Debug)]
+pub(crate) struct LineTracker {
+    offset: usize,
+    lineno: usize,
+    line_start: usize,
+}
+
+impl LineTracker {
+    #[inline]
+    pub(crate) fn new() -> Self {
+        Self { offset: 0, lineno: 1, line_start: 0 }
+    }
+
+    pub(crate) fn origin_at(&mut self, src: &str, offset: usize) -> Origin {
+        if offset < self.offset {
+            *self = Self::new();
+        }
+
+        for (index, _) in src[self.offset..offset].match_indices('\n') {
+            self.lineno += 1;
+            self.line_start = self.offset + index + 1;
+        }
+        self.offset = offset;
+
+        Origin::new(self.lineno, offset - self.line_start + 1)
+    }
+
+    /// Origin of `part`, which should be a sub-slice of `src`. Anything else
+    /// (like an empty string literal) is placed at the end of `src`.
+    #[inline]
+    pub(crate) fn origin_of(&mut self, src: &str, part: &str) -> Origin {
+        let offset = (part.as_ptr() as usize).wrapping_sub(src.as_ptr() as usize);
+        self.origin_at(src, offset.min(src.len()))
+    }
+}
+
+/// A variable definition as yielded by [`parse()`].
+#[derive(Debug, PartialEq, Clone)]
+pub struct Entry {
+    key: OsString,
+    value: OsString,
+    origin: Origin,
+}
+
+impl Entry {
+    #[inline]
+    pub fn new(key: impl Into<OsString>, value: impl Into<OsString>, origin: Origin) -> Self {
+        Self {
+            key: key.into(),
+            value: value.into(),
+            origin,
+        }
+    }
+
+    #[inline]
+    pub fn key(&self) -> &OsStr {
+        &self.key
+    }
+
+    #[inline]
+    pub fn value(&self) -> &OsStr {
+        &self.value
+    }
+
+    #[inline]
+    pub fn origin(&self) -> &Origin {
+        &self.origin
+    }
+
+    #[inline]
+    pub fn location(&self) -> SourceLocation {
+        self.origin.location
+    }
+
+    #[inline]
+    pub fn is_quoted(&self) -> bool {
+        self.origin.quoted
+    }
+
+    #[inline]
+    pub fn is_substituted(&self) -> bool {
+        self.origin.substituted
+    }
+
+    #[inline]
+    pub fn into_key_value(self) -> (OsString, OsString) {
+        (self.key, self.value)
+    }
+}
+
+/// The reader a parser reads from: the bytes read for the modeline and
+/// encoding detection followed by the rest of the input.
+type Source<'a> = std::io::Chain<Cursor<Vec<u8>>, Box<dyn BufRead + 'a>>;
+
+enum Input<'a> {
+    Plain(Source<'a>),
+    Decoding(DecodingReader<Source<'a>>),
+}
+
+impl Input<'_> {
+    #[inline]
+    fn reader(&mut self) -> &mut dyn BufRead {
+        match self {
+            Input::Plain(reader) => reader,
+            Input::Decoding(reader) => reader,
+        }
+    }
+}
+
+/// How far a dialect can be parsed at a time.
+enum Step {
+    Punktum(punktum::Statements),
+    Binary(binary::Records),
+    /// Dialects that read the whole file before parsing it.
+    Whole,
+}
+
+enum State<'a> {
+    Start(Box<dyn BufRead + 'a>),
+    Parsing(Input<'a>, Step),
+    Done,
+}
+
+/// Iterator over the entries of a file, see [`parse()`].
+pub struct Entries<'a> {
+    state: State<'a>,
+    parent: &'a dyn GetEnv,
+    options: Options<PathBuf>,
+    env: RecordingEnv,
+    error: Option<crate::Error>,
+}
+
+impl std::fmt::Debug for Entries<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Entries").
+            field("options", &self.options).
+            field("entries", &self.env.entries).
+            field("error", &self.error).
+            finish_non_exhaustive()
+    }
+}
+
+impl Entries<'_> {
+    /// Parse the next step of the input, which records zero or more entries.
+    fn advance(&mut self) -> Result<()> {
+        match std::mem::replace(&mut self.state, State::Done) {
+            State::Start(mut reader) => {
+                let head = crate::read_head(&mut reader, &mut self.options)?;
+                let reader = Read::chain(Cursor::new(head), reader);
+
+                // Parsers only get to see UTF-8. Binary ignores the encoding.
+                let input = if self.options.encoding == Encoding::UTF8 || self.options.dialect == Dialect::Binary {
+                    Input::Plain(reader)
+                } else {
+                    let reader = DecodingReader::new(reader, self.options.encoding).lossy(!self.options.strict);
+                    self.options.encoding = Encoding::UTF8;
+                    Input::Decoding(reader)
+                };
+
+                let step = match self.options.dialect {
+                    Dialect::Punktum => Step::Punktum(punktum::Statements::default()),
+                    Dialect::Binary  => Step::Binary(binary::Records::default()),
+                    _ => Step::Whole,
+                };
+
+                self.state = State::Parsing(input, step);
+            },
+            State::Parsing(mut input, mut step) => {
+                let options = Options {
+                    override_env:      self.options.override_env,
+                    strict:            self.options.strict,
+                    debug:             self.options.debug,
+                    encoding:          self.options.encoding,
+                    fallback_encoding: self.options.fallback_encoding,
+                    dialect:           self.options.dialect,
+                    path:              self.options.path.as_path(),
+                    mode:              None,
+                    search:            self.options.search,
+                    include_root:      self.options.include_root.clone(),
+                    diagnostics:       self.options.diagnostics.clone(),
+                    command_policy:    self.options.command_policy.clone(),
+                    key_mapping:       self.options.key_mapping.clone(),
+                };
+
+                let result = match &mut step {
+                    Step::Punktum(statements) => statements.parse_next(input.reader(), &mut self.env, self.parent, &options),
+                    Step::Binary(records) => records.parse_next(input.reader(), &mut self.env, &options),
+                    Step::Whole => options.dialect.parser().parse(input.reader(), &mut self.env, self.parent, &options).map(|()| false),
+                };
+
+                if let Input::Decoding(reader) = &mut input {
+                    for error in reader.take_errors() {
+                        options.report_decode_error(&error);
+                    }
+                }
+
+                if result? {
+                    self.state = State::Parsing(input, step);
+                }
+            },
+            State::Done => {},
+        }
+
+        Ok(())
+    }
+}
+
+impl Iterator for Entries<'_> {
+    type Item = Result<Entry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(entry) = self.env.entries.pop_front() {
+                return Some(Ok(entry));
+            }
+
+            if let State::Done = self.state {
+                return self.error.take().map(Err);
+            }
+
+            if let Err(err) = self.advance() {
+                self.error = Some(err);
+            }
+        }
+    }
+}
+
+impl std::iter::FusedIterator for Entries<'_> {}
+
+/// Records every definition while still resolving substitutions against the
+/// values defined so far.
+#[derive(Default)]
+struct RecordingEnv {
+    vars: HashMap<OsString, OsString>,
+    entries: VecDeque<Entry>,
+}
+
+impl GetEnv for RecordingEnv {
+    #[inline]
+    fn get<'a>(&'a self, key: &OsStr) -> Option<Cow<'a, OsStr>> {
+        self.vars.get(key).map(Cow::from)
+    }
+}
+
+impl Env for RecordingEnv {
+    #[inline]
+    fn set(&mut self, key: &OsStr, value: &OsStr) {
+        self.vars.insert(key.to_owned(), value.to_owned());
+    }
+
+    #[inline]
+    fn set_from(&mut self, key: &OsStr, value: &OsStr, origin: &Origin) {
+        self.set(key, value);
+        self.entries.push_back(Entry::new(key, value, *origin));
+    }
+
+    #[inline]
+    fn remove(&mut self, key: &OsStr) {
+        self.vars.remove(key);
+    }
+
+    #[inline]
+    fn as_get_env(&self) -> &dyn GetEnv {
+        self
+    }
+}
+
+/// Parse `reader` with the given options without applying anything to an
+/// environment. Variables are substituted and imported from the system
+/// environment.
+///
+/// Every definition is yielded in file order, even if a key is defined more
+/// than once. [`Dialect::Punktum`] and [`Dialect::Binary`] are parsed as the
+/// entries are pulled, the other dialects read and parse the whole input
+/// first, just like the implementations they emulate. In strict mode a syntax
+/// error is yielded after the entries that preceded it.
+#[inline]
+pub fn parse<'a, P>(reader: impl BufRead + 'a, options: &Options<P>) -> Entries<'a>
+where P: AsRef<Path> {
+    parse_with_parent(reader, &SYSTEM_ENV, options)
+}
+
+/// Same as [`parse()`], but variables are substituted and imported from
+/// `parent`.
+pub fn parse_with_parent<'a, P>(reader: impl BufRead + 'a, parent: &'a impl GetEnv, options: &Options<P>) -> Entries<'a>
+where P: AsRef<Path> {
+    let options = Options {
+        override_env:      true,
+        strict:            options.strict,
+        debug:             options.debug,
+        encoding:          options.encoding,
+        fallback_encoding: options.fallback_encoding,
+        dialect:           options.dialect,
+        path:              options.path.as_ref().to_owned(),
+        mode:              options.mode.clone(),
+        search:            options.search,
+        include_root:      options.include_root.clone(),
+        diagnostics:       options.diagnostics.clone(),
+        command_policy:    options.command_policy.clone(),
+        key_mapping:       options.key_mapping.clone(),
+    };
+
+    Entries {
+        state: State::Start(Box::new(reader)),
+        parent,
+        options,
+        env: RecordingEnv::default(),
+        error: None,
+    }
+}
@@ -0,0 +1,78 @@
+use std::{ffi::OsStr, path::{Path, PathBuf}};
+
+use crate::cascade;
+
+/// Entries that mark the root of a repository. [`Search::Upward`] doesn't
+/// look beyond a directory that contains one of them.
+pub const ROOT_MARKERS: &[&str] = &[".git", ".hg", ".jj", ".svn"];
+
+/// Where to look for a relative [`crate::Options::path`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Search {
+    /// Relative to the current directory only.
+    #[default]
+    Off,
+
+    /// In the current directory and then in each of its ancestors, up to the
+    /// first one with a [`ROOT_MARKERS`] entry or the filesystem root. Useful
+    /// when tests or binaries are run from a sub-crate of a workspace.
+    Upward,
+}
+
+impl std::fmt::Display for Search {
+    #[inline]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&self, f)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct IllegalSearch();
+
+impl std::fmt::Display for IllegalSearch {
+    #[inline]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        "IllegalSearch".fmt(f)
+    }
+}
+
+impl std::error::Error for IllegalSearch {}
+
+impl TryFrom<&OsStr> for Search {
+    type Error = IllegalSearch;
+
+    fn try_from(value: &OsStr) -> std::result::Result<Self, Self::Error> {
+        if value.is_empty() || value.eq_ignore_ascii_case("off") {
+            Ok(Search::Off)
+        } else if value.eq_ignore_ascii_case("upward") {
+            Ok(Search::Upward)
+        } else {
+            Err(IllegalSearch())
+        }
+    }
+}
+
+/// Search `path` in `dir` and its ancestors as described for
+/// [`Search::Upward`]. With a `mode` the first directory with any file of its
+/// [cascade](crate::cascade) wins and `path` as joined to that directory is
+/// returned.
+pub fn find_upward(dir: &Path, path: &Path, mode: Option<&OsStr>) -> Option<PathBuf> {
+    for dir in dir.ancestors() {
+        let candidate = dir.join(path);
+        let found = if let Some(mode) = mode {
+            cascade::paths(&candidate, mode).iter().any(|path| path.is_file())
+        } else {
+            candidate.is_file()
+        };
+
+        if found {
+            return Some(candidate);
+        }
+
+        if ROOT_MARKERS.iter().any(|marker| dir.join(marker).exists()) {
+            break;
+        }
+    }
+
+    None
+}
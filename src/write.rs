@@ -0,0 +1,555 @@
+use std::{ffi::OsStr, io::Write};
+
+use crate::{dialects::dotenv_expand::match_substitution, Dialect, Error, ErrorKind, Result};
+
+/// Serializes variables so that the parser of a chosen [`Dialect`] reads them
+/// back identically.
+///
+/// Values are written unquoted if they only consist of harmless characters
+/// and otherwise with the least involved quoting the dialect offers for them.
+/// Writing a key or value that can't be represented in the dialect (like a
+/// value with a carriage return for [`Dialect::NodeJS`]) fails with
+/// [`ErrorKind::Unrepresentable`] and writes nothing for that variable.
+///
+/// Keys of [`Dialect::JavaProperties`] are written as is, read them back with
+/// [`crate::KeyMapping::Keep`] to get the same names.
+#[derive(Debug)]
+pub struct Writer<W: Write> {
+    writer: W,
+    dialect: Dialect,
+    buf: String,
+}
+
+impl<W: Write> Writer<W> {
+    #[inline]
+    pub fn new(writer: W, dialect: Dialect) -> Self {
+        Self { writer, dialect, buf: String::new() }
+    }
+
+    #[inline]
+    pub fn dialect(&self) -> Dialect {
+        self.dialect
+    }
+
+    #[inline]
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    pub fn write_var(&mut self, key: impl AsRef<str>, value: impl AsRef<str>) -> Result<()> {
+        let key = key.as_ref();
+        let value = value.as_ref();
+
+        self.buf.clear();
+        if let Err(reason) = encode(self.dialect, key, value, &mut self.buf) {
+            return Err(Error::with_cause(
+                ErrorKind::Unrepresentable,
+                format!("{key:?} can't be written in the {} dialect: {reason}", self.dialect)));
+        }
+
+        self.writer.write_all(self.buf.as_bytes())?;
+
+        Ok(())
+    }
+
+    /// Like [`Self::write_var()`], but a key or value that isn't valid
+    /// Unicode fails with [`ErrorKind::Unrepresentable`].
+    pub fn write_var_os(&mut self, key: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> Result<()> {
+        let key = key.as_ref();
+        let (Some(key_str), Some(value)) = (key.to_str(), value.as_ref().to_str()) else {
+            return Err(Error::with_cause(
+                ErrorKind::Unrepresentable,
+                format!("{key:?} can't be written in the {} dialect: not valid Unicode", self.dialect)));
+        };
+        self.write_var(key_str, value)
+    }
+
+    pub fn write_iter(&mut self, iter: impl Iterator<Item=(impl AsRef<str>, impl AsRef<str>)>) -> Result<()> {
+        for (key, value) in iter {
+            self.write_var(key, value)?;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+type Encoded = std::result::Result<(), &'static str>;
+
+fn encode(dialect: Dialect, key: &str, value: &str, buf: &mut String) -> Encoded {
+    if key.contains('\0') {
+        return Err("key contains a null byte");
+    }
+
+    if value.contains('\0') {
+        return Err("value contains a null byte");
+    }
+
+    if !is_valid_key(dialect, key) {
+        return Err("illegal variable name");
+    }
+
+    match dialect {
+        Dialect::Binary => {
+            buf.push_str(key);
+            buf.push('=');
+            buf.push_str(value);
+            buf.push('\0');
+            return Ok(());
+        }
+        Dialect::JavaProperties => {
+            push_properties(buf, key, true);
+            buf.push('=');
+            push_properties(buf, value, false);
+            buf.push('\n');
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    buf.push_str(key);
+    buf.push('=');
+
+    match dialect {
+        Dialect::Punktum                 => encode_punktum(value, buf)?,
+        Dialect::NodeJS   |
+        Dialect::NodeJS20 |
+        Dialect::NodeJS24                => encode_nodejs(value, buf)?,
+        Dialect::JavaScriptDotenv        => encode_javascript_dotenv(value, buf)?,
+        Dialect::DotenvExpand            => encode_javascript_dotenv(&escape_dotenv_expand(value), buf)?,
+        Dialect::Dotenvx => {
+            // commands are found even after a backslash
+            if value.contains("$(") {
+                return Err("value contains \"$(\", which is always a command substitution");
+            }
+            encode_javascript_dotenv(&escape_dotenv_expand(value), buf)?
+        }
+        Dialect::PythonDotenv            => encode_python_dotenv(value, buf)?,
+        Dialect::PythonDotenvCLI         => encode_python_dotenv_cli(value, buf)?,
+        Dialect::ComposeGo               => encode_composego(value, buf),
+        Dialect::ComposeRaw |
+        Dialect::DockerCLI => {
+            if value.contains(['\n', '\r']) {
+                return Err("value contains a line break");
+            }
+            buf.push_str(value);
+        }
+        Dialect::GoDotenv                => encode_go_dotenv(value, buf)?,
+        Dialect::RubyDotenv              => encode_ruby_dotenv(value, buf)?,
+        Dialect::PhpDotenv               => encode_php_dotenv(value, buf),
+        Dialect::JavaDotenv              => encode_java_dotenv(value, buf)?,
+        Dialect::Dotenvy                 => encode_dotenvy(value, buf),
+        Dialect::Systemd                 => encode_systemd(value, buf),
+        Dialect::PosixShell              => encode_posix_shell(value, buf),
+        Dialect::Binary | Dialect::JavaProperties => unreachable!(),
+        Dialect::Custom(_) => return Err("custom dialects have no writer"),
+        Dialect::Auto => return Err("Auto only detects the dialect of files that are read"),
+    }
+
+    buf.push('\n');
+
+    Ok(())
+}
+
+fn is_valid_key(dialect: Dialect, key: &str) -> bool {
+    let extra = match dialect {
+        Dialect::Binary => return !key.is_empty() && !key.contains('='),
+        Dialect::ComposeRaw | Dialect::DockerCLI => {
+            return !key.is_empty() && !key.starts_with(['#', '\u{FEFF}']) &&
+                !key.starts_with(char::is_whitespace) &&
+                !key.contains(['=', ' ', '\t', '\n', '\r']);
+        }
+        Dialect::JavaProperties => return !key.is_empty(),
+        Dialect::Punktum => return !key.is_empty() && key.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '_'),
+        Dialect::NodeJS | Dialect::NodeJS20 | Dialect::NodeJS24 |
+        Dialect::JavaScriptDotenv | Dialect::DotenvExpand | Dialect::Dotenvx |
+        Dialect::PythonDotenv | Dialect::PythonDotenvCLI |
+        Dialect::ComposeGo | Dialect::JavaDotenv => ".-",
+        Dialect::GoDotenv | Dialect::RubyDotenv | Dialect::PhpDotenv | Dialect::Dotenvy => ".",
+        Dialect::Systemd | Dialect::PosixShell | Dialect::Auto | Dialect::Custom(_) => "",
+    };
+
+    key.starts_with(|ch: char| ch.is_ascii_alphabetic() || ch == '_') &&
+    key.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || extra.contains(ch))
+}
+
+/// Characters that don't need quoting in any of the dialects.
+#[inline]
+fn is_plain(value: &str) -> bool {
+    !value.is_empty() && value.chars().all(|ch| ch.is_ascii_alphanumeric() || "_-./,:+@%".contains(ch))
+}
+
+#[inline]
+fn push_quoted(buf: &mut String, quote: char, value: &str) {
+    buf.push(quote);
+    buf.push_str(value);
+    buf.push(quote);
+}
+
+/// Pushes `value` in double quotes, escaping characters with `escape()`, which
+/// returns the escape sequence or `None` to push the character as is.
+fn push_escaped(buf: &mut String, value: &str, escape: impl Fn(char) -> Option<&'static str>) {
+    buf.push('"');
+    for ch in value.chars() {
+        if let Some(escseq) = escape(ch) {
+            buf.push_str(escseq);
+        } else {
+            buf.push(ch);
+        }
+    }
+    buf.push('"');
+}
+
+fn encode_punktum(value: &str, buf: &mut String) -> Encoded {
+    if is_plain(value) || value.is_empty() {
+        buf.push_str(value);
+    } else if !value.contains(['\'', '\r']) {
+        push_quoted(buf, '\'', value);
+    } else {
+        push_escaped(buf, value, |ch| match ch {
+            '\\' => Some("\\\\"),
+            '"'  => Some("\\\""),
+            '$'  => Some("\\$"),
+            '\n' => Some("\\n"),
+            '\r' => Some("\\r"),
+            _ => None,
+        });
+    }
+    Ok(())
+}
+
+fn encode_posix_shell(value: &str, buf: &mut String) {
+    if is_plain(value) || value.is_empty() {
+        buf.push_str(value);
+    } else {
+        buf.push('\'');
+        buf.push_str(&value.replace('\'', "'\\''"));
+        buf.push('\'');
+    }
+}
+
+fn encode_systemd(value: &str, buf: &mut String) {
+    if is_plain(value) || value.is_empty() {
+        buf.push_str(value);
+    } else {
+        push_escaped(buf, value, |ch| match ch {
+            '"'  => Some("\\\""),
+            '\\' => Some("\\\\"),
+            '`'  => Some("\\`"),
+            '$'  => Some("\\$"),
+            _ => None,
+        });
+    }
+}
+
+// All NodeJS versions: no escapes except for \n in double quotes and all
+// carriage returns are removed before parsing.
+fn encode_nodejs(value: &str, buf: &mut String) -> Encoded {
+    if value.contains('\r') {
+        return Err("value contains a carriage return");
+    }
+
+    if is_plain(value) || value.is_empty() {
+        buf.push_str(value);
+    } else if !value.contains('\'') {
+        push_quoted(buf, '\'', value);
+    } else if !value.contains('`') {
+        push_quoted(buf, '`', value);
+    } else if !value.contains('"') && !value.contains("\\n") {
+        push_quoted(buf, '"', value);
+    } else {
+        return Err("value contains all kinds of quotes or \"\\n\"");
+    }
+    Ok(())
+}
+
+// A backslash right before the closing quote makes the parser look for
+// another quote further down the file.
+fn encode_javascript_dotenv(value: &str, buf: &mut String) -> Encoded {
+    if is_plain(value) || value.is_empty() {
+        buf.push_str(value);
+        return Ok(());
+    }
+
+    if value.ends_with('\\') {
+        return Err("value ends with a backslash");
+    }
+
+    if !value.contains('\r') {
+        if !value.contains('\'') {
+            push_quoted(buf, '\'', value);
+            return Ok(());
+        }
+
+        if !value.contains('`') {
+            push_quoted(buf, '`', value);
+            return Ok(());
+        }
+    }
+
+    if value.contains('"') || value.contains("\\n") || value.contains("\\r") {
+        return Err("value contains all kinds of quotes or \"\\n\" or \"\\r\"");
+    }
+
+    push_escaped(buf, value, |ch| match ch {
+        '\n' => Some("\\n"),
+        '\r' => Some("\\r"),
+        _ => None,
+    });
+    Ok(())
+}
+
+/// dotenv-expand removes the backslash of every `\$` after interpolating, so
+/// every `$` gets one. A `\$` that also starts an escaped substitution loses a
+/// backslash during interpolation already and is copied verbatim up to the end
+/// of that substitution, so it needs another one.
+fn escape_dotenv_expand(value: &str) -> String {
+    let escaped = value.replace('$', "\\$");
+    let mut buf = String::with_capacity(escaped.len());
+    let mut index = 0;
+
+    while index < escaped.len() {
+        if escaped[index..].starts_with("\\$") {
+            if let Some(subst) = match_substitution(&escaped, index) {
+                buf.push('\\');
+                buf.push_str(&escaped[index..subst.end]);
+                index = subst.end;
+                continue;
+            }
+        }
+        let ch = escaped[index..].chars().next().unwrap();
+        buf.push(ch);
+        index += ch.len_utf8();
+    }
+
+    buf
+}
+
+fn encode_python_dotenv(value: &str, buf: &mut String) -> Encoded {
+    // there is no way to escape an interpolation
+    if value.contains("${") {
+        return Err("value contains \"${\"");
+    }
+
+    if is_plain(value) || value.is_empty() {
+        buf.push_str(value);
+    } else if !value.ends_with('\\') {
+        buf.push('\'');
+        buf.push_str(&value.replace('\\', "\\\\").replace('\'', "\\'"));
+        buf.push('\'');
+    } else if !value.contains(['\n', '\r']) && !value.starts_with(['\'', '"']) &&
+              !value.starts_with(char::is_whitespace) && value.trim_end() == value &&
+              !value.char_indices().any(|(index, ch)| ch == '#' && value[..index].ends_with(char::is_whitespace)) {
+        // a quoted value ending in a backslash would swallow the closing quote
+        buf.push_str(value);
+    } else {
+        return Err("value ends with a backslash");
+    }
+    Ok(())
+}
+
+fn encode_python_dotenv_cli(value: &str, buf: &mut String) -> Encoded {
+    if is_plain(value) || value.is_empty() {
+        buf.push_str(value);
+    } else if !value.contains(['\n', '\r']) {
+        push_quoted(buf, '\'', value);
+    } else {
+        // The original decodes with Python's unicode_escape codec, which reads
+        // non-ASCII characters as ISO-8859-1. Escape them to be safe.
+        buf.push('"');
+        for ch in value.chars() {
+            match ch {
+                '\\' => buf.push_str("\\\\"),
+                '"'  => buf.push_str("\\\""),
+                '\n' => buf.push_str("\\n"),
+                '\r' => buf.push_str("\\r"),
+                '\u{10000}'.. => buf.push_str(&format!("\\U{:08x}", ch as u32)),
+                '\u{80}'..    => buf.push_str(&format!("\\u{:04x}", ch as u32)),
+                _ => buf.push(ch),
+            }
+        }
+        buf.push('"');
+    }
+    Ok(())
+}
+
+fn encode_composego(value: &str, buf: &mut String) {
+    if is_plain(value) || value.is_empty() {
+        buf.push_str(value);
+    } else if !value.contains('\'') && !value.ends_with('\\') {
+        push_quoted(buf, '\'', value);
+    } else {
+        push_escaped(buf, value, |ch| match ch {
+            '\\' => Some("\\\\"),
+            '"'  => Some("\\\""),
+            '$'  => Some("$$"),
+            '\n' => Some("\\n"),
+            '\r' => Some("\\r"),
+            _ => None,
+        });
+    }
+}
+
+// A quote right after a backslash never ends a quoted value.
+fn encode_go_dotenv(value: &str, buf: &mut String) -> Encoded {
+    if is_plain(value) || value.is_empty() {
+        buf.push_str(value);
+        return Ok(());
+    }
+
+    if value.ends_with('\\') {
+        return Err("value ends with a backslash");
+    }
+
+    if !value.contains('\'') && !value.contains("\r\n") {
+        push_quoted(buf, '\'', value);
+    } else if !value.ends_with('"') {
+        // quotes at the end are trimmed from the value
+        push_escaped(buf, value, |ch| match ch {
+            '\\' => Some("\\\\"),
+            '"'  => Some("\\\""),
+            '$'  => Some("\\$"),
+            '\n' => Some("\\n"),
+            '\r' => Some("\\r"),
+            _ => None,
+        });
+    } else {
+        return Err("value contains a single quote and ends with a double quote");
+    }
+    Ok(())
+}
+
+fn encode_ruby_dotenv(value: &str, buf: &mut String) -> Encoded {
+    if value.contains('\r') {
+        return Err("value contains a carriage return");
+    }
+
+    if is_plain(value) || value.is_empty() {
+        buf.push_str(value);
+        return Ok(());
+    }
+
+    if value.ends_with('\\') {
+        return Err("value ends with a backslash");
+    }
+
+    if !value.contains('\'') {
+        push_quoted(buf, '\'', value);
+        return Ok(());
+    }
+
+    // Escaping happens twice, the second time only for "\$". An odd number of
+    // backslashes before a "$" can't survive both.
+    let mut backslashes = 0;
+    for ch in value.chars() {
+        if ch == '$' && backslashes % 2 == 1 {
+            return Err("value contains a single quote and an odd number of backslashes before \"$\"");
+        }
+        backslashes = if ch == '\\' { backslashes + 1 } else { 0 };
+    }
+
+    push_escaped(buf, value, |ch| match ch {
+        '\\' => Some("\\\\"),
+        '"'  => Some("\\\""),
+        '$'  => Some("\\$"),
+        _ => None,
+    });
+    Ok(())
+}
+
+fn encode_php_dotenv(value: &str, buf: &mut String) {
+    if is_plain(value) || value.is_empty() {
+        buf.push_str(value);
+    } else {
+        // single quoted values containing '="' would be taken for the start
+        // of a multi-line value, so always use double quotes
+        push_escaped(buf, value, |ch| match ch {
+            '\\' => Some("\\\\"),
+            '"'  => Some("\\\""),
+            '$'  => Some("\\$"),
+            '\n' => Some("\\n"),
+            '\r' => Some("\\r"),
+            _ => None,
+        });
+    }
+}
+
+fn encode_java_dotenv(value: &str, buf: &mut String) -> Encoded {
+    if value.contains(['\n', '\r']) {
+        return Err("value contains a line break");
+    }
+
+    if is_plain(value) || value.is_empty() {
+        buf.push_str(value);
+    } else if !value.contains('"') {
+        push_quoted(buf, '"', value);
+    } else if value.contains('#') ||
+              value.starts_with(|ch| ch <= ' ') || value.ends_with(|ch| ch <= ' ') ||
+              (value.starts_with('"') && value.ends_with('"')) {
+        return Err("value contains a double quote and needs quoting");
+    } else {
+        // no escapes, but double quotes are only removed around the value
+        buf.push_str(value);
+    }
+    Ok(())
+}
+
+fn encode_dotenvy(value: &str, buf: &mut String) {
+    if is_plain(value) || value.is_empty() {
+        buf.push_str(value);
+    } else if !value.contains('\'') {
+        push_quoted(buf, '\'', value);
+    } else {
+        push_escaped(buf, value, |ch| match ch {
+            '\\' => Some("\\\\"),
+            '"'  => Some("\\\""),
+            '$'  => Some("\\$"),
+            '\n' => Some("\\n"),
+            _ => None,
+        });
+    }
+}
+
+/// Like `Properties.store()`, except that it doesn't need to escape `#` and
+/// `!` in the middle of a key or value.
+fn push_properties(buf: &mut String, value: &str, is_key: bool) {
+    for (index, ch) in value.chars().enumerate() {
+        match ch {
+            '\\' => buf.push_str("\\\\"),
+            '\t' => buf.push_str("\\t"),
+            '\n' => buf.push_str("\\n"),
+            '\r' => buf.push_str("\\r"),
+            '\x0C' => buf.push_str("\\f"),
+            ' ' if is_key || index == 0 => buf.push_str("\\ "),
+            '=' | ':' if is_key => {
+                buf.push('\\');
+                buf.push(ch);
+            }
+            '#' | '!' if is_key && index == 0 => {
+                buf.push('\\');
+                buf.push(ch);
+            }
+            '\x20'..='\x7E' => buf.push(ch),
+            _ => {
+                let mut units = [0; 2];
+                for unit in ch.encode_utf16(&mut units) {
+                    buf.push_str(&format!("\\u{unit:04X}"));
+                }
+            }
+        }
+    }
+}
@@ -1,3 +1,7 @@
+use std::{collections::HashMap, ffi::{OsStr, OsString}, io::Cursor, ops::Deref, path::{Path, PathBuf}, sync::{Arc, Mutex}};
+
+use punktum::{options::Builder, Diagnostic, Result};
+
 #[macro_export]
 macro_rules! assert_env_eq {
     ($env:ident, $fixture:expr) => {
@@ -10,3 +14,58 @@ macro_rules! assert_env_eq {
         }
     };
 }
+
+#[allow(dead_code)]
+pub fn get<'a>(env: &'a HashMap<OsString, OsString>, key: &str) -> Option<&'a str> {
+    env.get(OsStr::new(key)).map(|value| value.to_str().unwrap())
+}
+
+/// Load `src` with `builder` and an empty parent environment, collecting the
+/// diagnostics.
+#[allow(dead_code)]
+pub fn load_with_diagnostics(builder: Builder, src: &[u8]) -> (Result<HashMap<OsString, OsString>>, Vec<Diagnostic>) {
+    let diagnostics = Arc::new(Mutex::new(Vec::new()));
+    let res = builder.
+        diagnostics(diagnostics.clone()).
+        config_new_with_reader_and_parent(Cursor::new(src), &HashMap::<OsString, OsString>::new());
+
+    let diagnostics = diagnostics.lock().unwrap().clone();
+    (res, diagnostics)
+}
+
+/// A directory in the system temp directory that is removed again when it is
+/// dropped, even if the test fails.
+#[allow(dead_code)]
+pub struct TempDir(PathBuf);
+
+#[allow(dead_code)]
+impl TempDir {
+    /// Creates `punktum-PID-NAME` with `files` (relative path and contents) in
+    /// it. What is left of an earlier run is removed first.
+    pub fn new(name: &str, files: &[(&str, &str)]) -> Self {
+        let dir = std::env::temp_dir().join(format!("punktum-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        for (file, src) in files {
+            let path = dir.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, src).unwrap();
+        }
+        Self(dir)
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    #[inline]
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,37 @@
+pub const FIXTURE: &[(&str, &str)] = &[
+    ("VAR1", "simple"),
+    ("VAR2", ""),
+    ("VAR3", "FOO  BAR"),
+    ("VAR4", "FOO#NOCOMMENT"),
+    ("VAR5", "FOO"),
+    ("VAR6", "FOO\nBAR"),
+    ("VAR7", "single $VAR1 \\n quoted"),
+    ("VAR8", "double simple \n \" $ \\ quoted"),
+    ("VAR9", "unquoted with escapes\n"),
+    ("VAR10", "FOOBARBAZ"),
+    ("VAR11", "exported"),
+    ("export", "export"),
+    ("VAR12", "simple_suffix"),
+    ("VAR13", "simple_suffix"),
+    ("VAR14", "empty"),
+    ("VAR15", "inherited"),
+    ("VAR16", "not override"),
+    ("PRE_DEFINED", "not override"),
+    ("VAR17", "not override"),
+    ("VAR18", "a"),
+    ("VAR19", "simple"),
+    ("VAR20", "simplesimple"),
+    ("VAR21", "multi\nline #not a comment\nvalue"),
+    ("VAR22", "single\nquoted"),
+    ("VAR23", "a.b"),
+    ("dotted.key", "value"),
+    ("VAR24", "semi;colon"),
+    ("VAR25", ""),
+    ("VAR26", ""),
+    ("VAR27", "$VAR1"),
+    ("VAR28", "a\\b"),
+    ("VAR29", "a #b"),
+    ("VAR30", "indented"),
+    ("VAR31", ""),
+    ("VAR32", "_DEFINED"),
+];
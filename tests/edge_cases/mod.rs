@@ -1,9 +1,13 @@
 pub mod composego;
 pub mod composego_inherit_eof;
+pub mod dotenvy;
 pub mod godotenv;
 pub mod java;
 pub mod javascript;
 pub mod nodejs;
+pub mod nodejs20;
+pub mod nodejs24;
+pub mod phpdotenv;
 pub mod punktum;
 pub mod python_cli;
 pub mod python;
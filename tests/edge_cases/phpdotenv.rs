@@ -0,0 +1,31 @@
+pub const FIXTURE: &[(&str, &str)] = &[
+    ("VAR1", "simple"),
+    ("VAR2", ""),
+    ("VAR3", "FOO  BAR"),
+    ("VAR4", "BLUBB"),
+    ("VAR5", "FOO"),
+    ("VAR6", "FOO"),
+    ("VAR7", "FOO\nBAR"),
+    ("VAR8", "single ${VAR1} \\n quoted"),
+    ("VAR9", "double simple \n \t \" $ \\ quoted"),
+    ("VAR10", "unquoted\\n\\simple'\"'"),
+    ("VAR11", "multi\nline #not a comment\nvalue"),
+    ("VAR12", "exported"),
+    ("VAR13", "simple_suffix"),
+    ("VAR14", "$VAR1"),
+    ("VAR15", "${UNDEFINED}"),
+    ("VAR16", "inherited"),
+    ("VAR17", "not override"),
+    ("PRE_DEFINED", "not override"),
+    ("dotted.key", "value"),
+    ("VAR18", "value"),
+    ("NESTED", "{VAR1}"),
+    ("VAR19", "simple"),
+    ("VAR20", "${VAR1}"),
+    ("VAR21", "quoted"),
+    ("VAR22", "second"),
+    ("VAR25", "escaped \"quote\" and \\\"backslash\\"),
+    ("VAR26", ""),
+    ("Ärger", "umlaut"),
+    ("VAR27", "\u{c}nord\u{b}foo\rbar"),
+];
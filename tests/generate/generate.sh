@@ -14,15 +14,25 @@ export PRE_DEFINED="not override"
 PYTHON_DOTENV_CLI=${PYTHON_DOTENV_CLI:-~/.local/bin/dotenv}
 RUBY_DOTENV=${RUBY_DOTENV:-~/.rvm/gems/ruby-3.3.2/bin/dotenv}
 GO_DOTENV=${GO_DOTENV:-godotenv}
+DOTENVY=${DOTENVY:-dotenvy}
+NODE20=${NODE20:-node20}
+NODE24=${NODE24:-node24}
+PHP=${PHP:-php}
 
 pushd compose-go
 go build -o dotenv
 popd
 
+pushd php
+composer install
+popd
+
 # edge-cases.env
 # ==============
 
 node --env-file=edge-cases.env gen_dotenv.js > ../edge_cases/nodejs.rs
+"$NODE20" --env-file=edge-cases.env dumpenv.js > ../edge_cases/nodejs20.rs
+"$NODE24" --env-file=edge-cases.env dumpenv.js > ../edge_cases/nodejs24.rs
 DOTENV_CONFIG_PATH=edge-cases.env node gen_dotenv.js > ../edge_cases/javascript.rs
 
 "$PYTHON_DOTENV_CLI" --dotenv edge-cases.env node dumpenv.js > ../edge_cases/python_cli.rs
@@ -39,7 +49,13 @@ INHERIT=inherited compose-go/dotenv --file edge-cases-composego.env node dumpenv
 # since there can't be zero-length environment variable names.
 #INHERIT=inherited compose-go/dotenv --file inherit-eof.env --replace node dumpenv.js INHERIT > ../edge_cases/composego_inherit_eof.rs
 
-#dotenvy --file=dotenvy.env node dumpenv.js > ../edge_cases/dotenvy.rs
+# phpdotenv fails for the whole file on any syntax error, so it gets its own file.
+keys=(VAR1 VAR2 VAR3 VAR4 VAR5 VAR6 VAR7 VAR8 VAR9 VAR10 VAR11 VAR12 VAR13 VAR14 VAR15 VAR16 VAR17 PRE_DEFINED dotted.key VAR18 NESTED VAR19 VAR20 VAR21 VAR22 VAR23 VAR24 VAR25 VAR26 Ärger VAR27)
+INHERIT=inherited "$PHP" php/dotenv.php --file edge-cases-phpdotenv.env node dumpenv.js "${keys[@]}" > ../edge_cases/phpdotenv.rs
+
+# dotenvy stops at the first syntax error, so it gets its own file.
+keys=(VAR1 VAR2 VAR3 VAR4 VAR5 VAR6 VAR7 VAR8 VAR9 VAR10 VAR11 export VAR12 VAR13 VAR14 VAR15 VAR16 PRE_DEFINED VAR17 VAR18 VAR19 VAR20 VAR21 VAR22 VAR23 dotted.key VAR24 VAR25 VAR26 VAR27 VAR28 VAR29 VAR30 VAR31 VAR32)
+INHERIT=inherited "$DOTENVY" -f edge-cases-dotenvy.env node dumpenv.js "${keys[@]}" > ../edge_cases/dotenvy.rs
 
 pushd java
 gradle -q run --args="--file ../edge-cases-java.env node ../dumpenv.js" > ../../edge_cases/java.rs
@@ -54,6 +70,8 @@ popd
 
 keys=(FOO world BAR)
 node --env-file=quote-backtracking.env gen_dotenv.js "${keys[@]}" > ../quote_backtracking/nodejs.rs
+"$NODE20" --env-file=quote-backtracking.env dumpenv.js "${keys[@]}" > ../quote_backtracking/nodejs20.rs
+"$NODE24" --env-file=quote-backtracking.env dumpenv.js "${keys[@]}" > ../quote_backtracking/nodejs24.rs
 DOTENV_CONFIG_PATH=quote-backtracking.env node gen_dotenv.js "${keys[@]}" > ../quote_backtracking/javascript.rs
 
 "$PYTHON_DOTENV_CLI" --dotenv quote-backtracking.env node dumpenv.js "${keys[@]}" > ../quote_backtracking/python_cli.rs
@@ -78,6 +96,8 @@ popd
 keys=(BASIC BACKSLASH QUOTES SINGLE_QUOTED1 SINGLE_QUOTED2 INVALID_OCT OCT1 OCT2 OCT3 OCT4 HEX UTF16 UTF16_PAIR UTF32_6 UTF32_8 NAMED1 NAMED2 NAMED3 UNKNOWN ESCAPED_NEWLINE)
 
 node --env-file=escapes.env gen_dotenv.js "${keys[@]}" > ../escapes/nodejs.rs
+"$NODE20" --env-file=escapes.env dumpenv.js "${keys[@]}" > ../escapes/nodejs20.rs
+"$NODE24" --env-file=escapes.env dumpenv.js "${keys[@]}" > ../escapes/nodejs24.rs
 
 DOTENV_CONFIG_PATH=escapes.env node gen_dotenv.js "${keys[@]}" > ../escapes/javascript.rs
 "$PYTHON_DOTENV_CLI" --dotenv escapes-python-cli.env node dumpenv.js "${keys[@]}" > ../escapes/python_cli.rs
@@ -108,6 +128,8 @@ keys=(UNSET VAR1 VAR2 VAR3 VAR4 VAR5 VAR6 VAR7)
 python -m dotenv --file varsubst.env run --no-override node dumpenv.js "${keys[@]}" > ../varsubst/python.rs
 compose-go/dotenv --file varsubst.env node dumpenv.js "${keys[@]}" > ../varsubst/composego.rs
 "$RUBY_DOTENV" -f varsubst.env node dumpenv.js "${keys[@]}" > ../varsubst/ruby.rs
+"$DOTENVY" -f varsubst.env node dumpenv.js "${keys[@]}" > ../varsubst/dotenvy.rs
+"$PHP" php/dotenv.php --file varsubst.env node dumpenv.js "${keys[@]}" > ../varsubst/phpdotenv.rs
 
 pushd ../..
 cargo run -- --strict=false --file=tests/generate/varsubst.env node tests/generate/dumpenv.js "${keys[@]}" > tests/varsubst/punktum.rs
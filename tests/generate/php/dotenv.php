@@ -0,0 +1,41 @@
+<?php
+// usage: php dotenv.php [--file FILE] [--] command [args...]
+
+require __DIR__ . '/vendor/autoload.php';
+
+use Dotenv\Dotenv;
+use Dotenv\Repository\Adapter\PutenvAdapter;
+use Dotenv\Repository\RepositoryBuilder;
+
+$filename = '.env';
+$index = 1;
+for (; $index < $argc; ++ $index) {
+    $arg = $argv[$index];
+    if ($arg === '--') {
+        ++ $index;
+        break;
+    }
+
+    if ($arg === '--file') {
+        $filename = $argv[$index + 1];
+        ++ $index;
+    } else if (str_starts_with($arg, '-')) {
+        fwrite(STDERR, "illegal argument: $arg\n");
+        exit(1);
+    } else {
+        break;
+    }
+}
+
+$cmd = array_slice($argv, $index);
+
+// same repository as Laravel uses
+$repository = RepositoryBuilder::createWithDefaultAdapters()->
+    addAdapter(PutenvAdapter::class)->
+    immutable()->
+    make();
+
+Dotenv::create($repository, dirname($filename), basename($filename))->load();
+
+$proc = proc_open($cmd, [STDIN, STDOUT, STDERR], $pipes);
+exit(proc_close($proc));
@@ -1,6 +1,8 @@
 pub mod java;
 pub mod javascript;
 pub mod nodejs;
+pub mod nodejs20;
+pub mod nodejs24;
 pub mod punktum;
 pub mod python_cli;
 pub mod python;
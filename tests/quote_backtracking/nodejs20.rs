@@ -0,0 +1,4 @@
+pub const FIXTURE: &[(&str, &str)] = &[
+    ("FOO", "  hello\nworld  \\"),
+    ("BAR", "not quoted"),
+];
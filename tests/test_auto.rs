@@ -0,0 +1,78 @@
+mod common;
+
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+
+use punktum::{self, build, dialects::auto::{detect, Detection}, Diagnostic, DiagnosticCode, Dialect, ErrorKind, Options, Result, Severity};
+
+use common::{get, load_with_diagnostics};
+
+fn load(strict: bool, src: &[u8]) -> (Result<HashMap<OsString, OsString>>, Vec<Diagnostic>) {
+    load_with_diagnostics(build().dialect(Dialect::Auto).strict(strict), src)
+}
+
+fn detected(src: &[u8]) -> Detection {
+    detect(src, &Options::default())
+}
+
+#[test]
+fn test_auto_detect() {
+    assert_eq!(detected(b"FOO=bar\n# comment\nBAR='baz'\n"), Detection::Default);
+    assert_eq!(detected(b"FOO=bar\0BAR=baz\0"), Detection::Detected { dialect: Dialect::Binary, confidence: 100 });
+    assert_eq!(detected(b"FOO=`bar`\nBAR=baz\n"), Detection::Detected { dialect: Dialect::JavaScriptDotenv, confidence: 100 });
+    assert_eq!(detected(b"FOO: bar\nBAR: baz\n"), Detection::Detected { dialect: Dialect::JavaScriptDotenv, confidence: 100 });
+    assert_eq!(detected(b"! comment\nfoo.bar baz\nfoo.baz: x\n"), Detection::Detected { dialect: Dialect::JavaProperties, confidence: 100 });
+    // JavaScriptDotenv and NodeJS tie, but only NodeJS ignores the broken line
+    assert_eq!(detected(b"FOO=`bar`\nfoo.bar baz\nBAR=`baz`\n"), Detection::Detected { dialect: Dialect::NodeJS, confidence: 66 });
+}
+
+#[test]
+fn test_auto_ambiguous() {
+    // JavaScriptDotenv keeps the key, JavaProperties maps it to FOO
+    let src = b"foo: bar\n";
+    assert_eq!(detected(src), Detection::Ambiguous { candidates: vec![Dialect::JavaScriptDotenv, Dialect::JavaProperties] });
+
+    let (res, diagnostics) = load(true, src);
+    assert_eq!(res.err().map(|err| err.kind()), Some(ErrorKind::AmbiguousDialect));
+    assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+    assert_eq!(diagnostics[0].code(), DiagnosticCode::DialectDetection);
+    assert_eq!(diagnostics[0].severity(), Severity::Error);
+
+    // non-strict falls back to the first candidate instead of loading nothing
+    let (res, diagnostics) = load(false, src);
+    let env = res.unwrap();
+    assert_eq!(get(&env, "foo"), Some("bar"));
+    assert_eq!(env.len(), 1);
+    assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+    assert_eq!(diagnostics[0].severity(), Severity::Warning);
+    assert!(diagnostics[0].message().contains("JavaScriptDotenv"), "{diagnostics:?}");
+}
+
+#[test]
+fn test_auto_config() -> Result<()> {
+    let (res, diagnostics) = load(true, b"FOO=`it's \"quoted\"`\nBAR: baz\n");
+    let env = res?;
+
+    assert_eq!(get(&env, "FOO"), Some("it's \"quoted\""));
+    assert_eq!(get(&env, "BAR"), Some("baz"));
+    assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+    assert_eq!(diagnostics[0].code(), DiagnosticCode::DialectDetection);
+    assert_eq!(diagnostics[0].severity(), Severity::Info);
+    assert!(diagnostics[0].message().contains("JavaScriptDotenv"), "{diagnostics:?}");
+
+    let (res, _) = load(true, b"FOO=bar\0BAR=multi\nline\0");
+    let env = res?;
+    assert_eq!(get(&env, "BAR"), Some("multi\nline"));
+
+    let (res, _) = load(true, b"FOO='bar'\"baz\"\n");
+    let env = res?;
+    assert_eq!(get(&env, "FOO"), Some("barbaz"));
+
+    Ok(())
+}
+
+#[test]
+fn test_auto_name() {
+    assert_eq!(Dialect::try_from(OsStr::new("auto")), Ok(Dialect::Auto));
+    assert!(!Dialect::all().contains(&Dialect::Auto));
+}
@@ -0,0 +1,100 @@
+mod common;
+
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::path::Path;
+
+use punktum::{self, build, cascade, Result};
+
+use common::{get, TempDir};
+
+const FILES: &[(&str, &str)] = &[
+    (".env",                   "A=env\nB=env\nC=env\nD=env\nBASE=base\n"),
+    (".env.local",             "B=local\nC=local\nD=local\n"),
+    (".env.development",       "C=development\nD=development\nURL=http://$BASE/\n"),
+    (".env.development.local", "D=development.local\n"),
+    (".env.test",              "C=test\n"),
+];
+
+#[test]
+fn test_cascade_precedence() -> Result<()> {
+    let dir = TempDir::new("precedence", FILES);
+    let path = dir.join(".env");
+
+    let env = build().
+        path(&path).
+        mode(Some("development".into())).
+        config_new_with_parent(&HashMap::<OsString, OsString>::new())?;
+
+    assert_eq!(get(&env, "A"), Some("env"));
+    assert_eq!(get(&env, "B"), Some("local"));
+    assert_eq!(get(&env, "C"), Some("development"));
+    assert_eq!(get(&env, "D"), Some("development.local"));
+    assert_eq!(get(&env, "URL"), Some("http://base/"));
+
+    // .env.local is skipped in test mode, .env.test.local doesn't exist
+    let env = build().
+        path(&path).
+        mode(Some("test".into())).
+        config_new_with_parent(&HashMap::<OsString, OsString>::new())?;
+
+    assert_eq!(get(&env, "B"), Some("env"));
+    assert_eq!(get(&env, "C"), Some("test"));
+    assert_eq!(get(&env, "D"), Some("env"));
+
+    Ok(())
+}
+
+#[test]
+fn test_cascade_existing() -> Result<()> {
+    let dir = TempDir::new("existing", FILES);
+    let path = dir.join(".env");
+
+    let mut env = HashMap::new();
+    env.insert(OsString::from("C"), OsString::from("inherited"));
+    env.insert(OsString::from("BASE"), OsString::from("inherited"));
+
+    let builder = build().
+        path(&path).
+        mode(Some("development".into()));
+    builder.options().config_env(&mut env)?;
+
+    assert_eq!(get(&env, "C"), Some("inherited"));
+    assert_eq!(get(&env, "D"), Some("development.local"));
+    assert_eq!(get(&env, "URL"), Some("http://inherited/"));
+
+    builder.override_env(true).options().config_env(&mut env)?;
+    assert_eq!(get(&env, "C"), Some("development"));
+    assert_eq!(get(&env, "BASE"), Some("base"));
+
+    Ok(())
+}
+
+#[test]
+fn test_cascade_missing() -> Result<()> {
+    let dir = TempDir::new("missing", &[]);
+
+    // strict mode doesn't care about missing files of the cascade
+    let env = build().
+        path(dir.join(".env")).
+        strict(true).
+        mode(Some("production".into())).
+        config_new_with_parent(&HashMap::<OsString, OsString>::new())?;
+    assert!(env.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_cascade_paths() {
+    let paths = cascade::paths(Path::new("config/.env"), OsStr::new("staging"));
+    assert_eq!(paths, [
+        Path::new("config/.env"),
+        Path::new("config/.env.local"),
+        Path::new("config/.env.staging"),
+        Path::new("config/.env.staging.local"),
+    ]);
+
+    let paths = cascade::paths(Path::new(".env"), OsStr::new(cascade::TEST_MODE));
+    assert_eq!(paths, [Path::new(".env"), Path::new(".env.test"), Path::new(".env.test.local")]);
+}
@@ -0,0 +1,116 @@
+use std::io::{BufRead, Read, Write};
+
+use punktum::{codec::DecodeError, DecodingReader, Encoding, EncodingWriter};
+
+fn encode(src: &str, encoding: Encoding) -> Vec<u8> {
+    let mut writer = EncodingWriter::new(Vec::new(), encoding);
+    writer.write_all(src.as_bytes()).unwrap();
+    writer.into_inner()
+}
+
+#[test]
+fn test_codec_round_trip() {
+    let src = "FOO=bär\nBAR='\u{E000}\u{1F600}'\n";
+
+    for encoding in [Encoding::UTF8, Encoding::UTF16LE, Encoding::UTF16BE, Encoding::UTF32LE, Encoding::UTF32BE] {
+        let bytes = encode(src, encoding);
+        let mut decoded = String::new();
+        DecodingReader::new(bytes.as_slice(), encoding).read_to_string(&mut decoded).unwrap();
+        assert_eq!(decoded, src, "{encoding:?}");
+    }
+
+    let src = "FOO=\u{201C}€\u{201D}\n";
+    let bytes = encode(src, Encoding::Windows1252);
+    assert_eq!(bytes, b"FOO=\x93\x80\x94\n");
+    let mut decoded = String::new();
+    DecodingReader::new(bytes.as_slice(), Encoding::Windows1252).read_to_string(&mut decoded).unwrap();
+    assert_eq!(decoded, src);
+}
+
+#[test]
+fn test_codec_lines() {
+    let bytes = encode("A=1\nB=2\n", Encoding::UTF16LE);
+    let mut reader = DecodingReader::new(bytes.as_slice(), Encoding::UTF16LE);
+
+    let mut line = String::new();
+    reader.read_line(&mut line).unwrap();
+    assert_eq!(line, "A=1\n");
+    assert_eq!(reader.offset(), 8);
+    assert_eq!(reader.lineno(), 2);
+
+    // only decoded lines are consumed from the underlying reader
+    let rest = reader.into_inner();
+    assert_eq!(rest, &bytes[8..]);
+}
+
+#[test]
+fn test_codec_decode_error() {
+    let bytes = b"A=1\nB=\xff\xfe\nC=3\n";
+    let mut reader = DecodingReader::new(&bytes[..], Encoding::UTF8);
+    let mut line = String::new();
+
+    reader.read_line(&mut line).unwrap();
+    assert_eq!(line, "A=1\n");
+
+    line.clear();
+    let err = reader.read_line(&mut line).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    let decode_error = DecodeError::from_io(&err).unwrap();
+    assert_eq!(decode_error.offset(), 6);
+    assert_eq!(decode_error.lineno(), 2);
+    assert_eq!(decode_error.encoding(), Encoding::UTF8);
+
+    // reading resumes with the next line
+    line.clear();
+    reader.read_line(&mut line).unwrap();
+    assert_eq!(line, "C=3\n");
+
+    // a lone low surrogate
+    let bytes = b"A\0\n\0\x00\xdc\n\0";
+    let mut reader = DecodingReader::new(&bytes[..], Encoding::UTF16LE);
+    let mut src = String::new();
+    let err = reader.read_to_string(&mut src).unwrap_err();
+    let decode_error = DecodeError::from_io(&err).unwrap();
+    assert_eq!((decode_error.offset(), decode_error.lineno()), (4, 2));
+}
+
+#[test]
+fn test_codec_auto() {
+    let mut bytes = vec![0xFF, 0xFE];
+    bytes.extend(encode("FOO=bär\n", Encoding::UTF16LE));
+
+    let mut reader = DecodingReader::new(bytes.as_slice(), Encoding::Auto);
+    let mut src = String::new();
+    reader.read_to_string(&mut src).unwrap();
+    assert_eq!(src, "FOO=bär\n");
+    assert_eq!(reader.encoding(), Encoding::UTF16LE);
+    assert_eq!(reader.offset(), bytes.len() as u64);
+}
+
+#[test]
+fn test_codec_writer() {
+    // UTF-8 sequences may be split over several writes
+    let mut writer = EncodingWriter::new(Vec::new(), Encoding::Latin1);
+    let src = "ä".as_bytes();
+    writer.write_all(&src[..1]).unwrap();
+    writer.write_all(&src[1..]).unwrap();
+    assert_eq!(writer.into_inner(), b"\xe4");
+
+    let mut writer = EncodingWriter::new(Vec::new(), Encoding::Latin1);
+    let err = writer.write_all("€".as_bytes()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+    let mut writer = EncodingWriter::new(Vec::new(), Encoding::KOI8R);
+    let err = writer.write_all(b"\xff").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+    // a failed write keeps the pending part of a sequence
+    let mut writer = EncodingWriter::new(Vec::new(), Encoding::Latin1);
+    writer.write_all(&src[..1]).unwrap();
+    let err = writer.write_all(&[src[1], 0xff]).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    let err = writer.write_all("€".as_bytes()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    writer.write_all(&src[1..]).unwrap();
+    assert_eq!(writer.into_inner(), b"\xe4");
+}
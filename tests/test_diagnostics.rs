@@ -0,0 +1,69 @@
+use std::ffi::OsStr;
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+
+use punktum::{self, build, Diagnostic, DiagnosticCode, Dialect, Result, Severity};
+
+fn collect(dialect: Dialect, strict: bool, src: &str) -> (Result<()>, Vec<Diagnostic>) {
+    let diagnostics = Arc::new(Mutex::new(Vec::new()));
+    let res = build().
+        dialect(dialect).
+        strict(strict).
+        diagnostics(diagnostics.clone()).
+        config_new_with_reader(Cursor::new(src.as_bytes())).
+        map(|_| ());
+
+    let diagnostics = diagnostics.lock().unwrap().clone();
+    (res, diagnostics)
+}
+
+#[test]
+fn test_diagnostics_non_strict() -> Result<()> {
+    let (res, diagnostics) = collect(Dialect::Punktum, false, "FOO=bar\n-BAR=baz\nBAZ=\"unterminated\n");
+    res?;
+
+    assert_eq!(diagnostics.len(), 2, "{diagnostics:?}");
+    assert_eq!(diagnostics[0].code(), DiagnosticCode::SyntaxError);
+    assert_eq!(diagnostics[0].severity(), Severity::Warning);
+    assert_eq!(diagnostics[0].lineno(), Some(2));
+    assert_eq!(diagnostics[1].code(), DiagnosticCode::UnterminatedString);
+    assert_eq!(diagnostics[1].lineno(), Some(4));
+
+    for dialect in [Dialect::GoDotenv, Dialect::ComposeGo, Dialect::Binary] {
+        let (_, diagnostics) = collect(dialect, false, "FOO=\"bar\n");
+        assert!(!diagnostics.is_empty(), "{dialect}: no diagnostics");
+        assert!(diagnostics.iter().all(|diagnostic| diagnostic.severity() == Severity::Warning), "{dialect}: {diagnostics:?}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_diagnostics_strict() {
+    let (res, diagnostics) = collect(Dialect::Punktum, true, "FOO=bar\nBAR=${BAZ\n");
+    assert!(res.is_err());
+    assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+    assert_eq!(diagnostics[0].severity(), Severity::Error);
+    assert_eq!(diagnostics[0].lineno(), Some(2));
+}
+
+#[test]
+fn test_diagnostics_not_overwritten() -> Result<()> {
+    let diagnostics = Arc::new(Mutex::new(Vec::new()));
+    let mut env = std::collections::HashMap::new();
+    env.insert(OsStr::new("FOO").to_owned(), OsStr::new("old").to_owned());
+
+    build().
+        diagnostics(diagnostics.clone()).
+        config_with_reader(Cursor::new(b"FOO=new\n"), &mut env)?;
+
+    assert_eq!(env.get(OsStr::new("FOO")).unwrap(), "old");
+
+    let diagnostics = diagnostics.lock().unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].code(), DiagnosticCode::NotOverwritten);
+    assert_eq!(diagnostics[0].severity(), Severity::Info);
+    assert_eq!(diagnostics[0].code().to_string(), "not-overwritten");
+
+    Ok(())
+}
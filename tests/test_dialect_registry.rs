@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::io::{BufRead, Cursor};
+use std::path::Path;
+
+use punktum::{self, build, build_from, env::GetEnv, register_dialect, Dialect, DialectParser, Env, Options, Origin, Result};
+
+/// `KEY: VALUE` lines, nothing else.
+struct ColonDialect;
+
+impl DialectParser for ColonDialect {
+    fn name(&self) -> &str {
+        "Colon"
+    }
+
+    fn aliases(&self) -> &[&str] {
+        &["colon-legacy"]
+    }
+
+    fn parse(&self, reader: &mut dyn BufRead, env: &mut dyn Env, _parent: &dyn GetEnv, options: &Options<&Path>) -> Result<()> {
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            if let Some((key, value)) = line.split_once(':') {
+                options.set_var(env, key.trim().as_ref(), value.trim().as_ref(), Origin::new(index + 1, 1));
+            }
+        }
+        Ok(())
+    }
+}
+
+static COLON: ColonDialect = ColonDialect;
+
+#[test]
+fn test_dialect_registry() -> Result<()> {
+    let dialect = register_dialect(&COLON)?;
+    assert_eq!(dialect.to_string(), "Colon");
+    assert!(Dialect::all().contains(&dialect));
+
+    assert_eq!(Dialect::try_from(OsStr::new("colon")), Ok(dialect));
+    assert_eq!(Dialect::try_from(OsStr::new("COLON-LEGACY")), Ok(dialect));
+    assert_eq!(Dialect::try_from(OsStr::new("python-dotenv")), Ok(Dialect::PythonDotenv));
+
+    assert!(register_dialect(&COLON).is_err());
+
+    let parent: HashMap<OsString, OsString> = [
+        ("DOTENV_CONFIG_DIALECT".into(), "colon-legacy".into()),
+    ].into_iter().collect();
+
+    let env = build_from(&parent)?.config_new_with_reader(Cursor::new(b"FOO: bar\nBAZ :  x y \n"))?;
+    assert_eq!(env.get(OsStr::new("FOO")).unwrap(), "bar");
+    assert_eq!(env.get(OsStr::new("BAZ")).unwrap(), "x y");
+
+    let entries: Vec<_> = build().dialect(dialect).parse(Cursor::new(b"A:1\nB:2\n")).collect::<Result<_>>()?;
+    assert_eq!(entries[1].location().lineno(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn test_dialect_registry_builtin_names() {
+    struct Impostor;
+
+    impl DialectParser for Impostor {
+        fn name(&self) -> &str {
+            "js-dotenv"
+        }
+
+        fn parse(&self, _reader: &mut dyn BufRead, _env: &mut dyn Env, _parent: &dyn GetEnv, _options: &Options<&Path>) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    static IMPOSTOR: Impostor = Impostor;
+
+    assert!(register_dialect(&IMPOSTOR).is_err());
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::io::Cursor;
+
+use punktum::{self, build, Dialect, ErrorKind, Result};
+
+use common::get;
+
+#[test]
+fn test_docker_raw_values() -> Result<()> {
+    let src = concat!(
+        "\u{FEFF}# comment\n",
+        "  A=\"quoted\" # not a comment\r\n",
+        "\tB= spaces  \n",
+        "C=$A ${B}\n",
+        "\n",
+        "  # indented comment\n",
+        "D='x'=y",
+    );
+
+    for dialect in [Dialect::DockerCLI, Dialect::ComposeRaw] {
+        let env = build().
+            dialect(dialect).
+            config_new_with_reader(Cursor::new(src.as_bytes()))?;
+
+        assert_eq!(get(&env, "A"), Some("\"quoted\" # not a comment"), "{dialect}");
+        assert_eq!(get(&env, "B"), Some(" spaces  "), "{dialect}");
+        assert_eq!(get(&env, "C"), Some("$A ${B}"), "{dialect}");
+        assert_eq!(get(&env, "D"), Some("'x'=y"), "{dialect}");
+        assert_eq!(env.len(), 4, "{dialect}: {env:?}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_docker_inherit() -> Result<()> {
+    let mut parent = HashMap::new();
+    parent.insert(OsString::from("INHERIT"), OsString::from("inherited"));
+
+    let mut env = HashMap::new();
+    build().
+        dialect(Dialect::DockerCLI).
+        config_with_reader_and_parent(Cursor::new(b"INHERIT\nUNSET\nEMPTY=\n".as_slice()), &mut env, &parent)?;
+
+    assert_eq!(get(&env, "INHERIT"), Some("inherited"));
+    assert_eq!(get(&env, "UNSET"), None);
+    assert_eq!(get(&env, "EMPTY"), Some(""));
+
+    Ok(())
+}
+
+#[test]
+fn test_docker_bad_key() -> Result<()> {
+    for src in ["A=1\nFOO BAR=baz\n", "A=1\nFOO =bar\n", "A=1\n=bar\n", "A=1\nexport FOO\n"] {
+        let res = build().
+            dialect(Dialect::DockerCLI).
+            config_new_with_reader(Cursor::new(src.as_bytes()));
+        let err = res.err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::SyntaxError, "{src:?}");
+        assert_eq!(err.location().map(|location| location.lineno()), Some(2), "{src:?}");
+
+        let env = build().
+            dialect(Dialect::DockerCLI).
+            strict(false).
+            config_new_with_reader(Cursor::new(src.as_bytes()))?;
+        assert_eq!(get(&env, "A"), Some("1"), "{src:?}");
+        assert_eq!(env.len(), 1, "{src:?}: {env:?}");
+    }
+
+    Ok(())
+}
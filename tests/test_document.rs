@@ -0,0 +1,122 @@
+use std::ffi::OsStr;
+use std::io::Cursor;
+
+use punktum::{self, build, document::{Comment, Document, QuoteKind}, Dialect, Result};
+
+const ROUND_TRIP_FILES: &[&str] = &[
+    "examples/env1.env",
+    "examples/env2.env",
+    "examples/env3.env",
+    "examples/inherit.env",
+    "examples/vars.env",
+];
+
+#[test]
+fn test_document_round_trip() -> Result<()> {
+    for path in ROUND_TRIP_FILES {
+        let src = std::fs::read_to_string(path)?;
+        let doc = Document::parse(&src)?;
+        assert_eq!(src, doc.to_string(), "{path} didn't round trip");
+    }
+
+    let src = "# comment\r\n\r\n  export  FOO = 'a'\"b\"$BAR c  # trailing\r\nBAR\n\tBAZ=${X:-\"}\"\ny}\nEOF=x";
+    let doc = Document::parse(src)?;
+    assert_eq!(src, doc.to_string());
+    assert_eq!(doc.lines().len(), 6);
+
+    let foo = doc.get("FOO").unwrap();
+    assert!(foo.is_export());
+    assert_eq!(foo.raw_value(), "'a'\"b\"$BAR c");
+    let quotes: Vec<_> = foo.value_parts().iter().map(|part| part.quote()).collect();
+    assert_eq!(quotes, [QuoteKind::Single, QuoteKind::Double, QuoteKind::Unquoted]);
+    assert_eq!(doc.lines()[2].comment().map(Comment::text), Some("# trailing"));
+    assert_eq!(doc.lines()[2].newline(), "\r\n");
+    assert!(doc.get("BAR").unwrap().is_import());
+    assert_eq!(doc.get("BAZ").unwrap().raw_value(), "${X:-\"}\"\ny}");
+    assert_eq!(doc.lines()[5].newline(), "");
+
+    let key_span = foo.key_span().unwrap();
+    assert_eq!(&src[key_span.start..key_span.end], "FOO");
+
+    Ok(())
+}
+
+#[test]
+fn test_document_edit() -> Result<()> {
+    let src = "\
+# The database host.
+DB_HOST=localhost # for development
+DB_PASSWORD=\"secret\"
+
+# inherited
+HOME
+";
+    let mut doc = Document::parse(src)?;
+
+    doc.set("DB_HOST", "db.example.com");
+    doc.set("DB_PASSWORD", "it's a \"secret\" $HOME\n");
+    doc.set("HOME", "/home/nobody");
+    doc.set("NEW", "");
+    assert!(!doc.remove("MISSING"));
+
+    let out = doc.to_string();
+    assert!(out.starts_with("# The database host.\nDB_HOST=db.example.com # for development\n"));
+    assert!(out.contains("\n# inherited\nHOME=/home/nobody\nNEW=\n"));
+
+    let env = build().
+        dialect(Dialect::Punktum).
+        config_new_with_reader(Cursor::new(out.as_bytes()))?;
+
+    assert_eq!(env.get(OsStr::new("DB_HOST")).unwrap(), "db.example.com");
+    assert_eq!(env.get(OsStr::new("DB_PASSWORD")).unwrap(), "it's a \"secret\" $HOME\n");
+    assert_eq!(env.get(OsStr::new("HOME")).unwrap(), "/home/nobody");
+    assert_eq!(env.get(OsStr::new("NEW")).unwrap(), "");
+
+    assert!(doc.remove("DB_PASSWORD"));
+    assert!(doc.get("DB_PASSWORD").is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_document_include() -> Result<()> {
+    let src = "include ../base.env\n  include?  \"$HOME/.env.local\" # optional\r\ninclude ? x\ninclude=foo\ninclude\ninclude  # import\n";
+    let doc = Document::parse(src)?;
+    assert_eq!(src, doc.to_string());
+    assert_eq!(doc.lines().len(), 6);
+
+    let include = doc.lines()[0].include().unwrap();
+    assert!(!include.is_optional());
+    assert_eq!(include.raw_path(), "../base.env");
+
+    let include = doc.lines()[1].include().unwrap();
+    assert!(include.is_optional());
+    assert_eq!(include.raw_path(), "\"$HOME/.env.local\"");
+    assert_eq!(include.path_parts()[0].quote(), QuoteKind::Double);
+    assert_eq!(doc.lines()[1].comment().map(Comment::text), Some("# optional"));
+    assert_eq!(doc.lines()[1].newline(), "\r\n");
+
+    let include = doc.lines()[2].include().unwrap();
+    assert!(include.is_optional());
+    assert_eq!(include.raw_path(), "x");
+
+    assert_eq!(doc.lines()[3].entry().unwrap().raw_value(), "foo");
+    assert!(doc.lines()[4].entry().unwrap().is_import());
+    assert!(doc.lines()[5].entry().unwrap().is_import());
+    assert_eq!(doc.entries().count(), 3);
+
+    let mut doc = Document::parse("include foo\nFOO=bar\n")?;
+    doc.set("FOO", "baz");
+    assert_eq!(doc.to_string(), "include foo\nFOO=baz\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_document_syntax_errors() {
+    assert!(Document::parse("FOO=\"bar\n").is_err());
+    assert!(Document::parse("FOO=${BAR\n").is_err());
+    assert!(Document::parse("FOO BAR\n").is_err());
+    assert!(Document::parse("-FOO=BAR\n").is_err());
+    assert!(Document::parse("include \"foo\n").is_err());
+}
@@ -0,0 +1,154 @@
+mod common;
+
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::io::Cursor;
+
+use punktum::{self, build, Dialect, Result};
+
+use common::get;
+
+fn to_env(vars: &[(&str, &str)]) -> HashMap<OsString, OsString> {
+    vars.iter().map(|(key, value)| (key.into(), value.into())).collect()
+}
+
+fn expand(existing: &[(&str, &str)], parent: &[(&str, &str)], src: &str) -> Result<HashMap<OsString, OsString>> {
+    let parent = to_env(parent);
+    let mut env = to_env(existing);
+    build().
+        dialect(Dialect::DotenvExpand).
+        config_with_reader_and_parent(Cursor::new(src.as_bytes()), &mut env, &parent)?;
+
+    Ok(env)
+}
+
+#[test]
+fn test_dotenv_expand_basic() -> Result<()> {
+    let env = expand(&[], &[("HOME", "/home/user")], "\
+BASIC=basic
+EXPAND=$BASIC
+EXPAND_BRACES=${BASIC}
+EXPAND_LATER=$LATER
+LATER=later
+HOME_DIR=${HOME}/dir
+UNDEFINED=x${UNDEFINED_VAR}y
+SELF=$SELF
+ESCAPED=\\$BASIC
+NO_COMMAND=$(echo foo)
+")?;
+
+    assert_eq!(get(&env, "EXPAND"), Some("basic"));
+    assert_eq!(get(&env, "EXPAND_BRACES"), Some("basic"));
+    assert_eq!(get(&env, "EXPAND_LATER"), Some("later"));
+    assert_eq!(get(&env, "HOME_DIR"), Some("/home/user/dir"));
+    assert_eq!(get(&env, "UNDEFINED"), Some("xy"));
+    assert_eq!(get(&env, "SELF"), Some("$SELF"));
+    assert_eq!(get(&env, "ESCAPED"), Some("$BASIC"));
+    assert_eq!(get(&env, "NO_COMMAND"), Some("$(echo foo)"));
+    assert_eq!(get(&env, "HOME"), None);
+
+    Ok(())
+}
+
+#[test]
+fn test_dotenv_expand_defaults() -> Result<()> {
+    let env = expand(&[], &[], "\
+SET=set
+EMPTY=
+A=${UNSET:-default}
+B=${UNSET-default}
+C=$UNSET:-default
+D=${EMPTY:-default}
+E=${SET:-default}
+F=${UNSET:-$SET}
+G=${UNSET:-${ALSO_UNSET:-nested}}
+H=${UNSET:-${ALSO_UNSET:-${SET}}}
+")?;
+
+    assert_eq!(get(&env, "A"), Some("default"));
+    assert_eq!(get(&env, "B"), Some("default"));
+    assert_eq!(get(&env, "C"), Some("default"));
+    assert_eq!(get(&env, "D"), Some("default"));
+    assert_eq!(get(&env, "E"), Some("set"));
+    assert_eq!(get(&env, "F"), Some("set"));
+    assert_eq!(get(&env, "G"), Some("nested"));
+    assert_eq!(get(&env, "H"), Some("set"));
+
+    Ok(())
+}
+
+#[test]
+fn test_dotenv_expand_process_env() -> Result<()> {
+    // existing values are not overwritten and not expanded themselves, but
+    // are expanded where they are referenced
+    let existing = [("FOO", "${BAR}"), ("PASSWORD", "pas$word")];
+    let env = expand(&existing, &[], "\
+BAR='this is bar'
+FOO_ON_LINE2=\"$FOO\"
+BAR='replaced bar'
+FOO_ON_LINE4=\"$FOO\"
+PASSWORD=ignored
+")?;
+
+    assert_eq!(get(&env, "FOO"), Some("${BAR}"));
+    assert_eq!(get(&env, "BAR"), Some("replaced bar"));
+    assert_eq!(get(&env, "FOO_ON_LINE2"), Some("replaced bar"));
+    assert_eq!(get(&env, "FOO_ON_LINE4"), Some("replaced bar"));
+    assert_eq!(get(&env, "PASSWORD"), Some("pas$word"));
+
+    // once FOO itself was processed its parsed value is the existing value,
+    // which then isn't expanded anymore
+    let env = expand(&existing, &[], "\
+BAR='this is bar'
+FOO_ON_LINE2=\"$FOO\"
+BAR='replaced bar'
+FOO_ON_LINE4=\"$FOO\"
+FOO='replaced foo'
+FOO_ON_LINE6=\"$FOO\"
+")?;
+
+    assert_eq!(get(&env, "FOO"), Some("${BAR}"));
+    assert_eq!(get(&env, "FOO_ON_LINE2"), Some("replaced bar"));
+    assert_eq!(get(&env, "FOO_ON_LINE4"), Some("replaced bar"));
+    assert_eq!(get(&env, "FOO_ON_LINE6"), Some("${BAR}"));
+
+    Ok(())
+}
+
+#[test]
+fn test_dotenv_expand_empty_process_env() -> Result<()> {
+    // an empty value in processEnv is falsy, so it's replaced and expanded
+    let empty: &[(&str, &str)] = &[("FOO", "")];
+    for (existing, parent) in [(empty, &[][..]), (&[][..], empty)] {
+        let env = expand(existing, parent, "FOO=bar\nBAZ=${FOO}x\n")?;
+
+        assert_eq!(get(&env, "FOO"), Some("bar"));
+        assert_eq!(get(&env, "BAZ"), Some("barx"));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_dotenv_expand_recursion() -> Result<()> {
+    // cycles in the file itself are broken up because each value is only
+    // expanded once
+    let env = expand(&[], &[], "A=$B\nB=$A\n")?;
+    assert_eq!(get(&env, "A"), Some("$A"));
+    assert_eq!(get(&env, "B"), Some("$A"));
+
+    // but cycles through the existing environment recurse forever
+    let existing = [("A", "$B"), ("B", "$A")];
+    assert!(expand(&existing, &[], "C=$A\n").is_err());
+
+    let mut env = to_env(&existing);
+    build().
+        dialect(Dialect::DotenvExpand).
+        strict(false).
+        config_with_reader(Cursor::new(b"C=$A\nD=d\n".as_slice()), &mut env)?;
+
+    assert_eq!(env.get(OsStr::new("C")).map(|value| value.to_str().unwrap()), Some("$A"));
+    assert_eq!(env.get(OsStr::new("D")).map(|value| value.to_str().unwrap()), Some("d"));
+
+    Ok(())
+}
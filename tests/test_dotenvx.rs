@@ -0,0 +1,135 @@
+mod common;
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+
+use punktum::{self, build, CommandPolicy, Diagnostic, DiagnosticCode, Dialect, ErrorKind, Result, Severity};
+
+use common::get;
+
+fn echo(command: &str) -> std::io::Result<String> {
+    let Some(output) = command.strip_prefix("echo ") else {
+        return Err(std::io::Error::other(format!("unknown command: {command}")));
+    };
+    Ok(format!("{output}\n\n"))
+}
+
+#[test]
+fn test_dotenvx_command_policy() -> Result<()> {
+    let src = "A=$(echo foo)\nB=x$(echo bar)y\n";
+
+    let res = build().
+        dialect(Dialect::Dotenvx).
+        config_new_with_reader(Cursor::new(src.as_bytes()));
+    assert_eq!(res.err().map(|err| err.kind()), Some(ErrorKind::ExecError));
+
+    let diagnostics = Arc::new(Mutex::new(Vec::<Diagnostic>::new()));
+    let env = build().
+        dialect(Dialect::Dotenvx).
+        command_policy(CommandPolicy::Keep).
+        diagnostics(diagnostics.clone()).
+        config_new_with_reader(Cursor::new(src.as_bytes()))?;
+    assert_eq!(get(&env, "A"), Some("$(echo foo)"));
+    assert_eq!(get(&env, "B"), Some("x$(echo bar)y"));
+
+    let diagnostics = diagnostics.lock().unwrap();
+    assert_eq!(diagnostics.len(), 2, "{diagnostics:?}");
+    assert!(diagnostics.iter().all(|diagnostic|
+        diagnostic.code() == DiagnosticCode::CommandSubstitution &&
+        diagnostic.severity() == Severity::Info));
+
+    let env = build().
+        dialect(Dialect::Dotenvx).
+        strict(false).
+        config_new_with_reader(Cursor::new(src.as_bytes()))?;
+    assert_eq!(get(&env, "A"), Some("$(echo foo)"));
+
+    let env = build().
+        dialect(Dialect::Dotenvx).
+        command_policy(CommandPolicy::hook(echo)).
+        config_new_with_reader(Cursor::new(src.as_bytes()))?;
+    assert_eq!(get(&env, "A"), Some("foo"));
+    assert_eq!(get(&env, "B"), Some("xbary"));
+
+    let res = build().
+        dialect(Dialect::Dotenvx).
+        command_policy(CommandPolicy::hook(echo)).
+        config_new_with_reader(Cursor::new(b"A=$(false)\n".as_slice()));
+    assert_eq!(res.err().map(|err| err.kind()), Some(ErrorKind::ExecError));
+
+    Ok(())
+}
+
+#[test]
+fn test_dotenvx_command_quirks() -> Result<()> {
+    let commands = Arc::new(Mutex::new(Vec::new()));
+    let hook = {
+        let commands = commands.clone();
+        move |command: &str| {
+            commands.lock().unwrap().push(command.to_owned());
+            Ok(format!("<{command}>"))
+        }
+    };
+
+    let env = build().
+        dialect(Dialect::Dotenvx).
+        command_policy(CommandPolicy::hook(hook)).
+        config_new_with_reader(Cursor::new(b"\
+A=$(a) and $(b)
+B=$() $(c)
+C=$(d
+D=$(echo $BAR)
+BAR=bar
+".as_slice()))?;
+
+    // the command extends to the last ")" of a value
+    assert_eq!(get(&env, "A"), Some("<a) and $(b>"));
+    assert_eq!(get(&env, "B"), Some("$() <c>"));
+    assert_eq!(get(&env, "C"), Some("$(d"));
+    // commands are run before variables are expanded
+    assert_eq!(get(&env, "D"), Some("<echo bar>"));
+    assert_eq!(*commands.lock().unwrap(), ["a) and $(b", "c", "echo $BAR"]);
+
+    // "$&" etc. in the output are interpreted by String.prototype.replace()
+    let env = build().
+        dialect(Dialect::Dotenvx).
+        command_policy(CommandPolicy::hook(|_: &str| Ok("[$&|$$]".to_owned()))).
+        config_new_with_reader(Cursor::new(b"A=x$(cmd)y\n".as_slice()))?;
+    assert_eq!(get(&env, "A"), Some("x[$(cmd)|$]y"));
+
+    Ok(())
+}
+
+#[test]
+fn test_dotenvx_expand() -> Result<()> {
+    let mut env: HashMap<OsString, OsString> = [
+        ("EXISTING".into(), "existing".into()),
+    ].into_iter().collect();
+
+    build().
+        dialect(Dialect::Dotenvx).
+        config_with_reader(Cursor::new(b"\
+EXISTING=from file
+A=$EXISTING
+SELF=$SELF
+DEFAULT=${UNSET:-default}
+ESCAPED=\\$A
+".as_slice()), &mut env)?;
+
+    assert_eq!(get(&env, "EXISTING"), Some("existing"));
+    assert_eq!(get(&env, "A"), Some("existing"));
+    // unlike with dotenv-expand the file isn't applied before expanding
+    assert_eq!(get(&env, "SELF"), Some(""));
+    assert_eq!(get(&env, "DEFAULT"), Some("default"));
+    assert_eq!(get(&env, "ESCAPED"), Some("$A"));
+
+    // which means that cycles within the file recurse forever
+    let res = build().
+        dialect(Dialect::Dotenvx).
+        config_new_with_reader(Cursor::new(b"A=$B\nB=$A\n".as_slice()));
+    assert_eq!(res.err().map(|err| err.kind()), Some(ErrorKind::SubstitutionError));
+
+    Ok(())
+}
@@ -64,6 +64,20 @@ fn test_edge_cases_composego_inherit_eof() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_edge_cases_dotenvy() -> Result<()> {
+    // dotenvy stops at the first syntax error, so I use a different file.
+    assert_edge_cases!(edge_cases::dotenvy::FIXTURE, Dialect::Dotenvy, "tests/generate/edge-cases-dotenvy.env");
+    Ok(())
+}
+
+#[test]
+fn test_edge_cases_phpdotenv() -> Result<()> {
+    // phpdotenv fails for the whole file on any syntax error, so I use a different file.
+    assert_edge_cases!(edge_cases::phpdotenv::FIXTURE, Dialect::PhpDotenv, "tests/generate/edge-cases-phpdotenv.env");
+    Ok(())
+}
+
 #[test]
 fn test_edge_cases_java() -> Result<()> {
     // Java dotenv crashes (StringIndexOutOfBoundsException) in some cases of edge-cases.env, so I use a more limited version.
@@ -83,6 +97,18 @@ fn test_edge_cases_nodejs() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_edge_cases_nodejs20() -> Result<()> {
+    assert_edge_cases!(edge_cases::nodejs20::FIXTURE, Dialect::NodeJS20);
+    Ok(())
+}
+
+#[test]
+fn test_edge_cases_nodejs24() -> Result<()> {
+    assert_edge_cases!(edge_cases::nodejs24::FIXTURE, Dialect::NodeJS24);
+    Ok(())
+}
+
 #[test]
 fn test_edge_cases_punktum() -> Result<()> {
     assert_edge_cases!(edge_cases::punktum::FIXTURE, Dialect::Punktum);
@@ -0,0 +1,217 @@
+mod common;
+
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::io::Cursor;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use punktum::{self, build, Diagnostic, DiagnosticCode, Dialect, Encoding, ErrorKind, Result, Severity};
+
+use common::{get, load_with_diagnostics};
+
+fn load(encoding: Encoding, fallback_encoding: Option<Encoding>, src: &[u8]) -> (Result<HashMap<OsString, OsString>>, Vec<Diagnostic>) {
+    load_with_diagnostics(build().encoding(encoding).fallback_encoding(fallback_encoding), src)
+}
+
+fn utf16(src: &str, bom: bool, to_bytes: fn(u16) -> [u8; 2]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    if bom {
+        bytes.extend_from_slice(&to_bytes(0xFEFF));
+    }
+    for unit in src.encode_utf16() {
+        bytes.extend_from_slice(&to_bytes(unit));
+    }
+    bytes
+}
+
+fn utf32(src: &str, bom: bool, to_bytes: fn(u32) -> [u8; 4]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    if bom {
+        bytes.extend_from_slice(&to_bytes(0xFEFF));
+    }
+    for ch in src.chars() {
+        bytes.extend_from_slice(&to_bytes(ch as u32));
+    }
+    bytes
+}
+
+const SRC: &str = "FOO=bär\nBAR='\u{E000}\u{1F600}'\n";
+
+#[test]
+fn test_encoding_auto_utf16() -> Result<()> {
+    for (bom, to_bytes, encoding) in [
+        (true,  u16::to_le_bytes as fn(u16) -> [u8; 2], Encoding::UTF16LE),
+        (false, u16::to_le_bytes, Encoding::UTF16LE),
+        (true,  u16::to_be_bytes, Encoding::UTF16BE),
+        (false, u16::to_be_bytes, Encoding::UTF16BE),
+    ] {
+        let (res, diagnostics) = load(Encoding::Auto, None, &utf16(SRC, bom, to_bytes));
+        let env = res?;
+        assert_eq!(get(&env, "FOO"), Some("bär"), "{encoding:?}, BOM: {bom}");
+        assert_eq!(get(&env, "BAR"), Some("\u{E000}\u{1F600}"), "{encoding:?}, BOM: {bom}");
+        assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+        assert_eq!(diagnostics[0].code(), DiagnosticCode::EncodingDetection);
+        assert!(diagnostics[0].message().contains(&format!("{encoding:?}")), "{diagnostics:?}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_encoding_auto_utf32() -> Result<()> {
+    for (bom, to_bytes) in [
+        (true,  u32::to_le_bytes as fn(u32) -> [u8; 4]),
+        (false, u32::to_le_bytes),
+        (true,  u32::to_be_bytes),
+        (false, u32::to_be_bytes),
+    ] {
+        let (res, _) = load(Encoding::Auto, None, &utf32(SRC, bom, to_bytes));
+        let env = res?;
+        assert_eq!(get(&env, "FOO"), Some("bär"), "BOM: {bom}");
+        assert_eq!(get(&env, "BAR"), Some("\u{E000}\u{1F600}"), "BOM: {bom}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_encoding_auto_utf8() -> Result<()> {
+    let (res, _) = load(Encoding::Auto, None, "\u{FEFF}FOO=bär\n".as_bytes());
+    let env = res?;
+    assert_eq!(get(&env, "FOO"), Some("bär"));
+    assert_eq!(env.len(), 1);
+
+    let (res, _) = load(Encoding::Auto, None, b"");
+    assert_eq!(res?.len(), 0);
+
+    Ok(())
+}
+
+#[test]
+fn test_encoding_auto_fallback() -> Result<()> {
+    let (res, diagnostics) = load(Encoding::Auto, Some(Encoding::Latin1), b"FOO=b\xe4r\n");
+    assert_eq!(get(&res?, "FOO"), Some("bär"));
+    assert!(diagnostics[0].message().contains("Latin1"), "{diagnostics:?}");
+
+    let (res, _) = load(Encoding::Auto, Some(Encoding::Latin1), "FOO=bär\n".as_bytes());
+    assert_eq!(get(&res?, "FOO"), Some("bär"));
+
+    let (res, _) = load(Encoding::Auto, None, b"FOO=b\xe4r\n");
+    assert!(res.is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_encoding_detect() {
+    assert_eq!(Encoding::detect(b"\xEF\xBB\xBFFOO=bar"), Some((Encoding::UTF8, 3)));
+    assert_eq!(Encoding::detect(b"\xFF\xFEF\0"), Some((Encoding::UTF16LE, 2)));
+    assert_eq!(Encoding::detect(b"\xFF\xFE\0\0"), Some((Encoding::UTF32LE, 4)));
+    assert_eq!(Encoding::detect(b"FOO=bar\n"), None);
+    assert_eq!(Encoding::detect(b"FOO=bar\0BAZ=qux\0"), None);
+    assert_eq!(Encoding::try_from(OsStr::new("AUTO")), Ok(Encoding::Auto));
+}
+
+#[test]
+fn test_encoding_single_byte() -> Result<()> {
+    for (name, src, expected) in [
+        ("cp1252",      &b"FOO=\x93\x80 5\x94\n"[..],  "\u{201C}€ 5\u{201D}"),
+        ("ISO-8859-15", &b"FOO=\xa4 \xbd\xe4\n"[..],  "€ œä"),
+        ("latin2",      &b"FOO=\xb1\xe8\n"[..],       "ąč"),
+        ("KOI8-R",      &b"FOO=\xf0\xd2\xc9\n"[..],   "При"),
+    ] {
+        let encoding = Encoding::try_from(OsStr::new(name)).unwrap();
+
+        // the Punktum dialect reads line by line, JavaScriptDotenv the whole file
+        for dialect in [Dialect::Punktum, Dialect::JavaScriptDotenv] {
+            let env = build().
+                encoding(encoding).
+                dialect(dialect).
+                config_new_with_reader_and_parent(Cursor::new(src), &HashMap::<OsString, OsString>::new())?;
+            assert_eq!(get(&env, "FOO"), Some(expected), "{name}, {dialect}");
+        }
+    }
+
+    // undefined in Windows-1252
+    let (res, _) = load(Encoding::Windows1252, None, b"FOO=\x81\n");
+    assert!(res.is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_encoding_error_recovery() -> Result<()> {
+    let src = b"FOO=foo\n# caf\xe9\nBAR=bar\n";
+
+    for dialect in [
+        Dialect::JavaScriptDotenv, Dialect::NodeJS, Dialect::RubyDotenv, Dialect::PythonDotenv,
+        Dialect::ComposeGo, Dialect::GoDotenv, Dialect::JavaDotenv, Dialect::Dotenvy,
+        Dialect::DockerCLI, Dialect::PhpDotenv, Dialect::Systemd, Dialect::PosixShell,
+    ] {
+        let diagnostics = Arc::new(Mutex::new(Vec::new()));
+        let env = build().
+            dialect(dialect).
+            strict(false).
+            diagnostics(diagnostics.clone()).
+            config_new_with_reader_and_parent(Cursor::new(src), &HashMap::<OsString, OsString>::new())?;
+
+        assert_eq!(get(&env, "FOO"), Some("foo"), "{dialect}");
+        assert_eq!(get(&env, "BAR"), Some("bar"), "{dialect}");
+
+        let diagnostics = diagnostics.lock().unwrap();
+        assert_eq!(diagnostics.len(), 1, "{dialect}: {diagnostics:?}");
+        assert_eq!(diagnostics[0].code(), DiagnosticCode::EncodingError);
+        assert_eq!(diagnostics[0].severity(), Severity::Warning);
+        assert_eq!((diagnostics[0].lineno(), diagnostics[0].column()), (Some(2), Some(6)), "{dialect}");
+
+        let res = build().
+            dialect(dialect).
+            strict(true).
+            config_new_with_reader_and_parent(Cursor::new(src), &HashMap::<OsString, OsString>::new());
+        let err = res.err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::IOError, "{dialect}");
+        assert_eq!(err.location().map(|location| location.lineno()), Some(2), "{dialect}");
+    }
+
+    // other encodings are decoded before the dialect sees the file
+    let mut src = utf16("FOO=foo\n# x\n", false, u16::to_le_bytes);
+    src[18..20].copy_from_slice(&0xDC00u16.to_le_bytes());
+    let diagnostics = Arc::new(Mutex::new(Vec::new()));
+    let env = build().
+        dialect(Dialect::JavaScriptDotenv).
+        encoding(Encoding::UTF16LE).
+        strict(false).
+        diagnostics(diagnostics.clone()).
+        config_new_with_reader_and_parent(Cursor::new(src), &HashMap::<OsString, OsString>::new())?;
+    assert_eq!(get(&env, "FOO"), Some("foo"));
+    let diagnostics = diagnostics.lock().unwrap();
+    assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+    assert_eq!(diagnostics[0].lineno(), Some(2));
+
+    Ok(())
+}
+
+#[test]
+fn test_encoding_punktum_decode_error_location() -> Result<()> {
+    // calling the parser directly decodes with one reader for the whole input
+    let diagnostics = Arc::new(Mutex::new(Vec::new()));
+    let options = build().
+        path(Path::new(".env")).
+        encoding(Encoding::ASCII).
+        strict(false).
+        diagnostics(diagnostics.clone()).
+        into_options();
+
+    let mut env = HashMap::new();
+    punktum::dialects::punktum::config_punktum(
+        &mut Cursor::new(b"A=1\nB=2\nC=caf\xe9\nD=4\n"), &mut env, &HashMap::<OsString, OsString>::new(), &options)?;
+    assert_eq!(get(&env, "D"), Some("4"));
+
+    let diagnostics = diagnostics.lock().unwrap();
+    assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+    assert_eq!(diagnostics[0].lineno(), Some(3));
+    assert!(diagnostics[0].message().contains("at byte offset 13 on line 3 at column 6"), "{diagnostics:?}");
+
+    Ok(())
+}
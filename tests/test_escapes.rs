@@ -71,6 +71,18 @@ fn test_edge_cases_nodejs() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_edge_cases_nodejs20() -> Result<()> {
+    assert_escapes!(escapes::nodejs20::FIXTURE, Dialect::NodeJS20);
+    Ok(())
+}
+
+#[test]
+fn test_edge_cases_nodejs24() -> Result<()> {
+    assert_escapes!(escapes::nodejs24::FIXTURE, Dialect::NodeJS24);
+    Ok(())
+}
+
 #[test]
 fn test_edge_cases_punktum() -> Result<()> {
     assert_escapes!(escapes::punktum::FIXTURE, Dialect::Punktum);
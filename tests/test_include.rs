@@ -0,0 +1,166 @@
+mod common;
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use punktum::{self, build, DiagnosticCode, ErrorKind, Result};
+
+use common::{get, TempDir};
+
+fn load(path: &Path) -> Result<HashMap<OsString, OsString>> {
+    build().
+        path(path).
+        config_new_with_parent(&HashMap::<OsString, OsString>::new())
+}
+
+#[test]
+fn test_include() -> Result<()> {
+    let dir = TempDir::new("include", &[
+        ("base.env",        "HOST=localhost\nPORT=8080\nNAME=base\n"),
+        ("service/.env",    "NAME=service\ninclude ../base.env\ninclude? local.env\nURL=http://$HOST:$PORT/$NAME\n"),
+        ("quoted/.env",     "DIR=..\ninclude \"$DIR/base.env\" # comment\n"),
+    ]);
+
+    let env = load(&dir.join("service/.env"))?;
+    assert_eq!(get(&env, "HOST"), Some("localhost"));
+    // the first definition wins unless override is set
+    assert_eq!(get(&env, "NAME"), Some("service"));
+    assert_eq!(get(&env, "URL"), Some("http://localhost:8080/service"));
+
+    let env = load(&dir.join("quoted/.env"))?;
+    assert_eq!(get(&env, "PORT"), Some("8080"));
+
+    // `include` is still a valid variable name
+    let env = build().config_new_with_reader_and_parent("include=yes\n".as_bytes(), &HashMap::<OsString, OsString>::new())?;
+    assert_eq!(get(&env, "include"), Some("yes"));
+
+    Ok(())
+}
+
+#[test]
+fn test_include_missing() -> Result<()> {
+    let dir = TempDir::new("include-missing", &[
+        (".env", "A=1\ninclude missing.env\nB=2\n"),
+    ]);
+
+    let err = load(&dir.join(".env")).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::IOError);
+    assert_eq!(err.location().map(|location| location.lineno()), Some(2));
+
+    let diagnostics = Arc::new(Mutex::new(Vec::new()));
+    let env = build().
+        path(dir.join(".env")).
+        strict(false).
+        diagnostics(diagnostics.clone()).
+        config_new_with_parent(&HashMap::<OsString, OsString>::new())?;
+    assert_eq!(get(&env, "B"), Some("2"));
+    assert_eq!(diagnostics.lock().unwrap()[0].code(), DiagnosticCode::IOError);
+
+    Ok(())
+}
+
+#[test]
+fn test_include_diagnostics() -> Result<()> {
+    let dir = TempDir::new("include-diagnostics", &[
+        (".env",         "A=1\ninclude nested/b.env\n"),
+        ("nested/b.env", "B=1\n\ninclude c.env\n"),
+        ("nested/c.env", "C=1\n-BROKEN\nD=1\n"),
+    ]);
+
+    let diagnostics = Arc::new(Mutex::new(Vec::new()));
+    let env = build().
+        path(dir.join(".env")).
+        strict(false).
+        diagnostics(diagnostics.clone()).
+        config_new_with_parent(&HashMap::<OsString, OsString>::new())?;
+    assert_eq!(get(&env, "D"), Some("1"));
+
+    let diagnostics = diagnostics.lock().unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    let diagnostic = &diagnostics[0];
+    assert_eq!(diagnostic.code(), DiagnosticCode::SyntaxError);
+    assert_eq!(diagnostic.path(), Some(dir.join("nested/c.env").as_path()));
+    assert_eq!(diagnostic.lineno(), Some(2));
+
+    let chain = diagnostic.include_chain();
+    assert_eq!(chain.len(), 2);
+    assert_eq!(chain[0].path(), dir.join("nested/c.env"));
+    assert_eq!(chain[0].location().lineno(), 3);
+    assert_eq!(chain[1].path(), dir.join("nested/b.env"));
+    assert_eq!(chain[1].location().lineno(), 2);
+
+    let message = diagnostic.to_string();
+    assert!(message.contains("b.env, included on line 2 at column 1)"), "{message}");
+
+    Ok(())
+}
+
+#[test]
+fn test_include_cycle() {
+    let dir = TempDir::new("include-cycle", &[
+        ("a.env", "A=1\ninclude b.env\n"),
+        ("b.env", "B=1\n\ninclude a.env\n"),
+    ]);
+
+    let err = load(&dir.join("a.env")).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::IncludeError);
+    assert_eq!(err.location().map(|location| location.lineno()), Some(3));
+    assert_eq!(err.include_chain().len(), 1);
+    assert_eq!(err.include_chain()[0].path(), dir.join("b.env"));
+    assert_eq!(err.include_chain()[0].location().lineno(), 2);
+
+    let message = err.to_string();
+    assert!(message.contains("on line 3 at column 1 in "), "{message}");
+    assert!(message.contains("b.env, included on line 2 at column 1"), "{message}");
+}
+
+#[test]
+fn test_include_depth() {
+    let files: Vec<_> = (0..=punktum::dialects::punktum::MAX_INCLUDE_DEPTH + 1).map(|index| {
+        (format!("{index}.env"), format!("V{index}=1\ninclude {}.env\n", index + 1))
+    }).collect();
+    let files: Vec<_> = files.iter().map(|(name, src)| (name.as_str(), src.as_str())).collect();
+    let dir = TempDir::new("include-depth", &files);
+
+    let err = load(&dir.join("0.env")).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::IncludeError);
+    assert_eq!(err.include_chain().len(), punktum::dialects::punktum::MAX_INCLUDE_DEPTH);
+    assert!(err.to_string().contains("nested deeper"), "{err}");
+
+    // the loaded file doesn't exist, so it isn't on the include stack, but
+    // it still counts for the depth
+    let err = build().
+        path(dir.join("missing.env")).
+        config_new_with_reader_and_parent("include 0.env\n".as_bytes(), &HashMap::<OsString, OsString>::new()).
+        err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::IncludeError);
+    assert_eq!(err.include_chain().len(), punktum::dialects::punktum::MAX_INCLUDE_DEPTH);
+    assert_eq!(err.include_chain().last().unwrap().path(), dir.join("0.env"));
+}
+
+#[test]
+fn test_include_root() -> Result<()> {
+    let dir = TempDir::new("include-root", &[
+        ("secrets.env",  "SECRET=1\n"),
+        ("app/base.env", "BASE=1\n"),
+        ("app/.env",     "include base.env\ninclude ../secrets.env\n"),
+    ]);
+
+    let res = build().
+        path(dir.join("app/.env")).
+        include_root(Some(dir.join("app"))).
+        config_new_with_parent(&HashMap::<OsString, OsString>::new());
+    let err = res.err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::IncludeError);
+    assert_eq!(err.location().map(|location| location.lineno()), Some(2));
+
+    let env = build().
+        path(dir.join("app/.env")).
+        include_root(Some(dir.to_path_buf())).
+        config_new_with_parent(&HashMap::<OsString, OsString>::new())?;
+    assert_eq!(get(&env, "SECRET"), Some("1"));
+
+    Ok(())
+}
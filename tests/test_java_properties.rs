@@ -0,0 +1,146 @@
+mod common;
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+
+use punktum::{self, build, options::Options, Diagnostic, DiagnosticCode, Dialect, Encoding, ErrorKind, KeyMapping, Result};
+
+use common::get;
+
+#[test]
+fn test_java_properties_syntax() -> Result<()> {
+    let src = concat!(
+        "# comment\n",
+        "! comment\n",
+        "  spring.datasource.url = jdbc:postgresql://localhost/db\n",
+        "server.port:8080\n",
+        "app.name   My App\n",
+        "app.description = a long \\\n",
+        "                  description\\n\r\n",
+        "key\\ with\\:colon=\\u00e9\\t\\x\\\\\\\n",
+        "# not a comment\n",
+        "empty\n",
+        "dup=1\n",
+        "dup=2\n",
+    );
+
+    let env = build().
+        dialect(Dialect::JavaProperties).
+        key_mapping(KeyMapping::Keep).
+        config_new_with_reader_and_parent(Cursor::new(src.as_bytes()), &HashMap::<OsString, OsString>::new())?;
+
+    assert_eq!(get(&env, "spring.datasource.url"), Some("jdbc:postgresql://localhost/db"));
+    assert_eq!(get(&env, "server.port"), Some("8080"));
+    assert_eq!(get(&env, "app.name"), Some("My App"));
+    assert_eq!(get(&env, "app.description"), Some("a long description\n"));
+    assert_eq!(get(&env, "key with:colon"), Some("é\tx\\# not a comment"));
+    assert_eq!(get(&env, "empty"), Some(""));
+    assert_eq!(get(&env, "dup"), Some("2"));
+    assert_eq!(env.len(), 7, "{env:?}");
+
+    Ok(())
+}
+
+#[test]
+fn test_java_properties_key_mapping() -> Result<()> {
+    let src = b"spring.datasource.url=x\nmy.main-project.list[0]=y\n";
+
+    let env = build().
+        dialect(Dialect::JavaProperties).
+        config_new_with_reader_and_parent(Cursor::new(src.as_slice()), &HashMap::<OsString, OsString>::new())?;
+    assert_eq!(get(&env, "SPRING_DATASOURCE_URL"), Some("x"));
+    assert_eq!(get(&env, "MY_MAIN_PROJECT_LIST_0_"), Some("y"));
+
+    let env = build().
+        dialect(Dialect::JavaProperties).
+        key_mapping(KeyMapping::Spring).
+        config_new_with_reader_and_parent(Cursor::new(src.as_slice()), &HashMap::<OsString, OsString>::new())?;
+    assert_eq!(get(&env, "SPRING_DATASOURCE_URL"), Some("x"));
+    assert_eq!(get(&env, "MY_MAINPROJECT_LIST_0"), Some("y"));
+
+    let env = build().
+        dialect(Dialect::JavaProperties).
+        key_mapping(KeyMapping::hook(|key: &str| format!("APP_{}", key.replace('.', "__")))).
+        config_new_with_reader_and_parent(Cursor::new(src.as_slice()), &HashMap::<OsString, OsString>::new())?;
+    assert_eq!(get(&env, "APP_spring__datasource__url"), Some("x"));
+
+    let options_env: HashMap<OsString, OsString> = [
+        ("DOTENV_CONFIG_DIALECT".into(), "properties".into()),
+        ("DOTENV_CONFIG_KEY_MAPPING".into(), "spring".into()),
+    ].into_iter().collect();
+    let options = Options::try_from(&options_env)?;
+    assert_eq!(options.dialect, Dialect::JavaProperties);
+    assert_eq!(options.key_mapping, KeyMapping::Spring);
+
+    let options_env: HashMap<OsString, OsString> = [
+        ("DOTENV_CONFIG_KEY_MAPPING".into(), "kebab".into()),
+    ].into_iter().collect();
+    assert_eq!(Options::try_from(&options_env).err().map(|err| err.kind()), Some(ErrorKind::OptionsParseError));
+
+    Ok(())
+}
+
+#[test]
+fn test_java_properties_encoding() -> Result<()> {
+    for (src, encoding, expected) in [
+        (b"A=\xe9\n".as_slice(), Encoding::UTF8, "é"),
+        (b"A=\xc3\xa9\n".as_slice(), Encoding::UTF8, "é"),
+        (b"A=\xc3\xa9\n".as_slice(), Encoding::Latin1, "Ã©"),
+    ] {
+        let env = build().
+            dialect(Dialect::JavaProperties).
+            encoding(encoding).
+            config_new_with_reader_and_parent(Cursor::new(src), &HashMap::<OsString, OsString>::new())?;
+        assert_eq!(get(&env, "A"), Some(expected), "{src:?} {encoding:?}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_java_properties_errors() -> Result<()> {
+    for (src, code, column) in [
+        ("A=1\nB=\\u00zz\nC=3\n", DiagnosticCode::IllegalEscape, 1),
+        ("A=1\n  =2\nC=3\n", DiagnosticCode::SyntaxError, 3),
+    ] {
+        let res = build().
+            dialect(Dialect::JavaProperties).
+            config_new_with_reader_and_parent(Cursor::new(src.as_bytes()), &HashMap::<OsString, OsString>::new());
+        let err = res.err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::SyntaxError, "{src:?}");
+        assert_eq!(err.location().map(|location| (location.lineno(), location.column())), Some((2, column)), "{src:?}");
+
+        let diagnostics = Arc::new(Mutex::new(Vec::<Diagnostic>::new()));
+        let env = build().
+            dialect(Dialect::JavaProperties).
+            strict(false).
+            diagnostics(diagnostics.clone()).
+            config_new_with_reader_and_parent(Cursor::new(src.as_bytes()), &HashMap::<OsString, OsString>::new())?;
+        assert_eq!(get(&env, "A"), Some("1"), "{src:?}");
+        assert_eq!(get(&env, "C"), Some("3"), "{src:?}");
+        assert_eq!(env.len(), 2, "{src:?}: {env:?}");
+
+        let diagnostics = diagnostics.lock().unwrap();
+        let codes: Vec<_> = diagnostics.iter().map(|diagnostic| diagnostic.code()).collect();
+        assert_eq!(codes, [code], "{src:?}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_java_properties_no_override() -> Result<()> {
+    let mut env = HashMap::new();
+    env.insert(OsString::from("A"), OsString::from("existing"));
+
+    build().
+        dialect(Dialect::JavaProperties).
+        config_with_reader_and_parent(Cursor::new(b"a=1\na=2\nb=1\nb=2\n".as_slice()), &mut env, &HashMap::<OsString, OsString>::new())?;
+
+    assert_eq!(get(&env, "A"), Some("existing"));
+    assert_eq!(get(&env, "B"), Some("2"));
+
+    Ok(())
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+
+use punktum::{self, build, Diagnostic, DiagnosticCode, Dialect, Encoding, ErrorKind, Modeline, Result, Severity};
+
+use common::{get, load_with_diagnostics};
+
+fn load(strict: bool, src: &[u8]) -> (Result<HashMap<OsString, OsString>>, Vec<Diagnostic>) {
+    load_with_diagnostics(build().strict(strict), src)
+}
+
+#[test]
+fn test_modeline_parse() {
+    assert!(Modeline::parse("FOO=bar").is_none());
+    assert!(Modeline::parse("# just a comment").is_none());
+    assert!(Modeline::parse("# punktum: config for dev").is_none());
+    assert!(Modeline::parse("# punktum: dialect").is_none());
+    assert_eq!(Modeline::parse("# punktum:").map(|res| res.ok()), Some(Some(Modeline::default())));
+    assert_eq!(
+        Modeline::parse("  #punktum: dialect=ruby-dotenv   encoding=latin1\r\n").map(|res| res.ok()),
+        Some(Some(Modeline { dialect: Some(Dialect::RubyDotenv), encoding: Some(Encoding::Latin1) })));
+
+    for line in ["# punktum: dialect=nope", "# punktum: encoding=nope", "# punktum: color=red"] {
+        let res = Modeline::parse(line).unwrap();
+        assert_eq!(res.err().map(|err| err.kind()), Some(ErrorKind::OptionsParseError), "{line}");
+    }
+}
+
+#[test]
+fn test_modeline_dialect() -> Result<()> {
+    // Punktum would fail on the colon
+    let (res, diagnostics) = load(true, b"# punktum: dialect=JavaScriptDotenv\nFOO: `bar`\n");
+    let env = res?;
+
+    assert_eq!(get(&env, "FOO"), Some("bar"));
+    assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+    assert_eq!(diagnostics[0].code(), DiagnosticCode::Modeline);
+    assert_eq!(diagnostics[0].severity(), Severity::Info);
+    assert_eq!(diagnostics[0].lineno(), Some(1));
+
+    Ok(())
+}
+
+#[test]
+fn test_modeline_encoding() -> Result<()> {
+    let (res, _) = load(true, b"# punktum: encoding=latin1\nFOO=\xe4\n");
+    assert_eq!(get(&res?, "FOO"), Some("ä"));
+
+    let mut utf16 = Vec::new();
+    for unit in "# punktum: dialect=dotenvy encoding=utf-16le\nFOO='ä'\n".encode_utf16() {
+        utf16.extend_from_slice(&unit.to_le_bytes());
+    }
+    let (res, _) = load(true, &utf16);
+    assert_eq!(get(&res?, "FOO"), Some("ä"));
+
+    Ok(())
+}
+
+#[test]
+fn test_modeline_plain_comment() -> Result<()> {
+    let (res, diagnostics) = load(true, b"# punktum: config for dev\nFOO=bar\n");
+    assert_eq!(get(&res?, "FOO"), Some("bar"));
+    assert!(diagnostics.is_empty(), "{diagnostics:?}");
+
+    Ok(())
+}
+
+#[test]
+fn test_modeline_illegal() -> Result<()> {
+    let src = b"# punktum: dialect=nope\nFOO=bar\n";
+
+    let (res, diagnostics) = load(true, src);
+    assert_eq!(res.err().map(|err| err.kind()), Some(ErrorKind::OptionsParseError));
+    assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+    assert_eq!(diagnostics[0].severity(), Severity::Error);
+
+    let (res, diagnostics) = load(false, src);
+    assert_eq!(get(&res?, "FOO"), Some("bar"));
+    assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+    assert_eq!(diagnostics[0].severity(), Severity::Warning);
+
+    Ok(())
+}
+
+#[test]
+fn test_modeline_only_first_line() -> Result<()> {
+    let (res, _) = load(true, b"FOO=bar\n# punktum: dialect=nope\n");
+    assert_eq!(get(&res?, "FOO"), Some("bar"));
+    Ok(())
+}
@@ -0,0 +1,102 @@
+#![cfg(unix)]
+
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::io::Cursor;
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::sync::{Arc, Mutex};
+
+use punktum::{self, build, Dialect, ErrorKind, Result};
+
+fn legacy() -> HashMap<OsString, OsString> {
+    let mut env = HashMap::new();
+    env.insert(OsString::from("LEGACY"), OsString::from_vec(b"/opt/caf\xe9".to_vec()));
+    env
+}
+
+#[test]
+fn test_os_str_substitution() -> Result<()> {
+    let src = "A=$LEGACY/bin\nB=\"${LEGACY:-x}\"\nC=${MISSING:-$LEGACY}\n";
+    let diagnostics = Arc::new(Mutex::new(Vec::new()));
+    let mut env = legacy();
+    build().
+        strict(true).
+        diagnostics(diagnostics.clone()).
+        config_with_reader_and_parent(Cursor::new(src), &mut env, &legacy())?;
+
+    assert_eq!(env.get(OsStr::new("A")).map(|value| value.as_bytes()), Some(&b"/opt/caf\xe9/bin"[..]));
+    assert_eq!(env.get(OsStr::new("B")).map(|value| value.as_bytes()), Some(&b"/opt/caf\xe9"[..]));
+    assert_eq!(env.get(OsStr::new("C")).map(|value| value.as_bytes()), Some(&b"/opt/caf\xe9"[..]));
+    assert!(diagnostics.lock().unwrap().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_os_str_binary() -> Result<()> {
+    let mut env = legacy();
+    env.insert(OsString::from_vec(b"K\xff".to_vec()), OsString::from("it's"));
+
+    let mut buf = Vec::new();
+    for (key, value) in &env {
+        punktum::write_var_binary(&mut buf, key, value)?;
+    }
+
+    let read = build().
+        dialect(Dialect::Binary).
+        strict(true).
+        config_new_with_reader_and_parent(Cursor::new(&buf), &HashMap::<OsString, OsString>::new())?;
+    assert_eq!(read, env);
+
+    Ok(())
+}
+
+#[test]
+fn test_os_str_write() -> Result<()> {
+    let mut buf = Vec::new();
+    punktum::write_var(&mut buf, "LEGACY", OsStr::from_bytes(b"caf\xe9's"))?;
+    assert_eq!(buf, b"LEGACY='caf\xe9'\"'\"'s'\n");
+
+    // systemd ignores values that aren't valid UTF-8
+    buf.clear();
+    let err = punktum::write_var_systemd(&mut buf, "LEGACY", OsStr::from_bytes(b"$caf\xe9")).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::Unrepresentable);
+    assert!(buf.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_os_str_emulated_dialects() -> Result<()> {
+    let dialects = [
+        (Dialect::ComposeGo, "A=$LEGACY/bin\n"),
+        (Dialect::GoDotenv, "A=\"$LEGACY/bin\"\n"),
+        (Dialect::RubyDotenv, "A=$LEGACY/bin\n"),
+        (Dialect::PythonDotenv, "A=${LEGACY}/bin\n"),
+        (Dialect::PhpDotenv, "A=${LEGACY}/bin\n"),
+        (Dialect::PosixShell, "A=$LEGACY/bin\n"),
+        (Dialect::DotenvExpand, "A=$LEGACY/bin\n"),
+    ];
+
+    for (dialect, src) in dialects {
+        let mut env = legacy();
+        let error = build().
+            dialect(dialect).
+            strict(true).
+            config_with_reader_and_parent(Cursor::new(src), &mut env, &legacy());
+        assert!(error.is_err(), "{dialect}: expected an error");
+
+        let diagnostics = Arc::new(Mutex::new(Vec::new()));
+        let mut env = legacy();
+        build().
+            dialect(dialect).
+            strict(false).
+            diagnostics(diagnostics.clone()).
+            config_with_reader_and_parent(Cursor::new(src), &mut env, &legacy())?;
+
+        assert_eq!(env.get(OsStr::new("A")).map(|value| value.as_bytes()), Some("/opt/caf\u{FFFD}/bin".as_bytes()), "{dialect}");
+        assert_eq!(diagnostics.lock().unwrap().len(), 1, "{dialect}");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::io::Cursor;
+
+use punktum::{self, build, Dialect, Entry, Result};
+
+fn parse(dialect: Dialect, src: &str) -> Result<Vec<Entry>> {
+    let parent: HashMap<OsString, OsString> = [
+        ("HOME".into(), "/home/user".into()),
+    ].into_iter().collect();
+
+    build().
+        dialect(dialect).
+        parse_with_parent(Cursor::new(src.as_bytes()), &parent).
+        collect()
+}
+
+#[test]
+fn test_parse_all_dialects() -> Result<()> {
+    for dialect in [
+        Dialect::Punktum,
+        Dialect::NodeJS,
+        Dialect::NodeJS20,
+        Dialect::NodeJS24,
+        Dialect::JavaScriptDotenv,
+        Dialect::PythonDotenv,
+        Dialect::PythonDotenvCLI,
+        Dialect::ComposeGo,
+        Dialect::GoDotenv,
+        Dialect::RubyDotenv,
+        Dialect::PhpDotenv,
+        Dialect::JavaDotenv,
+        Dialect::JavaProperties,
+    ] {
+        let entries = parse(dialect, "A=1\n  B='two'\n")?;
+        assert_eq!(entries.len(), 2, "{dialect}: {entries:?}");
+
+        assert_eq!(entries[0].key(), "A", "{dialect}");
+        assert_eq!(entries[0].value(), "1", "{dialect}");
+        assert_eq!((entries[0].location().lineno(), entries[0].location().column()), (1, 1), "{dialect}");
+        assert!(!entries[0].is_quoted(), "{dialect}");
+
+        assert_eq!(entries[1].key(), "B", "{dialect}");
+        assert_eq!((entries[1].location().lineno(), entries[1].location().column()), (2, 3), "{dialect}");
+        if dialect != Dialect::JavaDotenv && dialect != Dialect::PythonDotenvCLI && dialect != Dialect::JavaProperties {
+            // these don't support single quotes
+            assert_eq!(entries[1].value(), "two", "{dialect}");
+            assert!(entries[1].is_quoted(), "{dialect}");
+        }
+    }
+
+    let entries = parse(Dialect::Binary, "A=1\0B=2\0")?;
+    let keys: Vec<_> = entries.iter().map(Entry::key).collect();
+    assert_eq!(keys, ["A", "B"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_punktum() -> Result<()> {
+    let entries = parse(Dialect::Punktum, "\
+FOO=bar
+FOO=\"${FOO}baz\"
+HOME
+  X=\"a\"b
+")?;
+
+    let values: Vec<_> = entries.iter().map(|entry| (entry.key(), entry.value())).collect();
+    assert_eq!(values, [
+        (OsStr::new("FOO"), OsStr::new("bar")),
+        (OsStr::new("FOO"), OsStr::new("barbaz")),
+        (OsStr::new("HOME"), OsStr::new("/home/user")),
+        (OsStr::new("X"), OsStr::new("ab")),
+    ]);
+
+    assert!(!entries[0].is_substituted());
+    assert!(entries[1].is_quoted());
+    assert!(entries[1].is_substituted());
+    assert!(entries[2].is_substituted());
+    assert!(entries[3].is_quoted());
+    assert!(!entries[3].is_substituted());
+    assert_eq!(entries[3].location().column(), 3);
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_error() {
+    let mut entries = build().
+        dialect(Dialect::Punktum).
+        parse(Cursor::new(b"FOO=bar\nBAR=\"baz\n".as_slice()));
+
+    assert_eq!(entries.next().unwrap().unwrap().key(), "FOO");
+    assert!(entries.next().unwrap().is_err());
+    assert!(entries.next().is_none());
+
+    let entries: Vec<_> = build().
+        dialect(Dialect::Punktum).
+        strict(false).
+        parse(Cursor::new(b"FOO=bar\n-BAR=baz\nBAZ=1\n".as_slice())).
+        collect::<Result<_>>().unwrap();
+
+    let keys: Vec<_> = entries.iter().map(Entry::key).collect();
+    assert_eq!(keys, ["FOO", "BAZ"]);
+}
+
+#[test]
+fn test_parse_lazy() {
+    let src = b"A=1\nB=\"two\nlines\"\nC=3\n";
+
+    for dialect in [Dialect::Punktum, Dialect::Binary] {
+        let src = if dialect == Dialect::Binary { b"A=1\0B=two\nlines\0C=3\0".as_slice() } else { src.as_slice() };
+        let mut reader = Cursor::new(src);
+
+        let mut entries = build().
+            dialect(dialect).
+            parse(&mut reader);
+        assert_eq!(entries.next().unwrap().unwrap().key(), "A", "{dialect}");
+        drop(entries);
+
+        // only what was needed for the first entry was read
+        assert!((reader.position() as usize) < src.len(), "{dialect}");
+    }
+}
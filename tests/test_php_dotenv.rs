@@ -0,0 +1,64 @@
+mod common;
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::io::Cursor;
+
+use punktum::{self, build, Dialect, ErrorKind, Result};
+
+use common::get;
+
+#[test]
+fn test_php_dotenv_unset() -> Result<()> {
+    let mut env = HashMap::new();
+    env.insert(OsString::from("PRE_DEFINED"), OsString::from("not override"));
+
+    build().
+        dialect(Dialect::PhpDotenv).
+        config_with_reader_and_parent(Cursor::new(b"A=1\nA=2\nB=3\nB\nexport PRE_DEFINED\n".as_slice()), &mut env, &HashMap::<OsString, OsString>::new())?;
+
+    // the file may overwrite and unset what it defined itself
+    assert_eq!(get(&env, "A"), Some("2"));
+    assert_eq!(get(&env, "B"), None);
+    assert_eq!(get(&env, "PRE_DEFINED"), Some("not override"));
+
+    build().
+        dialect(Dialect::PhpDotenv).
+        override_env(true).
+        config_with_reader_and_parent(Cursor::new(b"export PRE_DEFINED\n".as_slice()), &mut env, &HashMap::<OsString, OsString>::new())?;
+
+    assert_eq!(get(&env, "PRE_DEFINED"), None);
+
+    Ok(())
+}
+
+#[test]
+fn test_php_dotenv_errors() -> Result<()> {
+    for (src, lineno) in [
+        ("A=1\nB=two words\n", 2),
+        ("A=1\n=2\n", 2),
+        ("A=1\nB C=2\n", 2),
+        ("A=1\nB=\"\\x\"\n", 2),
+        ("A=1\nB='unterminated\n", 2),
+        ("A=1\nB=\"unterminated\nmulti-line\n", 2),
+    ] {
+        let mut env = HashMap::<OsString, OsString>::new();
+        let res = build().
+            dialect(Dialect::PhpDotenv).
+            config_with_reader_and_parent(Cursor::new(src.as_bytes()), &mut env, &HashMap::<OsString, OsString>::new());
+        let err = res.err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::SyntaxError, "{src:?}");
+        assert_eq!(err.location().map(|location| location.lineno()), Some(lineno), "{src:?}");
+        // like phpdotenv nothing is loaded if the file has an error
+        assert!(env.is_empty(), "{src:?}: {env:?}");
+
+        let env = build().
+            dialect(Dialect::PhpDotenv).
+            strict(false).
+            config_new_with_reader(Cursor::new(src.as_bytes()))?;
+        assert_eq!(get(&env, "A"), Some("1"), "{src:?}");
+        assert_eq!(env.len(), 1, "{src:?}: {env:?}");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,143 @@
+mod common;
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+
+use punktum::{self, build, Diagnostic, DiagnosticCode, Dialect, ErrorKind, Result};
+
+use common::get;
+
+fn parent() -> HashMap<OsString, OsString> {
+    let mut parent = HashMap::new();
+    parent.insert(OsString::from("HOME"), OsString::from("/home/user"));
+    parent.insert(OsString::from("FILE"), OsString::from("archive.tar.gz"));
+    parent
+}
+
+#[test]
+fn test_posix_shell_quoting() -> Result<()> {
+    let src = concat!(
+        "# comment\n",
+        "A=plain B='single $A \\'\"double $A \\$ \\\" \\x\" # comment\n",
+        "C=con\\\n",
+        "cat'enated'\"words\"\\ \\#x#y\n",
+        "D=\"multi\n",
+        "line\"; E=\n",
+        "export F=\"$A\" G\n",
+        "H=~/bin:~/lib:x~ I=\"~\"\n",
+    );
+
+    let mut env = HashMap::new();
+    build().
+        dialect(Dialect::PosixShell).
+        config_with_reader_and_parent(Cursor::new(src.as_bytes()), &mut env, &parent())?;
+
+    assert_eq!(get(&env, "A"), Some("plain"));
+    assert_eq!(get(&env, "B"), Some("single $A \\double plain $ \" \\x"));
+    assert_eq!(get(&env, "C"), Some("concatenatedwords #x#y"));
+    assert_eq!(get(&env, "D"), Some("multi\nline"));
+    assert_eq!(get(&env, "E"), Some(""));
+    assert_eq!(get(&env, "F"), Some("plain"));
+    assert_eq!(get(&env, "H"), Some("/home/user/bin:/home/user/lib:x~"));
+    assert_eq!(get(&env, "I"), Some("~"));
+    assert_eq!(env.len(), 8, "{env:?}");
+
+    Ok(())
+}
+
+#[test]
+fn test_posix_shell_parameter_expansion() -> Result<()> {
+    let src = concat!(
+        "EMPTY=\n",
+        "A=${FILE%.*} B=${FILE%%.*} C=${FILE#*.} D=${FILE##*.}\n",
+        "E=${#FILE} F=${UNSET:-default} G=${EMPTY-not used} H=${EMPTY:+alt}${FILE+alt}\n",
+        "I=${ASSIGNED:=\"$A\"} J=$ASSIGNED\n",
+        "K=\"${FILE#\"archive\"}\" L=${FILE#[a-z]*[!a-z.]} M=${FILE%'.*'}\n",
+        "N=$\\ $UNSET.\\ \"${FILE%.[[:alpha:]][[:alpha:]]}\"\n",
+    );
+
+    let mut env = HashMap::new();
+    build().
+        dialect(Dialect::PosixShell).
+        config_with_reader_and_parent(Cursor::new(src.as_bytes()), &mut env, &parent())?;
+
+    assert_eq!(get(&env, "A"), Some("archive.tar"));
+    assert_eq!(get(&env, "B"), Some("archive"));
+    assert_eq!(get(&env, "C"), Some("tar.gz"));
+    assert_eq!(get(&env, "D"), Some("gz"));
+    assert_eq!(get(&env, "E"), Some("14"));
+    assert_eq!(get(&env, "F"), Some("default"));
+    assert_eq!(get(&env, "G"), Some(""));
+    assert_eq!(get(&env, "H"), Some("alt"));
+    assert_eq!(get(&env, "I"), Some("archive.tar"));
+    assert_eq!(get(&env, "ASSIGNED"), Some("archive.tar"));
+    assert_eq!(get(&env, "J"), Some("archive.tar"));
+    assert_eq!(get(&env, "K"), Some(".tar.gz"));
+    assert_eq!(get(&env, "L"), Some("archive.tar.gz"));
+    assert_eq!(get(&env, "M"), Some("archive.tar.gz"));
+    assert_eq!(get(&env, "N"), Some("$ . archive.tar"));
+
+    Ok(())
+}
+
+#[test]
+fn test_posix_shell_refused() -> Result<()> {
+    let cases = [
+        ("A=$(id -u)", ErrorKind::SubstitutionError, DiagnosticCode::CommandSubstitution, 3),
+        ("A=\"`id -u`\"", ErrorKind::SubstitutionError, DiagnosticCode::CommandSubstitution, 4),
+        ("A=$((1 + 2))", ErrorKind::SubstitutionError, DiagnosticCode::SubstitutionError, 3),
+        ("A=$0", ErrorKind::SubstitutionError, DiagnosticCode::SubstitutionError, 3),
+        ("A=${UNSET:?is required}", ErrorKind::SubstitutionError, DiagnosticCode::SubstitutionError, 3),
+        ("A=~root", ErrorKind::SubstitutionError, DiagnosticCode::SubstitutionError, 3),
+        ("A=${FILE/a/b}", ErrorKind::SyntaxError, DiagnosticCode::SyntaxError, 3),
+        ("A=1 some-command", ErrorKind::SyntaxError, DiagnosticCode::SyntaxError, 5),
+        ("A=1 && B=2", ErrorKind::SyntaxError, DiagnosticCode::SyntaxError, 5),
+        ("A='unterminated", ErrorKind::SyntaxError, DiagnosticCode::UnterminatedString, 3),
+    ];
+
+    for (line, kind, code, column) in cases {
+        let src = format!("X=1\n{line}\nY=2\n");
+
+        let res = build().
+            dialect(Dialect::PosixShell).
+            config_with_reader_and_parent(Cursor::new(src.as_bytes()), &mut HashMap::<OsString, OsString>::new(), &parent());
+        let err = res.err().unwrap();
+        assert_eq!(err.kind(), kind, "{line}");
+        assert_eq!(err.location().map(|location| (location.lineno(), location.column())), Some((2, column)), "{line}");
+
+        let diagnostics = Arc::new(Mutex::new(Vec::<Diagnostic>::new()));
+        let mut env = HashMap::new();
+        build().
+            dialect(Dialect::PosixShell).
+            strict(false).
+            diagnostics(diagnostics.clone()).
+            config_with_reader_and_parent(Cursor::new(src.as_bytes()), &mut env, &parent())?;
+
+        assert_eq!(get(&env, "X"), Some("1"), "{line}");
+        assert_eq!(get(&env, "Y"), Some("2"), "{line}");
+        assert_eq!(env.len(), 2, "{line}: {env:?}");
+
+        let diagnostics = diagnostics.lock().unwrap();
+        let codes: Vec<_> = diagnostics.iter().map(|diagnostic| diagnostic.code()).collect();
+        assert_eq!(codes, [code], "{line}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_posix_shell_no_override() -> Result<()> {
+    let mut env = HashMap::new();
+    env.insert(OsString::from("A"), OsString::from("existing"));
+
+    build().
+        dialect(Dialect::PosixShell).
+        config_with_reader_and_parent(Cursor::new(b"A=new\nB=$A\n".as_slice()), &mut env, &parent())?;
+
+    assert_eq!(get(&env, "A"), Some("existing"));
+    assert_eq!(get(&env, "B"), Some("existing"));
+
+    Ok(())
+}
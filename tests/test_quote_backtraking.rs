@@ -52,6 +52,18 @@ fn test_quote_backtracking_nodejs() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_quote_backtracking_nodejs20() -> Result<()> {
+    assert_quote_backtracking!(quote_backtracking::nodejs20::FIXTURE, Dialect::NodeJS20);
+    Ok(())
+}
+
+#[test]
+fn test_quote_backtracking_nodejs24() -> Result<()> {
+    assert_quote_backtracking!(quote_backtracking::nodejs24::FIXTURE, Dialect::NodeJS24);
+    Ok(())
+}
+
 #[test]
 fn test_quote_backtracking_punktum() -> Result<()> {
     assert_quote_backtracking!(quote_backtracking::punktum::FIXTURE, Dialect::Punktum);
@@ -0,0 +1,84 @@
+mod common;
+
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::path::Path;
+
+use punktum::{self, search::find_upward, ErrorKind, Search};
+
+use common::TempDir;
+
+fn make_dir(name: &str) -> TempDir {
+    let dir = TempDir::new(name, &[]);
+    std::fs::create_dir_all(dir.join("repo/crates/sub/src")).unwrap();
+    // don't find anything outside of the test directory
+    std::fs::create_dir(dir.join(".hg")).unwrap();
+    dir
+}
+
+#[test]
+fn test_search_upward() {
+    let dir = make_dir("upward");
+    let repo = dir.join("repo");
+    let sub = repo.join("crates/sub/src");
+    let env = Path::new(".env");
+
+    assert_eq!(find_upward(&sub, env, None), None);
+
+    std::fs::write(repo.join(".env"), "A=1\n").unwrap();
+    assert_eq!(find_upward(&sub, env, None), Some(repo.join(".env")));
+
+    // the nearest file wins
+    std::fs::write(repo.join("crates/sub/.env"), "A=2\n").unwrap();
+    assert_eq!(find_upward(&sub, env, None), Some(repo.join("crates/sub/.env")));
+
+    // relative paths with directories work too
+    std::fs::create_dir(repo.join("config")).unwrap();
+    std::fs::write(repo.join("config/.env"), "A=3\n").unwrap();
+    assert_eq!(find_upward(&sub, Path::new("config/.env"), None), Some(repo.join("config/.env")));
+}
+
+#[test]
+fn test_search_root_marker() {
+    let dir = make_dir("root-marker");
+    let repo = dir.join("repo");
+    let sub = repo.join("crates/sub/src");
+
+    // outside of the repository
+    std::fs::write(dir.join(".env"), "A=1\n").unwrap();
+    assert_eq!(find_upward(&sub, Path::new(".env"), None), Some(dir.join(".env")));
+
+    std::fs::create_dir(repo.join(".git")).unwrap();
+    assert_eq!(find_upward(&sub, Path::new(".env"), None), None);
+
+    // the repository root itself is still searched
+    std::fs::write(repo.join(".env"), "A=2\n").unwrap();
+    assert_eq!(find_upward(&sub, Path::new(".env"), None), Some(repo.join(".env")));
+}
+
+#[test]
+fn test_search_mode() {
+    let dir = make_dir("mode");
+    let repo = dir.join("repo");
+    let sub = repo.join("crates/sub/src");
+
+    std::fs::write(repo.join(".env.development.local"), "A=1\n").unwrap();
+    assert_eq!(find_upward(&sub, Path::new(".env"), None), None);
+    assert_eq!(find_upward(&sub, Path::new(".env"), Some(OsStr::new("development"))), Some(repo.join(".env")));
+    assert_eq!(find_upward(&sub, Path::new(".env"), Some(OsStr::new("test"))), None);
+}
+
+#[test]
+fn test_search_option() -> punktum::Result<()> {
+    let mut env = HashMap::new();
+    assert_eq!(punktum::build_from(&env)?.options().search, Search::Off);
+
+    env.insert(OsString::from("DOTENV_CONFIG_SEARCH"), OsString::from("Upward"));
+    assert_eq!(punktum::build_from(&env)?.options().search, Search::Upward);
+
+    env.insert(OsString::from("DOTENV_CONFIG_SEARCH"), OsString::from("sideways"));
+    let err = punktum::build_from(&env).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::OptionsParseError);
+
+    Ok(())
+}
@@ -0,0 +1,103 @@
+mod common;
+
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+
+use punktum::{self, build, Diagnostic, DiagnosticCode, Dialect, ErrorKind, Result};
+
+use common::get;
+
+#[test]
+fn test_systemd_syntax() -> Result<()> {
+    let src = concat!(
+        "# comment\n",
+        "; also a comment \\\n",
+        "A=1\n",
+        "B=\"x \\\"y\\\" \\$z \\n \\\\ end\"\n",
+        "C=multi\\\n",
+        "  line  \n",
+        "D= foo # bar  \r\n",
+        "E=\"a\" 'b' c\n",
+        "F='single\n",
+        "quoted \\n'\n",
+        "  G  =\n",
+        "H=\"unterminated",
+    );
+
+    let env = build().
+        dialect(Dialect::Systemd).
+        strict(false).
+        config_new_with_reader(Cursor::new(src.as_bytes()))?;
+
+    assert_eq!(get(&env, "A"), Some("1"));
+    assert_eq!(get(&env, "B"), Some("x \"y\" $z \\n \\ end"));
+    assert_eq!(get(&env, "C"), Some("multi  line"));
+    assert_eq!(get(&env, "D"), Some("foo # bar"));
+    assert_eq!(get(&env, "E"), Some("abc"));
+    assert_eq!(get(&env, "F"), Some("single\nquoted \\n"));
+    assert_eq!(get(&env, "G"), Some(""));
+    assert_eq!(get(&env, "H"), Some("unterminated"));
+    assert_eq!(env.len(), 8, "{env:?}");
+
+    let res = build().
+        dialect(Dialect::Systemd).
+        config_new_with_reader(Cursor::new(src.as_bytes()));
+    assert_eq!(res.err().map(|err| err.kind()), Some(ErrorKind::SyntaxError));
+
+    Ok(())
+}
+
+#[test]
+fn test_systemd_invalid() -> Result<()> {
+    let src = "export A=1\nB.C=2\n1D=3\nE\nF=4\n";
+
+    let diagnostics = Arc::new(Mutex::new(Vec::<Diagnostic>::new()));
+    let env = build().
+        dialect(Dialect::Systemd).
+        strict(false).
+        diagnostics(diagnostics.clone()).
+        config_new_with_reader(Cursor::new(src.as_bytes()))?;
+
+    assert_eq!(get(&env, "F"), Some("4"));
+    assert_eq!(env.len(), 1, "{env:?}");
+
+    let diagnostics = diagnostics.lock().unwrap();
+    let lines: Vec<_> = diagnostics.iter().map(|diagnostic| diagnostic.lineno()).collect();
+    assert_eq!(lines, [Some(1), Some(2), Some(3), Some(4)], "{diagnostics:?}");
+    assert!(diagnostics.iter().all(|diagnostic| diagnostic.code() == DiagnosticCode::SyntaxError));
+
+    let res = build().
+        dialect(Dialect::Systemd).
+        config_new_with_reader(Cursor::new(src.as_bytes()));
+    let err = res.err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::SyntaxError);
+    assert_eq!(err.location().map(|location| location.lineno()), Some(1));
+
+    Ok(())
+}
+
+#[test]
+fn test_systemd_write() -> Result<()> {
+    let vars = [
+        ("A", "plain"),
+        ("B", "\"quotes\" 'and' `backticks`"),
+        ("C", "$VAR ${VAR} \\ \\n"),
+        ("D", "multi\nline\r\n"),
+        ("E", "  spaces  "),
+        ("F", ""),
+    ];
+
+    let mut buf = Vec::new();
+    punktum::write_iter_systemd(&mut buf, vars.iter().copied())?;
+
+    let env = build().
+        dialect(Dialect::Systemd).
+        config_new_with_reader(Cursor::new(buf))?;
+
+    for (key, value) in vars {
+        assert_eq!(get(&env, key), Some(value), "{key}");
+    }
+    assert_eq!(env.len(), vars.len());
+
+    Ok(())
+}
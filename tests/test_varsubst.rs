@@ -44,7 +44,7 @@ fn test_composego_invalid1() {
 FOO=\"${BAR:-${BAZ}
 }\"
 "));
-    assert_eq!(res.is_err(), true);
+    assert!(res.is_err());
 }
 
 #[test]
@@ -55,7 +55,7 @@ fn test_composego_invalid2() {
 FOO=\"${BAR:-
 }\"
 "));
-    assert_eq!(res.is_err(), true);
+    assert!(res.is_err());
 }
 
 #[test]
@@ -66,7 +66,7 @@ fn test_composego_invalid3() {
 EMPTY=
 FOO=\"${EMPTY:?message}\"
 "));
-    assert_eq!(res.is_err(), true);
+    assert!(res.is_err());
 }
 
 #[test]
@@ -76,7 +76,7 @@ fn test_composego_invalid4() {
         config_new_with_reader(Cursor::new(b"
 FOO=\"${BAR?message}\"
 "));
-    assert_eq!(res.is_err(), true);
+    assert!(res.is_err());
 }
 
 #[test]
@@ -85,6 +85,34 @@ fn test_varsubst_composego() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_dotenvy_invalid1() {
+    // the closing quote after an unbraced variable is taken as part of the value
+    let res = build().
+        dialect(Dialect::Dotenvy).
+        config_new_with_reader(Cursor::new(b"
+FOO=bar
+BAR=\"$FOO\"
+"));
+    assert!(res.is_err());
+}
+
+#[test]
+fn test_dotenvy_invalid2() {
+    let res = build().
+        dialect(Dialect::Dotenvy).
+        config_new_with_reader(Cursor::new(b"
+FOO=\"${BAR\"
+"));
+    assert!(res.is_err());
+}
+
+#[test]
+fn test_varsubst_dotenvy() -> Result<()> {
+    assert_varsubst!(varsubst::dotenvy::FIXTURE, Dialect::Dotenvy);
+    Ok(())
+}
+
 #[test]
 fn test_punktum_invalid1() {
     let res = build().
@@ -93,7 +121,7 @@ fn test_punktum_invalid1() {
 EMPTY=
 FOO=\"${EMPTY:?message}\"
 "));
-    assert_eq!(res.is_err(), true);
+    assert!(res.is_err());
 }
 
 #[test]
@@ -103,7 +131,7 @@ fn test_punktum_invalid2() {
         config_new_with_reader(Cursor::new(b"
 FOO=\"${BAR?message}\"
 "));
-    assert_eq!(res.is_err(), true);
+    assert!(res.is_err());
 }
 
 #[test]
@@ -123,3 +151,9 @@ fn test_varsubst_ruby() -> Result<()> {
     assert_varsubst!(varsubst::ruby::FIXTURE, Dialect::RubyDotenv);
     Ok(())
 }
+
+#[test]
+fn test_varsubst_phpdotenv() -> Result<()> {
+    assert_varsubst!(varsubst::phpdotenv::FIXTURE, Dialect::PhpDotenv);
+    Ok(())
+}
@@ -0,0 +1,158 @@
+mod common;
+
+use std::io::Cursor;
+
+use punktum::{self, build, env::EmptyEnv, Dialect, ErrorKind, KeyMapping, Result, Writer};
+
+use common::get;
+
+const VALUES: &[&str] = &[
+    "",
+    "plain",
+    "http://example.com/path?query=1&b=2",
+    "  spaces  ",
+    "\ttab",
+    "# not a comment",
+    "value # not a comment",
+    "single 'quotes'",
+    "double \"quotes\"",
+    "`backticks`",
+    "'\"`",
+    "\"fully quoted\"",
+    "'fully quoted'",
+    "$VAR ${VAR} $(command) \\$VAR",
+    "${VAR:-$OTHER} \\${VAR:-x}",
+    "$",
+    "back\\slash",
+    "ends with backslash\\",
+    "\\n \\r \\t \\\\ \\u0041",
+    "multi\nline",
+    "\nsurrounding newlines\n",
+    "dos\r\nline",
+    "\r",
+    "=equals=",
+    "ümlaut € 🦀",
+    "export X=1",
+    "'",
+    "\"",
+    "{}[]()<>|&;*?!~^",
+];
+
+/// Writes every value it can and returns the file and the written values.
+fn write_all(dialect: Dialect) -> (Vec<u8>, Vec<(String, &'static str)>) {
+    let mut writer = Writer::new(Vec::new(), dialect);
+    let mut written = Vec::new();
+
+    for (index, value) in VALUES.iter().enumerate() {
+        let key = format!("VAR_{index}");
+        match writer.write_var(&key, value) {
+            Ok(()) => written.push((key, *value)),
+            Err(err) => assert_eq!(err.kind(), ErrorKind::Unrepresentable, "{dialect} {value:?}: {err}"),
+        }
+    }
+
+    (writer.into_inner(), written)
+}
+
+#[test]
+fn test_write_round_trip() -> Result<()> {
+    for dialect in Dialect::all() {
+        let (buf, written) = write_all(dialect);
+
+        let env = build().
+            dialect(dialect).
+            strict(true).
+            key_mapping(KeyMapping::Keep).
+            config_new_with_reader_and_parent(Cursor::new(&buf), &EmptyEnv())?;
+
+        let src = String::from_utf8_lossy(&buf);
+        for (key, value) in &written {
+            assert_eq!(get(&env, key), Some(*value), "{dialect} {key}:\n{src}");
+        }
+        assert_eq!(env.len(), written.len(), "{dialect}:\n{src}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_write_representable() -> Result<()> {
+    let always = ["", "plain", "  spaces  ", "# not a comment", "single 'quotes'", "double \"quotes\"", "ümlaut € 🦀"];
+
+    for dialect in Dialect::all() {
+        for value in always {
+            let mut writer = Writer::new(Vec::new(), dialect);
+            writer.write_var("KEY", value)?;
+        }
+    }
+
+    for dialect in [
+        Dialect::Punktum,
+        Dialect::PythonDotenvCLI,
+        Dialect::ComposeGo,
+        Dialect::PhpDotenv,
+        Dialect::Dotenvy,
+        Dialect::Systemd,
+        Dialect::PosixShell,
+        Dialect::JavaProperties,
+        Dialect::Binary,
+    ] {
+        for value in VALUES {
+            let mut writer = Writer::new(Vec::new(), dialect);
+            writer.write_var("KEY", value)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_write_unrepresentable() {
+    let cases = [
+        (Dialect::NodeJS, "KEY", "dos\r\nline"),
+        (Dialect::NodeJS20, "KEY", "'\"` \\n"),
+        (Dialect::JavaDotenv, "KEY", "multi\nline"),
+        (Dialect::JavaDotenv, "KEY", "# \"quotes\""),
+        (Dialect::DockerCLI, "KEY", "multi\nline"),
+        (Dialect::PythonDotenv, "KEY", "${VAR}"),
+        (Dialect::Dotenvx, "KEY", "$(command)"),
+        (Dialect::RubyDotenv, "KEY", "\r"),
+        (Dialect::Punktum, "KEY", "null\0byte"),
+        (Dialect::Binary, "KEY", "null\0byte"),
+        (Dialect::Punktum, "KEY.NAME", "value"),
+        (Dialect::Systemd, "1KEY", "value"),
+        (Dialect::DockerCLI, "KEY NAME", "value"),
+        (Dialect::Binary, "KEY=NAME", "value"),
+    ];
+
+    for (dialect, key, value) in cases {
+        let mut writer = Writer::new(Vec::new(), dialect);
+        let res = writer.write_var(key, value);
+        assert_eq!(res.err().map(|err| err.kind()), Some(ErrorKind::Unrepresentable), "{dialect} {key:?}={value:?}");
+        assert!(writer.get_ref().is_empty(), "{dialect} {key:?}={value:?}");
+    }
+}
+
+#[test]
+fn test_write_minimal_quoting() -> Result<()> {
+    let cases = [
+        (Dialect::Punktum,          "a b",      "KEY='a b'\n"),
+        (Dialect::Punktum,          "it's",     "KEY=\"it's\"\n"),
+        (Dialect::NodeJS,           "it's",     "KEY=`it's`\n"),
+        (Dialect::PythonDotenvCLI,  "a\nb",     "KEY=\"a\\nb\"\n"),
+        (Dialect::ComposeGo,        "$it's",    "KEY=\"$$it's\"\n"),
+        (Dialect::PhpDotenv,        "a $b",     "KEY=\"a \\$b\"\n"),
+        (Dialect::Systemd,          "plain",    "KEY=plain\n"),
+        (Dialect::PosixShell,       "it's",     "KEY='it'\\''s'\n"),
+        (Dialect::JavaProperties,   " a=b",     "KEY=\\ a=b\n"),
+        (Dialect::Binary,           "a\nb",     "KEY=a\nb\0"),
+    ];
+
+    for (dialect, value, expected) in cases {
+        let mut writer = Writer::new(Vec::new(), dialect);
+        writer.write_var("KEY", value)?;
+        assert_eq!(String::from_utf8_lossy(writer.get_ref()), expected, "{dialect}");
+    }
+
+    Ok(())
+}
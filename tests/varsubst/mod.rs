@@ -1,4 +1,6 @@
 pub mod composego;
+pub mod dotenvy;
+pub mod phpdotenv;
 pub mod punktum;
 pub mod python;
 pub mod ruby;
@@ -0,0 +1,9 @@
+pub const FIXTURE: &[(&str, &str)] = &[
+    ("VAR1", "${UNSET:-{{}\n}"),
+    ("VAR2", "${UNSET:-{baz}\nbla}"),
+    ("VAR3", "$UNSET:-TEXT"),
+    ("VAR4", "$VAR3:-TEXT"),
+    ("VAR5", "$VAR3$VAR4"),
+    ("VAR6", "\nVAR4=$VAR4\nVAR5=$VAR5\n"),
+    ("VAR7", "$VAR3$VAR4"),
+];